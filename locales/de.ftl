@@ -0,0 +1,250 @@
+date-format = %d.%m.%Y
+
+status-loading = lädt { $percent }%
+status-unsaved = nicht gespeichert, neuer Versuch in { $seconds }s oder w
+status-read-only = schreibgeschützt
+view-waiting = wartet auf
+view-someday = irgendwann
+view-all = alle Aufgaben
+view-columns = Spalten
+pomodoro-focus = Fokus
+pomodoro-break = Pause
+
+mode-normal = NORMAL
+mode-insert = EINFÜGEN
+mode-edit = BEARBEITEN
+mode-search = SUCHE
+mode-focus = FOKUS
+mode-stats = STATISTIK
+mode-errors = FEHLER
+mode-conflict = KONFLIKT
+mode-confirm = BESTÄTIGEN
+mode-review = DURCHSICHT
+mode-command = BEFEHL
+mode-batch = ÄNDERUNGEN
+mode-sidebar = SCHLAGWÖRTER
+
+too-small = Terminal zu klein
+too-small-size = { $width }x{ $height } (mindestens { $min-width }x{ $min-height })
+
+title-progress = { $done }/{ $total } erledigt · { $percent }%
+title-syncing = synchronisiere…
+title-synced = synchronisiert um { $time }
+title-sync-failed = Synchronisierung fehlgeschlagen
+
+search-only-matches = nur Treffer
+
+startup-title = Aufgabendatei
+startup-missing = existiert noch nicht
+startup-unreadable = kann nicht gelesen werden
+startup-read-only = kann nicht geschrieben werden
+startup-create = anlegen
+startup-open = andere Datei öffnen
+startup-open-read-only = schreibgeschützt öffnen, nichts wird gespeichert
+startup-quit = beenden
+startup-path = Pfad:
+
+errors-title = Fehler
+errors-none = Bisher ist nichts schiefgegangen
+
+detail-every = alle { $interval }
+detail-tracked = { $duration } erfasst
+detail-created = erstellt am { $date }
+detail-streak = { $days ->
+    [one] Serie von { $days } Tag
+   *[other] Serie von { $days } Tagen
+}
+detail-completions = an { $count } der letzten { $days } Tage erledigt
+
+list-due = fällig am { $date }
+list-overdue = überfällig seit { $date }
+list-running = { $duration } laufend
+list-priority = Priorität { $priority }
+list-progress = { $done } von { $total } Unteraufgaben erledigt
+list-stale = liegen geblieben
+list-scheduled = beginnt am { $date }
+list-waiting = wartet
+list-someday = irgendwann
+column-priority = Prio
+column-due = fällig
+column-tags = Tags
+column-task = Aufgabe
+
+confirm-yes = [y] ja
+confirm-no = [n] nein
+confirm-cancel = [esc] abbrechen
+confirm-delete = „{ $task }“ löschen?
+confirm-purge = { $count ->
+    [one] Die erledigte Aufgabe entfernen?
+   *[other] { $count } erledigte Aufgaben entfernen?
+}
+confirm-quit = Änderungen an { $file } sind nicht gespeichert. Vor dem Beenden speichern?
+confirm-overwrite = { $file } wurde von einem anderen Programm geändert. Überschreiben? Nein lädt die geänderte Datei.
+duplicate-hint = Schon offen: „{ $task }“. Enter fügt sie trotzdem hinzu, Tab springt hin.
+
+review-title = Liegen gebliebene Aufgabe { $number } von { $total }
+review-untouched = seit { $days ->
+    [one] einem Tag
+   *[other] { $days } Tagen
+} unberührt
+review-keep = behalten
+review-tomorrow = morgen
+review-next-week = nächste Woche
+review-done = erledigt
+review-drop = verwerfen
+review-skip = überspringen
+
+batch-title = { $command } ändert { $count ->
+    [one] eine Aufgabe
+   *[other] { $count } Aufgaben
+}
+batch-keys = y anwenden  n abbrechen  j/k blättern
+
+sidebar-title = Schlagwörter
+sidebar-all = alle Aufgaben
+
+conflict-title = Sync-Konflikt (noch { $count })
+conflict-base = Letzter Sync
+conflict-local = Lokal
+conflict-remote = Entfernt
+conflict-new = (neu)
+conflict-state = Status
+conflict-due = fällig
+conflict-priority = Prio
+conflict-done = erledigt
+conflict-open = offen
+conflict-keep-local = lokal behalten
+conflict-keep-remote = entfernt behalten
+conflict-keep-both = beide behalten
+conflict-later = später entscheiden
+
+stats-title = Statistik
+stats-completed-today = Heute erledigt
+stats-completed-week = Diese Woche erledigt
+stats-completed-total = Insgesamt erledigt
+stats-added-total = Insgesamt hinzugefügt
+stats-tracked = Erfasste Zeit
+
+debug-title = Debug
+debug-frame = Frame
+debug-last-action = letzte Aktion
+debug-events = Ereignisse
+
+announce-mode-normal = Aufgabenliste
+announce-mode-edit = Aufgabe bearbeiten
+announce-mode-create = neue Aufgabe
+announce-mode-stats = Statistik
+announce-mode-focus = Fokus
+announce-mode-conflict = Sync-Konflikt
+announce-mode-errors = Fehler
+announce-mode-search = Suche
+announce-mode-confirm = Frage
+announce-mode-review = Durchsicht
+announce-mode-command = Befehl
+announce-mode-batch = Änderungen bestätigen
+announce-mode-sidebar = Schlagwörter
+announce-end-of-list = Ende der Liste
+announce-start-of-list = Anfang der Liste
+announce-matches = { $count ->
+    [one] 1 passende Aufgabe
+   *[other] { $count } passende Aufgaben
+}
+announce-only-matches = nur passende Aufgaben
+announce-all-tasks = alle Aufgaben
+announce-columns = Spalten
+announce-one-line = eine Zeile pro Aufgabe
+announce-tag = { $count } offen mit { $tag }
+announce-already-open = schon offen: { $task }
+announce-edit-empty = kein Text übrig, die Änderung wird verworfen
+announce-edited = bearbeitet: { $task }
+announce-review-finished = Durchsicht beendet
+announce-reloaded = { $file } neu geladen
+announce-done = erledigt: { $task }
+announce-open = offen: { $task }
+announce-highest-priority = schon die höchste Priorität
+announce-lowest-priority = keine Priorität zum Senken
+announce-priority = Priorität { $priority }: { $task }
+announce-no-priority = keine Priorität: { $task }
+announce-due = fällig am { $date }: { $task }
+announce-no-due = kein Fälligkeitsdatum: { $task }
+announce-deleted = gelöscht: { $task }
+announce-purged = { $count ->
+    [one] erledigte Aufgabe entfernt
+   *[other] { $count } erledigte Aufgaben entfernt
+}
+announce-tracking = Zeiterfassung: { $task }
+announce-stopped-tracking = Zeiterfassung beendet: { $task }
+announce-pomodoro-started = Pomodoro gestartet: { $task }
+announce-pomodoro-stopped = Pomodoro beendet
+announce-added = hinzugefügt: { $task }
+announce-added-many = { $count } Aufgaben hinzugefügt
+
+toast-still-loading = lädt noch, gleich noch einmal versuchen
+toast-edit-dropped = die Aufgabe hat sich inzwischen geändert, die Änderung wird verworfen
+toast-no-task = die Aufgabe { $id } gibt es nicht mehr
+toast-no-stale = keine liegen gebliebenen Aufgaben
+toast-not-saved-yet = Änderungen sind noch nicht gespeichert, danach noch einmal versuchen
+toast-plugin-failed = Plugin fehlgeschlagen: { $error }
+toast-moved = verschoben nach { $bucket }: { $task }
+toast-active-again = wieder aktiv: { $task }
+toast-nothing-to-purge = keine erledigten Aufgaben zum Entfernen
+toast-sync-not-saved = Änderungen werden hier nicht gespeichert, also auch nicht synchronisiert
+toast-pulling-issues = Issues werden geholt…
+toast-issues-changed = { $count ->
+    [one] 1 Issue geändert
+   *[other] { $count } Issues geändert
+}
+toast-pulling-issues-failed = Issues holen fehlgeschlagen: { $error }
+toast-syncing = synchronisiert…
+toast-synced = synchronisiert: { $summary }
+toast-sync-failed = Synchronisieren fehlgeschlagen: { $error }
+toast-conflicts-deferred = { $count ->
+    [one] 1 Konflikt für später, S zeigt ihn
+   *[other] { $count } Konflikte für später, S zeigt sie
+}
+toast-issue-title = Titel von Issues werden auf GitHub bearbeitet
+toast-saved = gespeichert
+toast-save-failed = Speichern fehlgeschlagen: { $error }
+toast-loaded = { $count } Aufgaben geladen
+toast-reading-stopped = Lesen abgebrochen: { $error }, Änderungen werden nicht gespeichert
+toast-undid = rückgängig: { $change }
+toast-nothing-to-undo = nichts rückgängig zu machen
+toast-undo-changed = die Aufgaben haben sich seitdem geändert, nichts rückgängig zu machen
+toast-reload-failed = Neuladen fehlgeschlagen: { $error }
+toast-batch-outdated = die Aufgaben haben sich inzwischen geändert, nichts wurde gemacht
+toast-batch-done = { $count ->
+    [one] 1 Aufgabe geändert
+   *[other] { $count } Aufgaben geändert
+}
+toast-batch-empty = keine Aufgabe würde sich ändern
+toast-unknown-command = unbekannter Befehl `{ $command }`
+toast-not-listed = { $task } steht nicht in dieser Ansicht, V wechselt sie
+toast-issue-update-failed = { $issue } aktualisieren fehlgeschlagen: { $error }
+toast-history-failed = Verlauf aktualisieren fehlgeschlagen: { $error }
+toast-read-only = { $file } ist schreibgeschützt, Änderungen werden nicht gespeichert
+toast-quick-add-unavailable = Schnelles Hinzufügen nicht verfügbar: { $error }
+toast-stale = { $count ->
+    [one] 1 Aufgabe ist liegen geblieben, R geht sie durch
+   *[other] { $count } Aufgaben sind liegen geblieben, R geht sie durch
+}
+
+undo-add = { $task } hinzufügen
+undo-add-many = { $count } Aufgaben hinzufügen
+undo-complete = { $task } erledigen
+undo-reopen = { $task } wieder öffnen
+undo-edit = { $task } bearbeiten
+undo-edit-file = Datei bearbeiten
+undo-plugin = Plugin { $action }
+undo-priority = Priorität von { $task }
+undo-move = { $task } verschieben
+undo-due = Fälligkeit von { $task }
+undo-delete = { $task } löschen
+undo-purge = erledigte Aufgaben entfernen
+undo-tracking = Zeiterfassung von { $task }
+undo-sync = synchronisieren
+undo-resolve-conflict = Konflikt lösen
+undo-review = { $task } durchsehen
+
+notify-focus-done = Fokus-Phase vorbei
+notify-focus-done-body = Zeit für eine Pause
+notify-break-over = Pause vorbei
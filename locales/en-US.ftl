@@ -0,0 +1 @@
+date-format = %m/%d/%Y
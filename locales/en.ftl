@@ -0,0 +1,263 @@
+# Dates are written with strftime patterns
+date-format = %Y-%m-%d
+
+status-loading = loading { $percent }%
+status-unsaved = unsaved, retry in { $seconds }s or w
+status-read-only = read-only
+view-waiting = waiting for
+view-someday = someday
+view-all = all tasks
+view-columns = columns
+pomodoro-focus = focus
+pomodoro-break = break
+
+mode-normal = NORMAL
+mode-insert = INSERT
+mode-edit = EDIT
+mode-search = SEARCH
+mode-focus = FOCUS
+mode-stats = STATS
+mode-errors = ERRORS
+mode-conflict = CONFLICT
+mode-confirm = CONFIRM
+mode-review = REVIEW
+mode-command = COMMAND
+mode-batch = CHANGES
+mode-sidebar = TAGS
+
+too-small = Terminal too small
+too-small-size = { $width }x{ $height } (need { $min-width }x{ $min-height })
+
+search-only-matches = only matches
+
+title-progress = { $done }/{ $total } done · { $percent }%
+title-syncing = syncing…
+title-synced = synced { $time }
+title-sync-failed = sync failed
+
+startup-title = Todo file
+startup-missing = doesn't exist yet
+startup-unreadable = can't be read
+startup-read-only = can't be written
+startup-create = create it
+startup-open = open another file
+startup-open-read-only = open read-only, nothing is saved
+startup-quit = quit
+startup-path = path:
+
+errors-title = Errors
+errors-none = Nothing went wrong so far
+
+detail-every = every { $interval }
+detail-tracked = { $duration } tracked
+detail-created = created { $date }
+detail-streak = { $days ->
+    [one] streak of { $days } day
+   *[other] streak of { $days } days
+}
+detail-completions = done on { $count } of the last { $days } days
+
+# Words for the glyphs and colours of a task line in accessible mode
+list-due = due { $date }
+list-overdue = overdue since { $date }
+list-running = { $duration } running
+list-priority = priority { $priority }
+list-progress = { $done } of { $total } subtasks done
+list-stale = stale
+list-scheduled = starts { $date }
+list-waiting = waiting
+list-someday = someday
+column-priority = pri
+column-due = due
+column-tags = tags
+column-task = task
+
+# The confirmation dialog, its answers show the key that picks them
+confirm-yes = [y] yes
+confirm-no = [n] no
+confirm-cancel = [esc] cancel
+confirm-delete = Delete “{ $task }”?
+confirm-purge = { $count ->
+    [one] Remove the done task?
+   *[other] Remove { $count } done tasks?
+}
+confirm-quit = Changes to { $file } aren't saved. Save before quitting?
+confirm-overwrite = { $file } was changed by another program. Overwrite it? No loads the changed file.
+duplicate-hint = Already open: “{ $task }”. Enter adds it anyway, tab jumps to it.
+
+# Reviewing stale tasks
+review-title = Stale task { $number } of { $total }
+review-untouched = untouched for { $days ->
+    [one] a day
+   *[other] { $days } days
+}
+review-keep = keep
+review-tomorrow = tomorrow
+review-next-week = next week
+review-done = done
+review-drop = drop
+review-skip = skip
+
+# Changes of :map before they are made
+batch-title = { $command } changes { $count ->
+    [one] a task
+   *[other] { $count } tasks
+}
+batch-keys = y apply  n cancel  j/k scroll
+
+# Sidebar of contexts and tags
+sidebar-title = Tags
+sidebar-all = all tasks
+
+# Sync conflicts, the last synced, local and remote version side by side
+conflict-title = Sync conflict ({ $count } left)
+conflict-base = Last sync
+conflict-local = Local
+conflict-remote = Remote
+conflict-new = (new)
+conflict-state = state
+conflict-due = due
+conflict-priority = pri
+conflict-done = done
+conflict-open = open
+conflict-keep-local = keep local
+conflict-keep-remote = keep remote
+conflict-keep-both = keep both
+conflict-later = decide later
+
+# Statistics
+stats-title = Stats
+stats-completed-today = Completed today
+stats-completed-week = Completed this week
+stats-completed-total = Completed all time
+stats-added-total = Added all time
+stats-tracked = Tracked time
+
+# The debug overlay
+debug-title = Debug
+debug-frame = frame
+debug-last-action = last action
+debug-events = events
+
+# Said in accessible mode when something changed on screen
+announce-mode-normal = task list
+announce-mode-edit = edit task
+announce-mode-create = new task
+announce-mode-stats = statistics
+announce-mode-focus = focus
+announce-mode-conflict = sync conflict
+announce-mode-errors = errors
+announce-mode-search = search
+announce-mode-confirm = question
+announce-mode-review = review
+announce-mode-command = command
+announce-mode-batch = changes to confirm
+announce-mode-sidebar = tags
+announce-end-of-list = end of list
+announce-start-of-list = start of list
+announce-matches = { $count ->
+    [one] 1 matching task
+   *[other] { $count } matching tasks
+}
+announce-only-matches = only matching tasks
+announce-all-tasks = all tasks
+announce-columns = columns
+announce-one-line = one line per task
+announce-tag = { $count } open with { $tag }
+announce-already-open = already open: { $task }
+announce-edit-empty = no text left, the edit is dropped
+announce-edited = edited: { $task }
+announce-review-finished = review finished
+announce-reloaded = reloaded { $file }
+announce-done = done: { $task }
+announce-open = open: { $task }
+announce-highest-priority = already the highest priority
+announce-lowest-priority = no priority to lower
+announce-priority = priority { $priority }: { $task }
+announce-no-priority = no priority: { $task }
+announce-due = due { $date }: { $task }
+announce-no-due = no due date: { $task }
+announce-deleted = deleted: { $task }
+announce-purged = { $count ->
+    [one] removed the done task
+   *[other] removed { $count } done tasks
+}
+announce-tracking = tracking: { $task }
+announce-stopped-tracking = stopped tracking: { $task }
+announce-pomodoro-started = pomodoro started: { $task }
+announce-pomodoro-stopped = pomodoro stopped
+announce-added = added: { $task }
+announce-added-many = added { $count } tasks
+
+# Messages below the list
+toast-still-loading = still loading, try again in a moment
+toast-edit-dropped = the task changed meanwhile, the edit is dropped
+toast-no-task = there is no task { $id } anymore
+toast-no-stale = no stale tasks
+toast-not-saved-yet = changes aren't saved yet, try again once they are
+toast-plugin-failed = plugin failed: { $error }
+toast-moved = moved to { $bucket }: { $task }
+toast-active-again = active again: { $task }
+toast-nothing-to-purge = no done tasks to remove
+toast-sync-not-saved = changes aren't saved here, so they aren't synced either
+toast-pulling-issues = pulling issues…
+toast-issues-changed = { $count ->
+    [one] 1 issue changed
+   *[other] { $count } issues changed
+}
+toast-pulling-issues-failed = pulling issues failed: { $error }
+toast-syncing = syncing…
+toast-synced = synced: { $summary }
+toast-sync-failed = sync failed: { $error }
+toast-conflicts-deferred = { $count ->
+    [one] 1 conflict left for later, S shows it
+   *[other] { $count } conflicts left for later, S shows them
+}
+toast-issue-title = issue titles are edited on GitHub
+toast-saved = saved
+toast-save-failed = saving failed: { $error }
+toast-loaded = loaded { $count } tasks
+toast-reading-stopped = reading stopped: { $error }, changes aren't saved
+toast-undid = undid { $change }
+toast-nothing-to-undo = nothing to undo
+toast-undo-changed = the tasks changed since, nothing to undo
+toast-reload-failed = reloading failed: { $error }
+toast-batch-outdated = the tasks changed meanwhile, nothing was done
+toast-batch-done = { $count ->
+    [one] 1 task changed
+   *[other] { $count } tasks changed
+}
+toast-batch-empty = no task would change
+toast-unknown-command = unknown command `{ $command }`
+toast-not-listed = { $task } isn't listed in this view, V switches it
+toast-issue-update-failed = updating { $issue } failed: { $error }
+toast-history-failed = updating the history failed: { $error }
+toast-read-only = { $file } is read-only, changes aren't saved
+toast-quick-add-unavailable = quick add unavailable: { $error }
+toast-stale = { $count ->
+    [one] 1 task is stale, R reviews it
+   *[other] { $count } tasks are stale, R reviews them
+}
+
+# What undo takes back, shown as "undid …"
+undo-add = adding { $task }
+undo-add-many = adding { $count } tasks
+undo-complete = completing { $task }
+undo-reopen = reopening { $task }
+undo-edit = editing { $task }
+undo-edit-file = editing the file
+undo-plugin = plugin { $action }
+undo-priority = priority of { $task }
+undo-move = moving { $task }
+undo-due = due date of { $task }
+undo-delete = deleting { $task }
+undo-purge = removing the done tasks
+undo-tracking = tracking { $task }
+undo-sync = syncing
+undo-resolve-conflict = resolving a conflict
+undo-review = reviewing { $task }
+
+# Desktop notifications of the pomodoro timer
+notify-focus-done = Focus session done
+notify-focus-done-body = Time for a break
+notify-break-over = Break is over
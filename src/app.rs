@@ -0,0 +1,2964 @@
+use chrono::{Local, NaiveDate};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    path::Path,
+    pin::Pin,
+    time::{Duration, Instant, SystemTime},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use crossterm::event::{
+    self,
+    KeyCode::{self, Char},
+    KeyModifiers,
+};
+use ratatui::{prelude::*, widgets::*};
+use serde_derive::Deserialize;
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        watch,
+    },
+    task::JoinHandle,
+};
+use tracing::{debug, trace, warn};
+use tui_input::Input;
+
+use crate::{
+    batch::{self, Batch, Transform},
+    cli,
+    config::Config,
+    confirm::{self, Answer, Confirm},
+    conflict::{self, Resolution},
+    dates::{self, Shift},
+    debug::{self, Timings},
+    detail,
+    duplicate::{self, Duplicate},
+    editor,
+    file::{self, parse_task, TaskStore},
+    focus,
+    front_matter::{self, FileSettings, SortOrder},
+    git,
+    github::Github,
+    history::{Appender, History, HistoryEntry, HistoryKind},
+    hooks::{self, Hook},
+    i18n::{self, t, t_args},
+    import, ipc, journal,
+    list::{self, TaskList},
+    modal::{Modal, Modals},
+    notify,
+    plugins::{self, Outcome},
+    pomodoro::{Phase, Pomodoro},
+    remote::Remote,
+    rules,
+    search::{self, Search},
+    session::{Session, View},
+    sidebar::{self, Sidebar},
+    stale::{self, Review, Verdict},
+    startup::{self, Opened},
+    stats, status,
+    sync::{self, Conflict},
+    task_item::{self, Bucket, TaskItem, TaskState},
+    text_input, theme,
+    title::{self, SyncStatus},
+    toast::{self, Severity, Toasts},
+    tui::{self, Event},
+    undo::{Undo, Undone},
+    view::ListView,
+};
+
+/// How the interface is started
+#[derive(Clone, Copy, Default)]
+pub struct Options {
+    /// Render a compact list below the cursor instead of taking over the screen
+    pub inline: bool,
+    /// Append tasks piped to stdin before starting
+    pub stdin: bool,
+    /// Plain text for screen readers, see `theme::set_accessible`
+    pub accessible: bool,
+    /// Overrides `interface.tick_rate` from the config
+    pub tick_rate: Option<f64>,
+    /// Overrides `interface.frame_rate` from the config
+    pub frame_rate: Option<f64>,
+}
+
+/// `[interface]` section of the config, how often the interface updates
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct InterfaceConfig {
+    /// Ticks per second, which count toasts and timers down and reopen recurring tasks
+    pub tick_rate: f64,
+    /// Frames drawn per second while keys are pressed
+    pub frame_rate: f64,
+    /// Frames drawn per second once nothing was pressed for `idle_after` seconds, which
+    /// saves battery on laptops
+    pub idle_frame_rate: f64,
+    pub idle_after: u64,
+}
+
+impl Default for InterfaceConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate: 1.0,
+            frame_rate: 30.0,
+            idle_frame_rate: 2.0,
+            idle_after: 5,
+        }
+    }
+}
+
+type Work = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+// App state
+struct App {
+    counter: i64,
+    should_quit: bool,
+    action_tx: UnboundedSender<Action>,
+    mode: Mode,
+    new_task: Input,
+    tasks: TaskList,
+    store: TaskStore,
+    config: Config,
+    /// The time a tick stands for
+    tick_period: Duration,
+    history: History,
+    /// Logs to the history in order, `None` where nothing is logged
+    appender: Option<Appender>,
+    history_entries: Vec<HistoryEntry>,
+    pomodoro: Option<Pomodoro>,
+    inline: bool,
+    toasts: Toasts,
+    /// Sync conflicts waiting for a decision, the first one is shown
+    conflicts: Vec<Conflict>,
+    /// Whether edits are written right away and hooks, history and syncing reach outside,
+    /// the engine leaves all of that to its caller
+    persist: bool,
+    /// Background work the engine runs one by one, `None` spawns it right away
+    queued: Option<VecDeque<Work>>,
+    /// The file couldn't be written when it was opened, so nothing is saved
+    read_only: bool,
+    /// Whether the debug overlay is shown
+    debug: bool,
+    timings: Timings,
+    /// Saves started so far, each one is numbered
+    saves: u64,
+    /// The latest save that went through
+    saved: u64,
+    pending_save: Option<PendingSave>,
+    /// Started with the first save
+    writer: Option<Writer>,
+    /// Set while a big file is still being read
+    loading: Option<Loading>,
+    /// The search being typed or confirmed, cleared with esc
+    search: Option<Search>,
+    sync: SyncStatus,
+    /// Popups over the screen, the mode below them comes back once they are closed
+    modals: Modals,
+    /// When the file was last read or saved here, to notice other programs changing it
+    disk_modified: Option<SystemTime>,
+    /// Changes weren't saved because the file changed on disk and overwriting was declined
+    dirty: bool,
+    /// Changes made here so far, counted on every save
+    changes: u64,
+    /// Set while the file is read again, to the changes made by then; the tasks read
+    /// don't replace changes made meanwhile
+    reloading: Option<u64>,
+    /// When the file was last changed as of the latest reload, so a file that can't be
+    /// read isn't tried again on every tick
+    reload_tried: Option<SystemTime>,
+    undo: Undo,
+    /// What the file's front matter asks for
+    settings: FileSettings,
+    /// A typed task that looks like an open one, until enter adds it anyway or tab jumps
+    /// to that one
+    duplicate: Option<Duplicate>,
+    /// The `:` command being typed
+    command: Input,
+    view: ListView,
+    sidebar: Sidebar,
+}
+
+/// A save that failed, retried with backoff until one goes through
+struct PendingSave {
+    /// The failed save, results of older ones don't affect it
+    number: u64,
+    attempts: u32,
+    retry_at: Instant,
+}
+
+#[derive(Default)]
+struct Loading {
+    /// How much of the file has been read, from 0 to 1
+    progress: f64,
+    /// Tasks read so far, they come before any added meanwhile
+    loaded: usize,
+    /// Changes were made that can only be saved once everything is read
+    save_pending: bool,
+}
+
+/// Longest wait between two save retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Writes the file one save at a time in the background, always the latest tasks handed
+/// over, so an older version can't be written after a newer one
+struct Writer {
+    latest: watch::Sender<Option<(u64, TaskStore)>>,
+    /// Ends with the number of the last save that went through
+    task: JoinHandle<u64>,
+}
+
+impl Writer {
+    /// Reports every save that went through or failed to `tx`, by number
+    fn spawn(tx: UnboundedSender<Action>) -> Self {
+        let (latest, mut rx) = watch::channel(None::<(u64, TaskStore)>);
+        let task = tokio::spawn(async move {
+            let mut written = 0;
+            while rx.changed().await.is_ok() {
+                let Some((number, store)) = rx.borrow_and_update().clone() else {
+                    continue;
+                };
+                // the pre-save hook runs in here too, one save's at a time
+                let file = store.file_path().to_owned();
+                let result = match store.save().await {
+                    Ok(_) => {
+                        written = number;
+                        Action::Saved(number, modified(&file))
+                    }
+                    Err(e) => Action::SaveFailed(number, e.to_string()),
+                };
+                // the app may be gone already when quitting
+                let _ = tx.send(result);
+            }
+            written
+        });
+        Self { latest, task }
+    }
+
+    /// Writes `store` once the save running now is done, in place of any waiting for it
+    fn save(&self, number: u64, store: TaskStore) {
+        self.latest.send_replace(Some((number, store)));
+    }
+
+    /// Waits for the saves handed over so far
+    async fn finish(self) -> u64 {
+        let Self { latest, task } = self;
+        drop(latest);
+        task.await.unwrap_or_default()
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Mode {
+    Normal,
+    Edit,
+    Create,
+    Stats,
+    Focus,
+    Conflict,
+    Errors,
+    Search,
+    Confirm,
+    Review,
+    /// Typing a `:` command
+    Command,
+    Batch,
+    /// Picking a tag in the sidebar
+    Sidebar,
+}
+
+impl Mode {
+    /// What accessible mode announces when the mode is entered
+    fn name(&self) -> String {
+        t(match self {
+            Mode::Normal => "announce-mode-normal",
+            Mode::Edit => "announce-mode-edit",
+            Mode::Create => "announce-mode-create",
+            Mode::Stats => "announce-mode-stats",
+            Mode::Focus => "announce-mode-focus",
+            Mode::Conflict => "announce-mode-conflict",
+            Mode::Errors => "announce-mode-errors",
+            Mode::Search => "announce-mode-search",
+            Mode::Confirm => "announce-mode-confirm",
+            Mode::Review => "announce-mode-review",
+            Mode::Command => "announce-mode-command",
+            Mode::Batch => "announce-mode-batch",
+            Mode::Sidebar => "announce-mode-sidebar",
+        })
+    }
+
+    /// The label and colour of the mode in the status line
+    fn badge(&self) -> (String, Color) {
+        let palette = theme::palette();
+        match self {
+            Mode::Normal => (t("mode-normal"), palette.normal_badge),
+            Mode::Create => (t("mode-insert"), palette.insert_badge),
+            Mode::Edit => (t("mode-edit"), palette.insert_badge),
+            Mode::Search => (t("mode-search"), palette.search_badge),
+            Mode::Focus => (t("mode-focus"), palette.view_badge),
+            Mode::Stats => (t("mode-stats"), palette.view_badge),
+            Mode::Errors => (t("mode-errors"), palette.view_badge),
+            Mode::Conflict => (t("mode-conflict"), palette.view_badge),
+            Mode::Confirm => (t("mode-confirm"), palette.view_badge),
+            Mode::Review => (t("mode-review"), palette.view_badge),
+            Mode::Command => (t("mode-command"), palette.search_badge),
+            Mode::Batch => (t("mode-batch"), palette.view_badge),
+            Mode::Sidebar => (t("mode-sidebar"), palette.search_badge),
+        }
+    }
+}
+
+// App actions
+#[derive(Clone, Debug)]
+pub enum Action {
+    Tick,
+    Increment,
+    Decrement,
+    NetworkRequestAndThenIncrement, // new
+    NetworkRequestAndThenDecrement, // new
+    Quit,
+    /// Quits without asking about unsaved changes, they are still saved on the way out
+    QuitNow,
+    /// Quits dropping the changes that weren't saved
+    DiscardAndQuit,
+    Render,
+    /// Stops until `fg`, handing the terminal back to the shell meanwhile
+    Suspend,
+    /// Opens the selected task in `$EDITOR`, for long text and notes
+    EditInEditor,
+    /// Hands the terminal to the editor for this task, which only the run loop can do
+    RunEditor(Box<TaskItem>),
+    /// The task as it was before and the buffer the editor saved
+    TaskEdited(Box<TaskItem>, String),
+    /// Goes through the stale tasks one by one
+    Review,
+    ReviewTask(Verdict),
+    /// Opens the whole file in `$EDITOR` and reads it again afterwards
+    EditFile,
+    RunFileEditor,
+    /// The tasks and settings the file was saved with
+    FileEdited(Vec<TaskItem>, Option<SystemTime>, Box<FileSettings>),
+    None,
+    NextTask,
+    PreviousTask,
+    ToggleTaskState,
+    DeleteTask,
+    /// Takes the latest change back, also one made before the file was last closed
+    Undo,
+    /// Deletes the selected task, asking first unless `confirm.delete` is off
+    AskDelete,
+    /// Removes all done tasks
+    PurgeDone,
+    AskPurge,
+    Answer(Answer),
+    /// Moves the selected answer on, or back
+    SelectAnswer(bool),
+    /// Saves over changes another program made to the file
+    Overwrite,
+    /// Drops the changes made here for what is on disk now
+    Reload,
+    Reloaded(Vec<TaskItem>, Option<SystemTime>),
+    ReloadFailed(String),
+    ToggleTracking,
+    /// Raises the priority of the selected task one level, or lowers it
+    BumpPriority(bool),
+    /// Moves the due date of the selected task without typing one
+    ShiftDue(Shift),
+    /// Moves the selected task and its subtasks to a bucket, or back out of it
+    MoveToBucket(Bucket),
+    TogglePomodoro,
+    ShowStats,
+    StatsLoaded(Vec<HistoryEntry>),
+    Sync,
+    /// Whether the sync went through
+    SyncFinished(bool),
+    ReplaceTasks(Vec<TaskItem>),
+    /// The tasks a sync started with and the ones it ended with
+    Synced(Vec<TaskItem>, Vec<TaskItem>),
+    SyncConflicts(Vec<Conflict>),
+    ResolveConflict(Resolution),
+    /// Closes the conflicts without deciding, the next sync shows them again
+    DeferConflicts,
+    Notify(Severity, String),
+    HandleInputKey(event::Event),
+    AddTask,
+    /// A task sent by `tuitodo add` from another terminal
+    InsertTask(String),
+    /// Tasks sent by `tuitodo import` from another terminal
+    ImportTasks(Vec<TaskItem>),
+    /// `tuitodo done`, `rm` and `edit` or `tuitodo serve` from elsewhere, for the task with
+    /// the id
+    SetTaskState(String, TaskState),
+    RemoveTask(String),
+    EditTask(String, String),
+    RunPlugin(String),
+    ClearNewTask,
+    /// Selects the open task the one being typed looks like, instead of adding it
+    JumpToDuplicate,
+    SaveTask,
+    HandleCommandKey(event::Event),
+    /// Runs the typed `:` command
+    RunCommand,
+    ClearCommand,
+    /// Makes the changes `:map` showed, as one step to undo
+    ApplyBatch,
+    ScrollBatch(bool),
+    StartSearch,
+    HandleSearchKey(event::Event),
+    ToggleRegex,
+    ConfirmSearch,
+    ClearSearch,
+    NextMatch,
+    PreviousMatch,
+    /// Switches between listing only the matches and all tasks
+    ToggleFiltering,
+    /// Switches between the light and the dark palette
+    ToggleTheme,
+    /// Moves on to the next way of listing the tasks
+    SwitchView,
+    /// Switches between a line per task and aligned columns
+    ToggleColumns,
+    /// Shows the tags next to the list and hands them the keys, or hides them again
+    ToggleSidebar,
+    MoveInSidebar(bool),
+    /// Lists only the tasks with the selected tag, or all of them again
+    PickInSidebar,
+    SwitchMode(Mode),
+    ShowErrors,
+    /// Closes the topmost popup
+    CloseModal,
+    ToggleDebug,
+    /// A save went through, leaving the file modified at that time
+    Saved(u64, Option<SystemTime>),
+    SaveFailed(u64, String),
+    RetrySave,
+    TasksLoaded(Vec<TaskItem>, f64),
+    /// Reading the file is done, with the error that stopped it early
+    LoadFinished(Option<String>),
+}
+
+impl Action {
+    /// The variant without its data, for the debug overlay
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Tick => "Tick",
+            Action::Increment => "Increment",
+            Action::Decrement => "Decrement",
+            Action::NetworkRequestAndThenIncrement => "NetworkRequestAndThenIncrement",
+            Action::NetworkRequestAndThenDecrement => "NetworkRequestAndThenDecrement",
+            Action::Quit => "Quit",
+            Action::QuitNow => "QuitNow",
+            Action::DiscardAndQuit => "DiscardAndQuit",
+            Action::Render => "Render",
+            Action::Suspend => "Suspend",
+            Action::EditInEditor => "EditInEditor",
+            Action::RunEditor(..) => "RunEditor",
+            Action::TaskEdited(..) => "TaskEdited",
+            Action::Review => "Review",
+            Action::ReviewTask(..) => "ReviewTask",
+            Action::EditFile => "EditFile",
+            Action::RunFileEditor => "RunFileEditor",
+            Action::FileEdited(..) => "FileEdited",
+            Action::None => "None",
+            Action::NextTask => "NextTask",
+            Action::PreviousTask => "PreviousTask",
+            Action::ToggleTaskState => "ToggleTaskState",
+            Action::DeleteTask => "DeleteTask",
+            Action::Undo => "Undo",
+            Action::AskDelete => "AskDelete",
+            Action::PurgeDone => "PurgeDone",
+            Action::AskPurge => "AskPurge",
+            Action::Answer(..) => "Answer",
+            Action::SelectAnswer(..) => "SelectAnswer",
+            Action::Overwrite => "Overwrite",
+            Action::Reload => "Reload",
+            Action::Reloaded(..) => "Reloaded",
+            Action::ReloadFailed(_) => "ReloadFailed",
+            Action::ToggleTracking => "ToggleTracking",
+            Action::BumpPriority(..) => "BumpPriority",
+            Action::ShiftDue(..) => "ShiftDue",
+            Action::MoveToBucket(..) => "MoveToBucket",
+            Action::TogglePomodoro => "TogglePomodoro",
+            Action::ShowStats => "ShowStats",
+            Action::StatsLoaded(..) => "StatsLoaded",
+            Action::Sync => "Sync",
+            Action::SyncFinished(..) => "SyncFinished",
+            Action::ReplaceTasks(..) => "ReplaceTasks",
+            Action::Synced(..) => "Synced",
+            Action::SyncConflicts(..) => "SyncConflicts",
+            Action::ResolveConflict(..) => "ResolveConflict",
+            Action::DeferConflicts => "DeferConflicts",
+            Action::Notify(..) => "Notify",
+            Action::HandleInputKey(..) => "HandleInputKey",
+            Action::AddTask => "AddTask",
+            Action::InsertTask(..) => "InsertTask",
+            Action::ImportTasks(..) => "ImportTasks",
+            Action::SetTaskState(..) => "SetTaskState",
+            Action::RemoveTask(..) => "RemoveTask",
+            Action::EditTask(..) => "EditTask",
+            Action::RunPlugin(..) => "RunPlugin",
+            Action::ClearNewTask => "ClearNewTask",
+            Action::JumpToDuplicate => "JumpToDuplicate",
+            Action::SaveTask => "SaveTask",
+            Action::HandleCommandKey(..) => "HandleCommandKey",
+            Action::RunCommand => "RunCommand",
+            Action::ClearCommand => "ClearCommand",
+            Action::ApplyBatch => "ApplyBatch",
+            Action::ScrollBatch(..) => "ScrollBatch",
+            Action::StartSearch => "StartSearch",
+            Action::HandleSearchKey(..) => "HandleSearchKey",
+            Action::ToggleRegex => "ToggleRegex",
+            Action::ConfirmSearch => "ConfirmSearch",
+            Action::ClearSearch => "ClearSearch",
+            Action::NextMatch => "NextMatch",
+            Action::PreviousMatch => "PreviousMatch",
+            Action::ToggleFiltering => "ToggleFiltering",
+            Action::ToggleTheme => "ToggleTheme",
+            Action::SwitchView => "SwitchView",
+            Action::ToggleColumns => "ToggleColumns",
+            Action::ToggleSidebar => "ToggleSidebar",
+            Action::MoveInSidebar(..) => "MoveInSidebar",
+            Action::PickInSidebar => "PickInSidebar",
+            Action::SwitchMode(..) => "SwitchMode",
+            Action::ShowErrors => "ShowErrors",
+            Action::CloseModal => "CloseModal",
+            Action::ToggleDebug => "ToggleDebug",
+            Action::Saved(..) => "Saved",
+            Action::SaveFailed(..) => "SaveFailed",
+            Action::RetrySave => "RetrySave",
+            Action::TasksLoaded(..) => "TasksLoaded",
+            Action::LoadFinished(..) => "LoadFinished",
+        }
+    }
+}
+
+/// Below this size there is no sensible way to draw the list
+const MIN_WIDTH: u16 = 24;
+const MIN_HEIGHT: u16 = 6;
+/// Below this size the padding around the list and the detail pane are dropped
+const COMPACT_WIDTH: u16 = 60;
+const COMPACT_HEIGHT: u16 = 16;
+/// Lines taken by the `--inline` viewport at most
+const INLINE_MAX_HEIGHT: u16 = 15;
+
+/// The terminal's size and the size needed, centered in `area`
+fn too_small_ui(f: &mut Frame, area: Rect) {
+    let size = f.size();
+    let message = vec![
+        Line::from(t("too-small")),
+        Line::from(t_args(
+            "too-small-size",
+            &[
+                ("width", size.width.into()),
+                ("height", size.height.into()),
+                ("min-width", MIN_WIDTH.into()),
+                ("min-height", MIN_HEIGHT.into()),
+            ],
+        )),
+    ];
+
+    let message_area = Rect {
+        y: area.y + area.height.saturating_sub(2) / 2,
+        height: area.height.min(2),
+        ..area
+    };
+
+    f.render_widget(
+        Paragraph::new(message)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::Yellow)),
+        message_area,
+    );
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    // accessible mode announces on a line of its own below the status line
+    let (area, announcements) = match theme::accessible() {
+        true => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Min(0), Constraint::Length(1)])
+                .split(f.size());
+            (rows[0], Some(rows[1]))
+        }
+        false => (f.size(), None),
+    };
+    screen_ui(f, area, app);
+    modals_ui(f, area, app);
+
+    match (app.toasts.current(), announcements) {
+        (Some(toast), Some(line)) => toast::line_ui(f, line, toast),
+        (Some(toast), None) => {
+            // below the title bar, which shows whether a sync went through
+            let below_title = match app.inline {
+                true => f.size(),
+                false => Rect {
+                    y: 1,
+                    height: f.size().height.saturating_sub(1),
+                    ..f.size()
+                },
+            };
+            toast::ui(f, below_title, toast);
+        }
+        (None, _) => {}
+    }
+
+    if app.debug {
+        debug::ui(f, centered_rect(f.size(), 90, 80), &app.timings);
+    }
+}
+
+fn screen_ui(f: &mut Frame, area: Rect, app: &mut App) {
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        too_small_ui(f, area);
+        return;
+    }
+
+    // the inline viewport has no room to spare
+    let title_height = if app.inline { 0 } else { 1 };
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Length(title_height),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(area);
+    let screen = &rows[1..];
+
+    if !app.inline {
+        let file = app.store.file_path();
+        title::ui(f, rows[0], file, &app.store.items, app.sync);
+    }
+
+    let retry_in = app
+        .pending_save
+        .as_ref()
+        .map(|p| p.retry_at.saturating_duration_since(Instant::now()));
+    let loading = app.loading.as_ref().map(|l| l.progress);
+    let view = (app.view.label()).map(|label| (label, theme::palette().view_badge));
+    let columns = (app.tasks.columns).then(|| (t("view-columns"), theme::palette().view_badge));
+    let badges: Vec<(String, Color)> = [Some(current_mode(app).badge()), view, columns]
+        .into_iter()
+        .flatten()
+        .collect();
+    status::ui(
+        f,
+        screen[1],
+        &badges,
+        app.pomodoro.as_ref(),
+        app.read_only,
+        retry_in,
+        loading,
+    );
+
+    if app.mode == Mode::Focus {
+        if let Some(index) = app.tasks.state.selected() {
+            focus::ui(f, screen[0], &app.store.items[index], app.pomodoro.as_ref());
+        }
+        return;
+    }
+
+    // the tags go on the left, the rest is the list's
+    let main = match app.sidebar.visible && !app.inline && screen[0].width > sidebar::WIDTH * 2 {
+        true => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(sidebar::WIDTH), Constraint::Min(0)])
+                .split(screen[0]);
+            let entries = sidebar_entries(app);
+            app.sidebar.selected = app.sidebar.selected.min(entries.len());
+            let focused = current_mode(app) == Mode::Sidebar;
+            let active = sidebar_filter(app);
+            sidebar::ui(f, columns[0], &app.sidebar, &entries, active, focused);
+            columns[1]
+        }
+        false => screen[0],
+    };
+
+    let compact_width = app.inline || main.width < COMPACT_WIDTH;
+    let compact_height = app.inline || main.height < COMPACT_HEIGHT;
+    let center = centered_rect(
+        main,
+        if compact_width { 100 } else { 80 },
+        if compact_height { 100 } else { 30 },
+    );
+
+    let search = app.search.as_ref().filter(|s| s.filters());
+    let today = Local::now().date_naive();
+    let task_count = match search.is_some() || app.settings.hide_done || app.view != ListView::All {
+        true => (app.store.items.iter())
+            .filter(|t| listed(search, &app.settings, app.view, today, t))
+            .count(),
+        false => app.store.items.len(),
+    };
+    // the columns have a header above them
+    let task_count = task_count + usize::from(app.tasks.columns);
+    let task_count = task_count.min(u16::MAX as usize) as u16;
+    let detail_height = if compact_height { 0 } else { detail::HEIGHT };
+    let duplicate = app.duplicate.as_ref().filter(|_| app.mode == Mode::Create);
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Max(task_count),
+            Constraint::Length(1),
+            Constraint::Length(duplicate.map_or(0, |_| 1)),
+            Constraint::Length(detail_height),
+        ])
+        .split(center);
+
+    list::ui(
+        f,
+        layout[0],
+        &app.store.items,
+        &mut app.tasks,
+        app.search.as_ref(),
+        &app.settings,
+        app.view,
+    );
+
+    if let (Some(search), Mode::Normal | Mode::Search | Mode::Sidebar) = (&app.search, &app.mode) {
+        search::ui(f, layout[1], search, app.mode == Mode::Search);
+    }
+
+    if let (false, Some(index)) = (compact_height, app.tasks.state.selected()) {
+        detail::ui(f, layout[3], &app.store.items[index]);
+    }
+
+    if let Some(task) = duplicate.and_then(|d| app.store.items.get(d.index)) {
+        let hint = t_args("duplicate-hint", &[("task", task.text.clone().into())]);
+        let hint = Paragraph::new(hint).style(Style::default().fg(Color::Yellow));
+        f.render_widget(hint, layout[2]);
+    }
+
+    let (input, prompt) = match app.mode {
+        Mode::Create if theme::accessible() => (&app.new_task, ">"),
+        Mode::Create => (&app.new_task, theme::glyphs().prompt.as_str()),
+        Mode::Command => (&app.command, ":"),
+        _ => return,
+    };
+    let input_line = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Length(2), Constraint::Min(1)])
+        .split(layout[1]);
+
+    let (visible, cursor) = text_input::visible(input, input_line[1].width as usize);
+    f.render_widget(Paragraph::new(prompt), input_line[0]);
+    f.render_widget(Paragraph::new(visible), input_line[1]);
+
+    f.set_cursor(input_line[1].x + cursor as u16, input_line[1].y)
+}
+
+/// The open popups over the dimmed screen, the topmost drawn last
+fn modals_ui(f: &mut Frame, area: Rect, app: &App) {
+    if app.modals.is_empty() || area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        return;
+    }
+    f.buffer_mut()
+        .set_style(area, Style::default().add_modifier(Modifier::DIM));
+
+    let compact_width = app.inline || area.width < COMPACT_WIDTH;
+    let compact_height = app.inline || area.height < COMPACT_HEIGHT;
+    let popup = |height| {
+        centered_rect(
+            area,
+            if compact_width { 100 } else { 80 },
+            if compact_height { 100 } else { height },
+        )
+    };
+
+    for modal in app.modals.iter() {
+        match modal {
+            Modal::Confirm(confirm) => confirm::ui(f, area, confirm),
+            Modal::Stats => stats::ui(f, popup(30), &app.store.items, &app.history_entries),
+            Modal::Errors => toast::history_ui(f, popup(30), app.toasts.history()),
+            Modal::Conflict => {
+                if let Some(conflict) = app.conflicts.first() {
+                    conflict::ui(f, popup(50), conflict, app.conflicts.len());
+                }
+            }
+            Modal::Review(review) => stale::ui(f, popup(30), review),
+            Modal::Batch(batch) => batch::ui(f, popup(50), batch),
+        }
+    }
+}
+
+fn centered_rect(r: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+fn get_action(app: &App, event: Event) -> Action {
+    match event {
+        Event::Error => Action::None,
+        Event::Tick => Action::Tick,
+        Event::Render => Action::Render,
+        Event::Key(key, _) if key.code == KeyCode::F(12) => Action::ToggleDebug,
+        Event::Key(key, _)
+            if key.code == Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            Action::Suspend
+        }
+        Event::Key(key, _) if !app.modals.is_empty() => modal_action(app, key),
+        Event::Key(key, event) => match app.mode {
+            Mode::Normal => match key.code {
+                Char('j') => Action::NextTask,
+                Char('k') => Action::PreviousTask,
+                Char(' ') => Action::ToggleTaskState,
+                Char('d') => Action::AskDelete,
+                Char('D') => Action::AskPurge,
+                Char('t') => Action::ToggleTracking,
+                Char('p') => Action::TogglePomodoro,
+                Char('s') => Action::ShowStats,
+                Char('f') => Action::SwitchMode(Mode::Focus),
+                Char('S') => Action::Sync,
+                Char('q') => Action::Quit,
+                Char('e') => Action::SwitchMode(Mode::Edit),
+                Char('E') => Action::EditInEditor,
+                Char('O') => Action::EditFile,
+                Char('R') => Action::Review,
+                Char('!') => Action::ShowErrors,
+                Char('w') => Action::RetrySave,
+                Char('/') => Action::StartSearch,
+                Char(':') => Action::SwitchMode(Mode::Command),
+                Char('n') if app.search.is_some() => Action::NextMatch,
+                Char('N') if app.search.is_some() => Action::PreviousMatch,
+                Char('F') if app.search.is_some() => Action::ToggleFiltering,
+                Char('T') => Action::ToggleTheme,
+                Char('V') => Action::SwitchView,
+                Char('C') => Action::ToggleColumns,
+                Char('b') => Action::ToggleSidebar,
+                KeyCode::Tab if app.sidebar.visible => Action::SwitchMode(Mode::Sidebar),
+                Char('W') => Action::MoveToBucket(Bucket::Waiting),
+                Char('Z') => Action::MoveToBucket(Bucket::Someday),
+                Char('u') => Action::Undo,
+                Char('+') => Action::BumpPriority(true),
+                Char('-') => Action::BumpPriority(false),
+                Char('>') => Action::ShiftDue(Shift::Day),
+                Char('}') => Action::ShiftDue(Shift::Week),
+                Char('M') => Action::ShiftDue(Shift::Monday),
+                Char('X') => Action::ShiftDue(Shift::Clear),
+                KeyCode::Esc if app.search.is_some() => Action::ClearSearch,
+                KeyCode::Enter => Action::SwitchMode(Mode::Create),
+                Char(c) => match plugins::get().and_then(|p| p.key(c)) {
+                    Some(action) => Action::RunPlugin(action.to_owned()),
+                    None => Action::None,
+                },
+                _ => Action::None,
+            },
+            Mode::Create => match key.code {
+                KeyCode::Esc => Action::ClearNewTask,
+                KeyCode::Enter => Action::AddTask,
+                KeyCode::Tab if app.duplicate.is_some() => Action::JumpToDuplicate,
+                _ => Action::HandleInputKey(event),
+            },
+            Mode::Search => match key.code {
+                KeyCode::Esc => Action::ClearSearch,
+                KeyCode::Enter => Action::ConfirmSearch,
+                Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::ToggleRegex,
+                _ => Action::HandleSearchKey(event),
+            },
+            Mode::Focus => match key.code {
+                KeyCode::Esc | Char('q') | Char('f') => Action::SwitchMode(Mode::Normal),
+                Char(' ') => Action::ToggleTaskState,
+                Char('t') => Action::ToggleTracking,
+                Char('p') => Action::TogglePomodoro,
+                _ => Action::None,
+            },
+            Mode::Sidebar => match key.code {
+                Char('j') | KeyCode::Down => Action::MoveInSidebar(true),
+                Char('k') | KeyCode::Up => Action::MoveInSidebar(false),
+                KeyCode::Enter | Char(' ') => Action::PickInSidebar,
+                Char('b') => Action::ToggleSidebar,
+                KeyCode::Esc | KeyCode::Tab => Action::SwitchMode(Mode::Normal),
+                _ => Action::None,
+            },
+            Mode::Command => match key.code {
+                KeyCode::Esc => Action::ClearCommand,
+                KeyCode::Enter => Action::RunCommand,
+                _ => Action::HandleCommandKey(event),
+            },
+            Mode::Edit => match key.code {
+                KeyCode::Esc => Action::ClearNewTask,
+                KeyCode::Enter => Action::SaveTask,
+                _ => Action::HandleInputKey(event),
+            },
+            // only ever shown while their popup is on top
+            Mode::Stats
+            | Mode::Errors
+            | Mode::Conflict
+            | Mode::Confirm
+            | Mode::Review
+            | Mode::Batch => Action::None,
+        },
+        _ => Action::None,
+    }
+}
+
+/// Keys go to the topmost popup while one is open
+fn modal_action(app: &App, key: event::KeyEvent) -> Action {
+    let Some(modal) = app.modals.top() else {
+        return Action::None;
+    };
+    match modal {
+        Modal::Stats => match key.code {
+            KeyCode::Esc | Char('q') | Char('s') => Action::CloseModal,
+            _ => Action::None,
+        },
+        Modal::Errors => match key.code {
+            KeyCode::Esc | Char('q') | Char('!') => Action::CloseModal,
+            _ => Action::None,
+        },
+        Modal::Conflict => match key.code {
+            Char('l') => Action::ResolveConflict(Resolution::Local),
+            Char('r') => Action::ResolveConflict(Resolution::Remote),
+            Char('b') => Action::ResolveConflict(Resolution::Both),
+            KeyCode::Esc | Char('q') => Action::DeferConflicts,
+            _ => Action::None,
+        },
+        Modal::Review(_) => match key.code {
+            Char('k') => Action::ReviewTask(Verdict::Keep),
+            Char('t') => Action::ReviewTask(Verdict::Tomorrow),
+            Char('w') => Action::ReviewTask(Verdict::NextWeek),
+            Char('x') | Char(' ') => Action::ReviewTask(Verdict::Done),
+            Char('d') => Action::ReviewTask(Verdict::Drop),
+            Char('s') | Char('j') => Action::ReviewTask(Verdict::Skip),
+            KeyCode::Esc | Char('q') | Char('R') => Action::CloseModal,
+            _ => Action::None,
+        },
+        Modal::Batch(_) => match key.code {
+            Char('y') | KeyCode::Enter => Action::ApplyBatch,
+            Char('n') | Char('q') | KeyCode::Esc => Action::CloseModal,
+            Char('j') | KeyCode::Down => Action::ScrollBatch(true),
+            Char('k') | KeyCode::Up => Action::ScrollBatch(false),
+            _ => Action::None,
+        },
+        Modal::Confirm(confirm) => match key.code {
+            Char('y') => Action::Answer(Answer::Yes),
+            Char('n') => Action::Answer(Answer::No),
+            KeyCode::Esc => Action::Answer(Answer::Cancel),
+            KeyCode::Enter => Action::Answer(confirm.selected()),
+            KeyCode::Tab | KeyCode::Right | Char('l') => Action::SelectAnswer(false),
+            KeyCode::BackTab | KeyCode::Left | Char('h') => Action::SelectAnswer(true),
+            _ => Action::None,
+        },
+    }
+}
+
+/// Tells screen readers what changed in accessible mode, on the line below the status
+fn announce(app: &mut App, text: String) {
+    if theme::accessible() {
+        app.toasts.push(Severity::Info, text);
+    }
+}
+
+/// Accessible mode stops at the ends of the list instead of wrapping around, which a
+/// screen reader user couldn't tell from a jump
+fn stop_at_end(app: &mut App, before: Option<usize>, forward: bool) {
+    let after = app.tasks.state.selected();
+    let wrapped = match (before, after) {
+        (Some(before), Some(after)) => (after < before) == forward && after != before,
+        _ => false,
+    };
+    if !theme::accessible() || !wrapped {
+        return;
+    }
+
+    app.tasks.state.select(before);
+    let text = match forward {
+        true => t("announce-end-of-list"),
+        false => t("announce-start-of-list"),
+    };
+    announce(app, text);
+}
+
+/// Asks `confirm` before going on, a question already open is answered first
+fn ask(app: &mut App, confirm: Confirm) {
+    open_modal(app, Modal::Confirm(confirm));
+}
+
+/// Opens `modal` over the screen, saying so in accessible mode
+fn open_modal(app: &mut App, modal: Modal) {
+    let text = match &modal {
+        Modal::Confirm(confirm) => confirm.message().to_owned(),
+        modal => modal.mode().name(),
+    };
+    if app.modals.open(modal) {
+        announce(app, text);
+    }
+}
+
+/// The mode of the topmost popup, or the one below them all
+fn current_mode(app: &App) -> Mode {
+    app.modals.top().map_or(app.mode.clone(), Modal::mode)
+}
+
+/// Enters `mode`, saying so in accessible mode
+fn switch_mode(app: &mut App, mode: Mode) {
+    announce(app, mode.name());
+    app.mode = mode;
+}
+
+fn update(app: &mut App, action: Action) -> Option<Action> {
+    match &action {
+        Action::Tick | Action::Render => trace!(?action),
+        _ => debug!(?action),
+    }
+
+    match action {
+        Action::Increment => {
+            app.counter += 1;
+        }
+        Action::Decrement => {
+            app.counter -= 1;
+        }
+        Action::NetworkRequestAndThenIncrement => {
+            let tx = app.action_tx.clone();
+            background(app, async move {
+                tokio::time::sleep(Duration::from_secs(5)).await; // simulate network request
+                tx.send(Action::Increment).unwrap();
+            });
+        }
+        Action::NetworkRequestAndThenDecrement => {
+            let tx = app.action_tx.clone();
+            background(app, async move {
+                tokio::time::sleep(Duration::from_secs(1)).await; // simulate network request
+                tx.send(Action::Decrement).unwrap();
+            });
+        }
+
+        Action::NextTask => {
+            let before = app.tasks.state.selected();
+            let (items, settings, view) = (&app.store.items, &app.settings, app.view);
+            let search = app.search.as_ref().filter(|s| s.filters());
+            let today = Local::now().date_naive();
+            match search.is_some() || settings.hide_done || view != ListView::All {
+                true => (app.tasks).next_match(items.len(), |i| {
+                    listed(search, settings, view, today, &items[i])
+                }),
+                false => app.tasks.next(items.len()),
+            }
+            stop_at_end(app, before, true);
+        }
+        Action::PreviousTask => {
+            let before = app.tasks.state.selected();
+            let (items, settings, view) = (&app.store.items, &app.settings, app.view);
+            let search = app.search.as_ref().filter(|s| s.filters());
+            let today = Local::now().date_naive();
+            match search.is_some() || settings.hide_done || view != ListView::All {
+                true => (app.tasks).previous_match(items.len(), |i| {
+                    listed(search, settings, view, today, &items[i])
+                }),
+                false => app.tasks.previous(items.len()),
+            }
+            stop_at_end(app, before, false);
+        }
+
+        Action::HandleCommandKey(event) => text_input::handle_event(&mut app.command, &event),
+        Action::ClearCommand => {
+            app.command.reset();
+            switch_mode(app, Mode::Normal);
+        }
+        Action::RunCommand => {
+            let command = app.command.value().trim().to_owned();
+            app.command.reset();
+            switch_mode(app, Mode::Normal);
+            run_command(app, command);
+        }
+        Action::ScrollBatch(down) => {
+            if let Some(Modal::Batch(batch)) = app.modals.top_mut() {
+                batch.scroll(down);
+            }
+        }
+        Action::ApplyBatch => {
+            let Some(Modal::Batch(batch)) = app.modals.close() else {
+                return None;
+            };
+            apply_batch(app, batch);
+        }
+
+        Action::StartSearch => {
+            app.search.get_or_insert_with(Search::default);
+            switch_mode(app, Mode::Search);
+        }
+
+        Action::HandleSearchKey(event) => {
+            let search = app.search.get_or_insert_with(Search::default);
+            text_input::handle_event(&mut search.input, &event);
+            search.update(Local::now().date_naive(), &app.config.search);
+            select_match(app);
+        }
+
+        Action::ToggleRegex => {
+            let search = app.search.get_or_insert_with(Search::default);
+            search.toggle_regex(Local::now().date_naive(), &app.config.search);
+            select_match(app);
+        }
+
+        Action::ConfirmSearch => {
+            if !app.search.as_ref().is_some_and(|s| s.is_active()) {
+                app.search = None;
+            }
+            let items = &app.store.items;
+            let matches =
+                (app.search.as_ref()).map(|s| items.iter().filter(|t| s.matches(t)).count());
+            match matches {
+                Some(matches) => {
+                    app.mode = Mode::Normal;
+                    let text = t_args("announce-matches", &[("count", matches.into())]);
+                    announce(app, text);
+                }
+                None => switch_mode(app, Mode::Normal),
+            }
+        }
+
+        Action::ClearSearch => {
+            app.search = None;
+            switch_mode(app, Mode::Normal);
+        }
+
+        Action::NextMatch => {
+            let search = app.search.as_ref()?;
+            let items = &app.store.items;
+            app.tasks
+                .next_match(items.len(), |i| search.matches(&items[i]));
+        }
+        Action::PreviousMatch => {
+            let search = app.search.as_ref()?;
+            let items = &app.store.items;
+            app.tasks
+                .previous_match(items.len(), |i| search.matches(&items[i]));
+        }
+
+        Action::ToggleFiltering => {
+            let search = app.search.as_mut()?;
+            search.filtering = !search.filtering;
+            let text = match search.filtering {
+                true => t("announce-only-matches"),
+                false => t("announce-all-tasks"),
+            };
+            announce(app, text);
+        }
+
+        Action::ToggleTheme => theme::toggle(),
+
+        Action::SwitchView => {
+            app.view = app.view.next();
+            announce(app, app.view.name().to_owned());
+        }
+        Action::ToggleColumns => {
+            app.tasks.columns = !app.tasks.columns;
+            let text = match app.tasks.columns {
+                true => t("announce-columns"),
+                false => t("announce-one-line"),
+            };
+            announce(app, text);
+        }
+
+        Action::ToggleSidebar => {
+            app.sidebar.visible = !app.sidebar.visible;
+            match app.sidebar.visible {
+                true => switch_mode(app, Mode::Sidebar),
+                false => switch_mode(app, Mode::Normal),
+            }
+        }
+        Action::MoveInSidebar(forward) => {
+            let count = sidebar_entries(app).len();
+            app.sidebar.select_next(count, forward);
+        }
+        Action::PickInSidebar => {
+            let entries = sidebar_entries(app);
+            let Some(entry) = app
+                .sidebar
+                .selected
+                .checked_sub(1)
+                .and_then(|i| entries.get(i))
+            else {
+                app.search = None;
+                announce(app, t("announce-all-tasks"));
+                return None;
+            };
+            let today = Local::now().date_naive();
+            let search = Search::new(entry.tag.clone(), true, today, &app.config.search);
+            let args = [
+                ("count", entry.open.into()),
+                ("tag", entry.tag.clone().into()),
+            ];
+            announce(app, t_args("announce-tag", &args));
+            app.search = Some(search);
+            select_match(app);
+        }
+
+        Action::ClearNewTask => {
+            app.new_task.reset();
+            app.duplicate = None;
+            switch_mode(app, Mode::Normal);
+        }
+
+        Action::AddTask => {
+            let value = app.new_task.value().to_owned();
+            // enter again on the same line adds it anyway
+            let warned = app.duplicate.take().is_some_and(|d| d.value == value);
+            if !warned && app.config.confirm.duplicates && !value.starts_with('/') {
+                let text = parse_task(&value, TaskState::Open).text;
+                if let Some(index) = duplicate::find(&app.store.items, &text) {
+                    announce(
+                        app,
+                        t_args(
+                            "announce-already-open",
+                            &[("task", app.store.items[index].text.clone().into())],
+                        ),
+                    );
+                    app.duplicate = Some(Duplicate { value, index });
+                    return None;
+                }
+            }
+            app.new_task.reset();
+            add_tasks(app, &value);
+        }
+
+        Action::JumpToDuplicate => {
+            let index = app.duplicate.take()?.index;
+            app.new_task.reset();
+            switch_mode(app, Mode::Normal);
+            let task = app.store.items.get(index)?;
+            if !app.view.lists(task, Local::now().date_naive()) {
+                app.view = ListView::All;
+            }
+            // a filtering search could hide it
+            if app
+                .search
+                .as_ref()
+                .is_some_and(|s| s.filters() && !s.matches(task))
+            {
+                app.search = None;
+            }
+            app.tasks.state.select(Some(index));
+        }
+
+        Action::InsertTask(value) => add_tasks(app, &value),
+        Action::ImportTasks(tasks) => insert_tasks(app, tasks),
+        Action::SetTaskState(id, state) => {
+            let index = find(app, &id)?;
+            if app.store.items[index].state != state {
+                change_state(app, index);
+            }
+        }
+        Action::RemoveTask(id) => {
+            let index = find(app, &id)?;
+            delete(app, index);
+        }
+        Action::EditTask(id, text) => {
+            let index = find(app, &id)?;
+            let original = &app.store.items[index];
+            if original.get_meta("issue").is_some() {
+                app.toasts.push(Severity::Info, t("toast-issue-title"));
+                return None;
+            }
+
+            let edited = parse_task(&text, TaskState::Open);
+            let mut task = original.clone();
+            task.text = edited.text;
+            for (key, value) in edited.meta {
+                task.set_meta(&key, Some(value));
+            }
+            let today = Local::now().date_naive();
+            task.resolve_dates(today);
+            if task == *original {
+                return None;
+            }
+            task.touch(today);
+
+            let label = t_args("undo-edit", &[("task", original.text.clone().into())]);
+            app.undo.begin(label, &app.store.items);
+            announce(
+                app,
+                t_args("announce-edited", &[("task", task.text.clone().into())]),
+            );
+            app.store.items[index] = task;
+            save(app);
+        }
+
+        // they replace the whole list, which isn't complete yet
+        Action::Sync
+        | Action::RunPlugin(_)
+        | Action::PurgeDone
+        | Action::Undo
+        | Action::EditFile
+            if app.loading.is_some() =>
+        {
+            app.toasts.push(Severity::Info, t("toast-still-loading"));
+        }
+
+        Action::EditInEditor => {
+            let index = app.tasks.state.selected()?;
+            return Some(Action::RunEditor(Box::new(app.store.items[index].clone())));
+        }
+        // the run loop owns the terminal
+        Action::RunEditor(_) => {}
+        Action::TaskEdited(original, buffer) => {
+            // tasks may have come and gone while the editor was open
+            let Some(index) = app.store.items.iter().position(|t| *t == *original) else {
+                app.toasts.push(Severity::Warning, t("toast-edit-dropped"));
+                return None;
+            };
+            let Some(mut task) = editor::apply_buffer(&original, &buffer) else {
+                announce(app, t("announce-edit-empty"));
+                return None;
+            };
+            let today = Local::now().date_naive();
+            task.resolve_dates(today);
+            if task == *original {
+                return None;
+            }
+            task.touch(today);
+
+            let label = t_args("undo-edit", &[("task", original.text.clone().into())]);
+            app.undo.begin(label, &app.store.items);
+            announce(
+                app,
+                t_args("announce-edited", &[("task", task.text.clone().into())]),
+            );
+            app.store.items[index] = task;
+            save(app);
+        }
+
+        Action::Review => {
+            let today = Local::now().date_naive();
+            let items = &app.store.items;
+            let tasks: Vec<TaskItem> = (items.iter())
+                .filter(|t| stale::is_stale(t, today))
+                .cloned()
+                .collect();
+            match tasks.is_empty() {
+                true => app.toasts.push(Severity::Info, t("toast-no-stale")),
+                false => open_modal(app, Modal::Review(Review::new(tasks))),
+            }
+        }
+        Action::ReviewTask(verdict) => {
+            let Some(Modal::Review(review)) = app.modals.top_mut() else {
+                return None;
+            };
+            let task = review.current().cloned();
+            review.advance();
+            if review.current().is_none() {
+                app.modals.close();
+                announce(app, t("announce-review-finished"));
+            }
+            // gone or changed since the review started
+            let index = task.and_then(|task| app.store.items.iter().position(|t| *t == task))?;
+            review_task(app, index, verdict);
+        }
+
+        Action::EditFile => {
+            // the editor would show the file without them, and reading it back drop them
+            if app.saved < app.saves || app.pending_save.is_some() || app.dirty {
+                app.toasts.push(Severity::Info, t("toast-not-saved-yet"));
+                return None;
+            }
+            return Some(Action::RunFileEditor);
+        }
+        Action::RunFileEditor => {}
+        Action::FileEdited(tasks, modified, settings) => {
+            app.disk_modified = modified;
+            theme::set_accent(settings.accent.map(|a| a.0));
+            app.settings = *settings;
+            if tasks == app.store.items {
+                return None;
+            }
+
+            app.undo.begin(t("undo-edit-file"), &app.store.items);
+            let old = std::mem::replace(&mut app.store.items, tasks);
+            app.undo.commit(&app.store.items);
+            keep_selection(app, &old);
+            let file = app.store.file_path().to_owned();
+            announce(app, t_args("announce-reloaded", &[("file", file.into())]));
+        }
+
+        Action::RunPlugin(action) => {
+            let plugins = plugins::get()?;
+            let selected = app.tasks.state.selected();
+            match plugins.run(&action, &app.store.items, selected) {
+                Ok(Outcome::Tasks(tasks)) => {
+                    let label = t_args("undo-plugin", &[("action", action.clone().into())]);
+                    app.undo.begin(label, &app.store.items);
+                    app.store.items = tasks;
+                    if selected.is_some_and(|i| i >= app.store.items.len()) {
+                        app.tasks.state.select(app.store.items.len().checked_sub(1));
+                    }
+                    save(app);
+                }
+                Ok(Outcome::Message(message)) => app.toasts.push(Severity::Info, message),
+                Ok(Outcome::Nothing) => {}
+                Err(e) => {
+                    let text = t_args("toast-plugin-failed", &[("error", e.to_string().into())]);
+                    app.toasts.push(Severity::Error, text)
+                }
+            }
+        }
+
+        Action::HandleInputKey(event) => {
+            text_input::handle_event(&mut app.new_task, &event);
+            if app
+                .duplicate
+                .as_ref()
+                .is_some_and(|d| d.value != app.new_task.value())
+            {
+                app.duplicate = None;
+            }
+        }
+
+        Action::ToggleTaskState => {
+            if let Some(index) = app.tasks.state.selected() {
+                change_state(app, index);
+            }
+        }
+
+        Action::BumpPriority(up) => {
+            let index = app.tasks.state.selected()?;
+            let mut task = app.store.items[index].clone();
+            task.bump_priority(&app.config.priorities(), up);
+            if task == app.store.items[index] {
+                let text = match up {
+                    true => t("announce-highest-priority"),
+                    false => t("announce-lowest-priority"),
+                };
+                announce(app, text);
+                return None;
+            }
+            task.touch(Local::now().date_naive());
+
+            let name = task.text.clone();
+            let label = t_args("undo-priority", &[("task", name.clone().into())]);
+            app.undo.begin(label, &app.store.items);
+            let text = match task.priority() {
+                Some(priority) => t_args(
+                    "announce-priority",
+                    &[
+                        ("priority", priority.to_owned().into()),
+                        ("task", name.into()),
+                    ],
+                ),
+                None => t_args("announce-no-priority", &[("task", name.into())]),
+            };
+            app.store.items[index] = task;
+            resort(app, SortOrder::Priority);
+            announce(app, text);
+            save(app);
+        }
+
+        Action::MoveToBucket(bucket) => {
+            let index = app.tasks.state.selected()?;
+            let task = &app.store.items[index];
+            // moving it to the bucket it is in brings it back among the active tasks
+            let bucket = Some(bucket).filter(|b| task.bucket() != Some(*b));
+            let name = task.text.clone();
+            let text = match bucket {
+                Some(bucket) => t_args(
+                    "toast-moved",
+                    &[
+                        ("bucket", bucket.name().into()),
+                        ("task", name.clone().into()),
+                    ],
+                ),
+                None => t_args("toast-active-again", &[("task", name.clone().into())]),
+            };
+            let label = t_args("undo-move", &[("task", name.into())]);
+            app.undo.begin(label, &app.store.items);
+
+            let today = Local::now().date_naive();
+            let end = index + 1 + task_item::subtasks(&app.store.items, index).len();
+            for task in &mut app.store.items[index..end] {
+                task.set_bucket(bucket);
+                task.touch(today);
+            }
+            app.toasts.push(Severity::Info, text);
+            save(app);
+        }
+
+        Action::ShiftDue(shift) => {
+            let index = app.tasks.state.selected()?;
+            let today = Local::now().date_naive();
+            let mut task = app.store.items[index].clone();
+            let due = shift.apply(task.due(), today);
+            task.set_meta("due", due.map(|d| d.format(dates::FORMAT).to_string()));
+            if task == app.store.items[index] {
+                return None;
+            }
+            task.touch(today);
+
+            let name = task.text.clone();
+            let label = t_args("undo-due", &[("task", name.clone().into())]);
+            app.undo.begin(label, &app.store.items);
+            let text = match due {
+                Some(due) => t_args(
+                    "announce-due",
+                    &[("date", i18n::date(due).into()), ("task", name.into())],
+                ),
+                None => t_args("announce-no-due", &[("task", name.into())]),
+            };
+            app.store.items[index] = task;
+            resort(app, SortOrder::Due);
+            announce(app, text);
+            save(app);
+        }
+
+        Action::DeleteTask => {
+            if let Some(index) = app.tasks.state.selected() {
+                delete(app, index);
+            }
+        }
+
+        Action::AskDelete => {
+            let index = app.tasks.state.selected()?;
+            if !app.config.confirm.delete {
+                return Some(Action::DeleteTask);
+            }
+            let text = app.store.items[index].text.clone();
+            let message = t_args("confirm-delete", &[("task", text.into())]);
+            ask(app, Confirm::new(message, Action::DeleteTask));
+        }
+
+        Action::AskPurge => {
+            let done = app
+                .store
+                .items
+                .iter()
+                .filter(|t| t.state == TaskState::Done)
+                .count();
+            if done == 0 {
+                app.toasts.push(Severity::Info, t("toast-nothing-to-purge"));
+                return None;
+            }
+            let message = t_args("confirm-purge", &[("count", done.into())]);
+            ask(app, Confirm::new(message, Action::PurgeDone));
+        }
+
+        Action::PurgeDone => {
+            app.undo.begin(t("undo-purge"), &app.store.items);
+            let (done, open): (Vec<_>, Vec<_>) = std::mem::take(&mut app.store.items)
+                .into_iter()
+                .partition(|t| t.state == TaskState::Done);
+            app.store.items = open;
+            for task in &done {
+                log(app, HistoryKind::Delete, task);
+                hook(app, Hook::Delete, task);
+            }
+            announce(
+                app,
+                t_args("announce-purged", &[("count", done.len().into())]),
+            );
+
+            let count = app.store.items.len();
+            if app.tasks.state.selected().is_some_and(|i| i >= count) {
+                app.tasks.state.select(count.checked_sub(1));
+            }
+
+            save(app);
+        }
+
+        Action::Answer(answer) => {
+            let Some(Modal::Confirm(confirm)) = app.modals.top().cloned() else {
+                return None;
+            };
+            app.modals.close();
+            return confirm.answer(answer);
+        }
+
+        Action::SelectAnswer(back) => {
+            if let Some(Modal::Confirm(confirm)) = app.modals.top_mut() {
+                confirm.select_next(back);
+            }
+        }
+
+        Action::ShowErrors => open_modal(app, Modal::Errors),
+
+        Action::CloseModal => {
+            app.modals.close();
+            announce(app, current_mode(app).name());
+        }
+
+        Action::ToggleTracking => {
+            if let Some(index) = app.tasks.state.selected() {
+                let name = app.store.items[index].text.clone();
+                let label = t_args("undo-tracking", &[("task", name.into())]);
+                app.undo.begin(label, &app.store.items);
+                // only one timer runs at a time
+                for (i, task) in app.store.items.iter_mut().enumerate() {
+                    if i != index {
+                        task.stop_tracking();
+                    }
+                }
+                app.store.items[index].toggle_tracking();
+
+                let task = &app.store.items[index];
+                let id = match task.started() {
+                    Some(_) => "announce-tracking",
+                    None => "announce-stopped-tracking",
+                };
+                let text = t_args(id, &[("task", task.text.clone().into())]);
+                announce(app, text);
+
+                save(app);
+            }
+        }
+
+        Action::TogglePomodoro => {
+            app.pomodoro = match (app.pomodoro.take(), app.tasks.state.selected()) {
+                (None, Some(index)) => Some(Pomodoro::new(&app.store.items[index])),
+                _ => None,
+            };
+            let text = match &app.pomodoro {
+                Some(pomodoro) => t_args(
+                    "announce-pomodoro-started",
+                    &[("task", pomodoro.task.clone().into())],
+                ),
+                None => t("announce-pomodoro-stopped"),
+            };
+            announce(app, text);
+        }
+
+        Action::Tick => {
+            reopen_recurring(app);
+            app.toasts.tick(app.tick_period);
+
+            let finished = app.pomodoro.as_mut().and_then(|p| p.tick());
+            if let (Some(finished), Some(pomodoro)) = (finished, &app.pomodoro) {
+                let (title, body) = match finished {
+                    Phase::Focus => {
+                        let entry = HistoryEntry::new(
+                            HistoryKind::Focus,
+                            app.store.file_path(),
+                            &pomodoro.task,
+                        );
+                        append_history(app, entry.task_id(pomodoro.task_id.as_deref()));
+                        (t("notify-focus-done"), t("notify-focus-done-body"))
+                    }
+                    Phase::Break => (t("notify-break-over"), pomodoro.task.clone()),
+                };
+                if app.persist {
+                    notify::desktop(&title, &body);
+                }
+            }
+
+            if app
+                .pending_save
+                .as_ref()
+                .is_some_and(|p| p.retry_at <= Instant::now())
+            {
+                return Some(Action::RetrySave);
+            }
+
+            // written elsewhere, e.g. through `tuitodo serve`, so it's read again before
+            // anything is saved over it; unsaved changes here ask on their next save
+            if !app.dirty
+                && app.loading.is_none()
+                && app.reloading.is_none()
+                && app.pending_save.is_none()
+                && changed_on_disk(app)
+            {
+                let modified = modified(app.store.file_path());
+                if app.reload_tried != modified {
+                    app.reload_tried = modified;
+                    return Some(Action::Reload);
+                }
+            }
+        }
+
+        Action::Sync if !app.persist => app
+            .toasts
+            .push(Severity::Warning, t("toast-sync-not-saved")),
+
+        // the ones put off have to be decided before syncing again
+        Action::Sync if !app.conflicts.is_empty() => open_modal(app, Modal::Conflict),
+
+        Action::Sync if is_github_file(app) => {
+            let mut store = app.store.clone();
+            let config = app.config.github.clone()?;
+            let tx = app.action_tx.clone();
+            app.toasts.push(Severity::Info, t("toast-pulling-issues"));
+            app.sync = SyncStatus::Syncing;
+
+            background(app, async move {
+                let before = store.items.clone();
+                let result = async {
+                    let changed = Github::new(&config)?.pull(&config, &mut store).await?;
+                    Ok::<_, color_eyre::eyre::Error>(changed)
+                };
+                let (severity, message) = match result.await {
+                    Ok(changed) => {
+                        tx.send(Action::Synced(before, store.items)).unwrap();
+                        let args = [("count", changed.into())];
+                        (Severity::Info, t_args("toast-issues-changed", &args))
+                    }
+                    Err(e) => {
+                        let args = [("error", e.to_string().into())];
+                        (
+                            Severity::Error,
+                            t_args("toast-pulling-issues-failed", &args),
+                        )
+                    }
+                };
+                tx.send(Action::SyncFinished(severity != Severity::Error))
+                    .unwrap();
+                tx.send(Action::Notify(severity, message)).unwrap();
+            });
+        }
+
+        Action::Sync => {
+            let mut store = app.store.clone();
+            let config = app.config.sync.clone();
+            let tx = app.action_tx.clone();
+            app.toasts.push(Severity::Info, t("toast-syncing"));
+            app.sync = SyncStatus::Syncing;
+
+            background(app, async move {
+                let before = store.items.clone();
+                let result = sync::sync_configured(&config, &mut store, false).await;
+                // kept even when it failed partway, with the uids of tasks created remotely
+                tx.send(Action::Synced(before, store.items)).unwrap();
+                let (severity, message) = match result {
+                    Ok(report) => {
+                        if !report.conflicts.is_empty() {
+                            tx.send(Action::SyncConflicts(report.conflicts.clone()))
+                                .unwrap();
+                        }
+                        let args = [("summary", report.summary().into())];
+                        (Severity::Info, t_args("toast-synced", &args))
+                    }
+                    Err(e) => {
+                        let args = [("error", e.to_string().into())];
+                        (Severity::Error, t_args("toast-sync-failed", &args))
+                    }
+                };
+                tx.send(Action::SyncFinished(severity != Severity::Error))
+                    .unwrap();
+                tx.send(Action::Notify(severity, message)).unwrap();
+            });
+        }
+
+        Action::SyncFinished(ok) => {
+            app.sync = match ok {
+                true => SyncStatus::Synced(Local::now()),
+                false => SyncStatus::Failed,
+            };
+        }
+
+        Action::ReplaceTasks(items) => {
+            app.store.items = items;
+            let selected = app.tasks.state.selected();
+            if selected.is_some_and(|i| i >= app.store.items.len()) {
+                app.tasks.state.select(app.store.items.len().checked_sub(1));
+            }
+        }
+
+        // written like any other change, the edits made while it ran kept
+        Action::Synced(before, synced) => {
+            let merged = sync::merge(&before, &synced, &app.store.items);
+            if merged != app.store.items {
+                app.undo.begin(t("undo-sync"), &app.store.items);
+                let old = std::mem::replace(&mut app.store.items, merged);
+                keep_selection(app, &old);
+                save(app);
+            }
+        }
+
+        Action::Notify(severity, message) => app.toasts.push(severity, message),
+
+        Action::SyncConflicts(conflicts) => {
+            app.conflicts = conflicts;
+            open_modal(app, Modal::Conflict);
+        }
+
+        Action::DeferConflicts => {
+            app.modals.close_where(|m| matches!(m, Modal::Conflict));
+            let text = t_args(
+                "toast-conflicts-deferred",
+                &[("count", app.conflicts.len().into())],
+            );
+            app.toasts.push(Severity::Info, text);
+        }
+
+        Action::ResolveConflict(resolution) => {
+            if app.conflicts.is_empty() {
+                return None;
+            }
+
+            let conflict = app.conflicts.remove(0);
+            app.undo.begin(t("undo-resolve-conflict"), &app.store.items);
+            conflict::resolve(&mut app.store.items, &conflict, resolution);
+            save(app);
+
+            // push the versions that were kept locally
+            if app.conflicts.is_empty() {
+                app.modals.close_where(|m| matches!(m, Modal::Conflict));
+                return Some(Action::Sync);
+            }
+        }
+
+        Action::ShowStats => {
+            let history = app.history.clone();
+            let tx = app.action_tx.clone();
+            background(app, async move {
+                let entries = history.load().await.unwrap_or_default();
+                tx.send(Action::StatsLoaded(entries)).unwrap();
+            });
+        }
+
+        Action::StatsLoaded(entries) => {
+            app.history_entries = entries;
+            open_modal(app, Modal::Stats);
+        }
+
+        Action::SwitchMode(mode) => {
+            let needs_selection = mode == Mode::Edit || mode == Mode::Focus;
+            let selected = app.tasks.state.selected();
+            if needs_selection && selected.is_none() {
+                return None;
+            }
+
+            let is_issue = selected.is_some_and(|i| app.store.items[i].get_meta("issue").is_some());
+            if mode == Mode::Edit && is_issue {
+                app.toasts.push(Severity::Warning, t("toast-issue-title"));
+                return None;
+            }
+
+            switch_mode(app, mode);
+        }
+
+        Action::ToggleDebug => app.debug = !app.debug,
+
+        Action::Saved(number, modified) => {
+            if number >= app.saved {
+                app.disk_modified = modified;
+            }
+            app.saved = app.saved.max(number);
+            app.dirty = false;
+            if app
+                .pending_save
+                .as_ref()
+                .is_some_and(|p| number >= p.number)
+            {
+                app.pending_save = None;
+                app.toasts.push(Severity::Info, t("toast-saved"));
+            }
+        }
+
+        Action::SaveFailed(number, error) => {
+            // a later save already wrote these changes
+            if number <= app.saved {
+                return None;
+            }
+
+            let attempts = app.pending_save.as_ref().map_or(0, |p| p.attempts) + 1;
+            let delay =
+                (Duration::from_secs(2) * 2u32.pow(attempts.min(6) - 1)).min(MAX_RETRY_DELAY);
+            if attempts == 1 {
+                let text = t_args("toast-save-failed", &[("error", error.clone().into())]);
+                app.toasts.push(Severity::Error, text);
+            } else {
+                warn!("saving failed again: {}", error);
+            }
+
+            app.pending_save = Some(PendingSave {
+                number,
+                attempts,
+                retry_at: Instant::now() + delay,
+            });
+        }
+
+        Action::TasksLoaded(tasks, progress) => {
+            let Some(loading) = &mut app.loading else {
+                return None;
+            };
+            let at = loading.loaded;
+            let count = tasks.len();
+            loading.loaded += count;
+            loading.progress = progress;
+            app.store.items.splice(at..at, tasks);
+
+            // keep tasks added meanwhile selected
+            if let Some(selected) = app.tasks.state.selected().filter(|i| *i >= at) {
+                app.tasks.state.select(Some(selected + count));
+            }
+        }
+
+        Action::LoadFinished(error) => {
+            let loading = app.loading.take()?;
+
+            match error {
+                None => {
+                    let text = t_args("toast-loaded", &[("count", loading.loaded.into())]);
+                    app.toasts.push(Severity::Info, text);
+                    if loading.save_pending {
+                        save(app);
+                    }
+                }
+                // saving now would cut the file off where reading stopped
+                Some(error) => {
+                    app.read_only = true;
+                    app.persist = false;
+                    let text = t_args("toast-reading-stopped", &[("error", error.into())]);
+                    app.toasts.push(Severity::Error, text);
+                }
+            }
+        }
+
+        Action::RetrySave => {
+            let Some(pending) = &mut app.pending_save else {
+                return None;
+            };
+            // not again until this attempt has reported back
+            pending.retry_at = Instant::now() + MAX_RETRY_DELAY;
+            save(app);
+        }
+
+        Action::Undo => match app.undo.undo(&app.store.items) {
+            Undone::Tasks(label, tasks) => {
+                app.store.items = tasks;
+                let count = app.store.items.len();
+                if app.tasks.state.selected().is_some_and(|i| i >= count) {
+                    app.tasks.state.select(count.checked_sub(1));
+                }
+                app.toasts.push(
+                    Severity::Info,
+                    t_args("toast-undid", &[("change", label.into())]),
+                );
+                save(app);
+            }
+            Undone::Nothing => app.toasts.push(Severity::Info, t("toast-nothing-to-undo")),
+            Undone::Changed => app.toasts.push(Severity::Warning, t("toast-undo-changed")),
+        },
+
+        Action::Quit if app.pending_save.is_some() || app.dirty => {
+            let message = t_args("confirm-quit", &[("file", app.store.file_path().into())]);
+            let confirm = Confirm::new(message, Action::QuitNow)
+                .on_no(Action::DiscardAndQuit)
+                .with_cancel()
+                .default_answer(Answer::Yes);
+            ask(app, confirm);
+        }
+
+        Action::Quit | Action::QuitNow => app.should_quit = true,
+
+        Action::DiscardAndQuit => {
+            app.persist = false;
+            app.should_quit = true;
+        }
+
+        Action::Overwrite => {
+            app.disk_modified = None;
+            save(app);
+        }
+
+        // until the tasks are read, saves still ask before overwriting the file
+        Action::Reload => {
+            app.reloading = Some(app.changes);
+            let file = app.store.file_path().to_owned();
+            let tx = app.action_tx.clone();
+            background(app, async move {
+                // taken first, a change while reading is noticed again afterwards
+                let modified = modified(&file);
+                let action = match TaskStore::new(file).await {
+                    Ok(store) => Action::Reloaded(store.items, modified),
+                    Err(e) => Action::ReloadFailed(e.to_string()),
+                };
+                let _ = tx.send(action);
+            });
+        }
+
+        Action::Reloaded(tasks, modified) => {
+            let since = app.reloading.take();
+            // the changes made meanwhile asked about overwriting the file already
+            if since.is_some_and(|changes| changes != app.changes) {
+                return None;
+            }
+            app.disk_modified = modified;
+            app.dirty = false;
+            return Some(Action::ReplaceTasks(tasks));
+        }
+
+        Action::ReloadFailed(error) => {
+            app.reloading = None;
+            let text = t_args("toast-reload-failed", &[("error", error.into())]);
+            app.toasts.push(Severity::Error, text);
+        }
+        _ => {}
+    };
+
+    None
+}
+
+/// Does what the review decided for the task at `index`
+fn review_task(app: &mut App, index: usize, verdict: Verdict) {
+    if verdict == Verdict::Skip {
+        return;
+    }
+    let today = Local::now().date_naive();
+    let name = app.store.items[index].text.clone();
+    let label = t_args("undo-review", &[("task", name.into())]);
+    app.undo.begin(label, &app.store.items);
+
+    let task = &mut app.store.items[index];
+    match verdict {
+        Verdict::Keep => task.touch(today),
+        Verdict::Tomorrow | Verdict::NextWeek => {
+            let days = if verdict == Verdict::Tomorrow { 1 } else { 7 };
+            let due = today + chrono::Duration::days(days);
+            task.set_meta("due", Some(due.format(dates::FORMAT).to_string()));
+            task.touch(today);
+            let args = [
+                ("date", i18n::date(due).into()),
+                ("task", app.store.items[index].text.clone().into()),
+            ];
+            announce(app, t_args("announce-due", &args));
+        }
+        Verdict::Done => toggle(app, index),
+        Verdict::Drop => {
+            let task = app.store.items.remove(index);
+            log(app, HistoryKind::Delete, &task);
+            hook(app, Hook::Delete, &task);
+            announce(
+                app,
+                t_args("announce-deleted", &[("task", task.text.into())]),
+            );
+            let selected = app.tasks.state.selected();
+            if selected.is_some_and(|i| i >= app.store.items.len()) {
+                app.tasks.state.select(app.store.items.len().checked_sub(1));
+            }
+        }
+        Verdict::Skip => {}
+    }
+    save(app);
+}
+
+/// Runs a line typed after `:`, for now only `map <change>` on the listed tasks
+fn run_command(app: &mut App, command: String) {
+    let (name, args) = command.split_once(' ').unwrap_or((&command, ""));
+    let transforms = match name {
+        "" => return,
+        "map" => Transform::parse(args),
+        _ => Err(t_args("toast-unknown-command", &[("command", name.into())])),
+    };
+    let transforms = match transforms {
+        Ok(transforms) => transforms,
+        Err(e) => {
+            return app
+                .toasts
+                .push(Severity::Error, format!(":{}: {}", name, e))
+        }
+    };
+
+    // the tasks the list shows, all of them without a filtering search
+    let (items, settings) = (&app.store.items, &app.settings);
+    let search = app.search.as_ref().filter(|s| s.is_active());
+    let today = Local::now().date_naive();
+    let targets =
+        (0..items.len()).filter(|&i| listed(search, settings, app.view, today, &items[i]));
+    let batch = Batch::new(command, &transforms, items, targets, today);
+    match batch.changes.is_empty() {
+        true => (app.toasts).push(Severity::Info, t("toast-batch-empty")),
+        false => open_modal(app, Modal::Batch(batch)),
+    }
+}
+
+/// Makes the changes of `batch` to the tasks that are still as they were shown
+fn apply_batch(app: &mut App, batch: Batch) {
+    let items = &app.store.items;
+    let changes: Vec<_> = (batch.changes.into_iter())
+        .filter(|c| items.get(c.index) == Some(&c.before))
+        .collect();
+    let text = match changes.len() {
+        0 => t("toast-batch-outdated"),
+        n => t_args("toast-batch-done", &[("count", n.into())]),
+    };
+    app.toasts.push(Severity::Info, text);
+    if changes.is_empty() {
+        return;
+    }
+
+    let today = Local::now().date_naive();
+    app.undo.begin(batch.command, &app.store.items);
+    for change in changes {
+        let mut task = change.after;
+        task.touch(today);
+        app.store.items[change.index] = task;
+    }
+    save(app);
+}
+
+/// Sorts the tasks again when the file is sorted by `order`, which a change just affected;
+/// the selected task moves along
+fn resort(app: &mut App, order: SortOrder) {
+    // a list still being read stays as it is written
+    if app.settings.sort != Some(order) || app.loading.is_some() {
+        return;
+    }
+    let old = app.store.items.clone();
+    order.sort(&mut app.store.items, &app.config.priorities());
+    keep_selection(app, &old);
+}
+
+/// Selects the task that was selected among `old` in the tasks that replaced them, or the
+/// one in its place when it is gone
+fn keep_selection(app: &mut App, old: &[TaskItem]) {
+    let Some(selected) = app.tasks.state.selected().and_then(|i| old.get(i)) else {
+        return;
+    };
+    let items = &app.store.items;
+    let index = (items.iter().position(|t| t == selected))
+        .or_else(|| items.iter().position(|t| t.text == selected.text))
+        .or_else(|| {
+            app.tasks
+                .state
+                .selected()
+                .map(|i| i.min(items.len().saturating_sub(1)))
+        })
+        .filter(|_| !items.is_empty());
+    app.tasks.state.select(index);
+}
+
+/// The task with the id `id`, a toast when it is gone
+fn find(app: &mut App, id: &str) -> Option<usize> {
+    let index = app.store.items.iter().position(|t| t.id() == Some(id));
+    if index.is_none() {
+        let text = t_args("toast-no-task", &[("id", id.to_owned().into())]);
+        app.toasts.push(Severity::Warning, text);
+    }
+    index
+}
+
+/// Completes or reopens the task at `index` and its parents, as one step to undo
+fn change_state(app: &mut App, index: usize) {
+    let task = &app.store.items[index];
+    let id = match task.state {
+        TaskState::Open => "undo-complete",
+        TaskState::Done => "undo-reopen",
+    };
+    let label = t_args(id, &[("task", task.text.clone().into())]);
+    app.undo.begin(label, &app.store.items);
+    toggle(app, index);
+    update_parents(app, task_item::parent(&app.store.items, index));
+
+    let task = &app.store.items[index];
+    let id = match task.state {
+        TaskState::Done => "announce-done",
+        TaskState::Open => "announce-open",
+    };
+    announce(app, t_args(id, &[("task", task.text.clone().into())]));
+
+    save(app);
+}
+
+/// Deletes the task at `index`, the selection stays on the task it was on
+fn delete(app: &mut App, index: usize) {
+    let name = app.store.items[index].text.clone();
+    let label = t_args("undo-delete", &[("task", name.into())]);
+    app.undo.begin(label, &app.store.items);
+    let parent = task_item::parent(&app.store.items, index);
+    let task = app.store.items.remove(index);
+    update_parents(app, parent);
+    if let Some(loading) = app.loading.as_mut().filter(|l| index < l.loaded) {
+        loading.loaded -= 1;
+    }
+    log(app, HistoryKind::Delete, &task);
+    hook(app, Hook::Delete, &task);
+    announce(
+        app,
+        t_args("announce-deleted", &[("task", task.text.clone().into())]),
+    );
+
+    let len = app.store.items.len();
+    match app.tasks.state.selected() {
+        _ if len == 0 => app.tasks.state.select(None),
+        Some(selected) if selected > index => app.tasks.state.select(Some(selected - 1)),
+        Some(selected) if selected >= len => app.tasks.state.select(Some(len - 1)),
+        _ => {}
+    }
+
+    save(app);
+}
+
+/// Completes or reopens the task at `index`, with everything that comes with it
+fn toggle(app: &mut App, index: usize) {
+    app.store.items[index].toggle_state();
+    app.store.items[index].record_state_change(Local::now().date_naive());
+
+    let kind = match app.store.items[index].state {
+        TaskState::Done => {
+            app.store.items[index].stop_tracking();
+            hook(app, Hook::Complete, &app.store.items[index]);
+            HistoryKind::Complete
+        }
+        TaskState::Open => {
+            // picked up again, so not stale either
+            app.store.items[index].touch(Local::now().date_naive());
+            HistoryKind::Uncomplete
+        }
+    };
+    log(app, kind, &app.store.items[index]);
+    update_issue(app, &app.store.items[index]);
+}
+
+/// Completes the parents from `parent` up whose subtasks are all done and reopens the ones
+/// with an open subtask, when the file asks for it
+fn update_parents(app: &mut App, mut parent: Option<usize>) {
+    if !app.settings.complete_parents {
+        return;
+    }
+    while let Some(index) = parent {
+        let Some((done, total)) = task_item::progress(&app.store.items, index) else {
+            break;
+        };
+        let state = match done == total {
+            true => TaskState::Done,
+            false => TaskState::Open,
+        };
+        if app.store.items[index].state != state {
+            toggle(app, index);
+        }
+        parent = task_item::parent(&app.store.items, index);
+    }
+}
+
+/// The tags of the tasks in the current view, the sidebar lists them
+fn sidebar_entries(app: &App) -> Vec<sidebar::Entry> {
+    let today = Local::now().date_naive();
+    let tasks =
+        (app.store.items.iter()).filter(|t| app.view.lists(t, today) && app.settings.lists(t));
+    sidebar::entries(tasks, sidebar_filter(app))
+}
+
+/// The tag the list is filtered by when the search is just that, as the sidebar picks it
+fn sidebar_filter(app: &App) -> Option<&str> {
+    let query = app.search.as_ref().filter(|s| s.filters())?.query().trim();
+    let tag = query.len() > 1 && query.starts_with(['#', '@']) && !query.contains(' ');
+    tag.then_some(query)
+}
+
+/// Whether `task` is a row of the list in `view`, given the filtering search if there is one
+fn listed(
+    search: Option<&Search>,
+    settings: &FileSettings,
+    view: ListView,
+    today: NaiveDate,
+    task: &TaskItem,
+) -> bool {
+    view.lists(task, today) && settings.lists(task) && search.is_none_or(|s| s.matches(task))
+}
+
+/// Moves the selection on to the next match while the search is typed, unless the selected
+/// task still matches
+fn select_match(app: &mut App) {
+    let Some(search) = &app.search else {
+        return;
+    };
+    let items = &app.store.items;
+    let selected = app.tasks.state.selected();
+    if !selected.is_some_and(|i| search.matches(&items[i])) {
+        app.tasks
+            .next_match(items.len(), |i| search.matches(&items[i]));
+    }
+}
+
+/// Adds a typed line, or the tasks of a template for `/name`
+fn add_tasks(app: &mut App, value: &str) {
+    insert_tasks(app, app.config.new_tasks(value));
+}
+
+/// Adds the tasks as one step to undo, tagged and dated the way the file asks for
+fn insert_tasks(app: &mut App, new_tasks: Vec<TaskItem>) {
+    if new_tasks.is_empty() {
+        return;
+    }
+
+    let (text, label) = match new_tasks.as_slice() {
+        [task] => {
+            let args = [("task", task.text.clone().into())];
+            (t_args("announce-added", &args), t_args("undo-add", &args))
+        }
+        tasks => {
+            let args = [("count", tasks.len().into())];
+            (
+                t_args("announce-added-many", &args),
+                t_args("undo-add-many", &args),
+            )
+        }
+    };
+    app.undo.begin(label, &app.store.items);
+    announce(app, text);
+
+    let today = Local::now().date_naive();
+    for mut task in new_tasks {
+        app.settings.tag(&mut task);
+        task.resolve_dates(today);
+        task.mark_created(today);
+        if task.bucket().is_none() {
+            task.set_bucket(app.view.bucket());
+        }
+        log(app, HistoryKind::Add, &task);
+        hook(app, Hook::Add, &task);
+        if !app.view.lists(&task, today) {
+            let text = t_args("toast-not-listed", &[("task", task.text.clone().into())]);
+            app.toasts.push(Severity::Info, text);
+        }
+        app.store.items.push(task);
+    }
+
+    save(app);
+}
+
+fn reopen_recurring(app: &mut App) {
+    let today = Local::now().date_naive();
+    let mut changed = false;
+    for task in app.store.items.iter_mut() {
+        changed |= task.reopen_if_due(today);
+    }
+
+    if changed {
+        save(app);
+    }
+}
+
+fn is_github_file(app: &App) -> bool {
+    app.config
+        .github
+        .as_ref()
+        .is_some_and(|github| github.file() == app.store.file_path())
+}
+
+/// Closes or reopens the GitHub issue behind `task` to match its state
+fn update_issue(app: &App, task: &TaskItem) {
+    if !app.persist {
+        return;
+    }
+    let (Some(issue), Some(config)) = (task.get_meta("issue"), &app.config.github) else {
+        return;
+    };
+
+    let issue = issue.to_owned();
+    let state = task.state.clone();
+    let config = config.clone();
+    let tx = app.action_tx.clone();
+    tokio::spawn(async move {
+        let result = match Github::new(&config) {
+            Ok(github) => github.set_state(&issue, &state).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            let args = [("issue", issue.into()), ("error", e.to_string().into())];
+            let text = t_args("toast-issue-update-failed", &args);
+            tx.send(Action::Notify(Severity::Error, text)).unwrap();
+        }
+    });
+}
+
+/// Runs `work` in the background, the engine queues it to run in order instead
+fn background(app: &mut App, work: impl Future<Output = ()> + Send + 'static) {
+    match &mut app.queued {
+        Some(queued) => queued.push_back(Box::pin(work)),
+        None => {
+            tokio::spawn(work);
+        }
+    }
+}
+
+/// Writes the tasks in the background, failed saves are retried until one goes through
+fn save(app: &mut App) {
+    app.undo.commit(&app.store.items);
+    app.changes += 1;
+    if !app.persist {
+        return;
+    }
+    if let Some(loading) = &mut app.loading {
+        loading.save_pending = true;
+        return;
+    }
+    if changed_on_disk(app) {
+        app.dirty = true;
+        let file = app.store.file_path().to_owned();
+        let message = t_args("confirm-overwrite", &[("file", file.into())]);
+        let confirm = Confirm::new(message, Action::Overwrite)
+            .on_no(Action::Reload)
+            .with_cancel()
+            .default_answer(Answer::Cancel);
+        ask(app, confirm);
+        return;
+    }
+    app.saves += 1;
+    let tx = app.action_tx.clone();
+    let writer = app.writer.get_or_insert_with(|| Writer::spawn(tx));
+    writer.save(app.saves, app.store.clone());
+}
+
+/// Whether another program wrote the file since it was read or saved here, which saves
+/// still running would look like too
+fn changed_on_disk(app: &App) -> bool {
+    let Some(known) = app.disk_modified else {
+        return false;
+    };
+    if app.saved < app.saves {
+        return false;
+    }
+    modified(app.store.file_path()) != Some(known)
+}
+
+/// When the file was last written, big files are written through their journal
+fn modified(file: &str) -> Option<SystemTime> {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    modified(Path::new(file)).max(modified(&journal::path(file)))
+}
+
+/// Hands the tasks of a big file to the app in chunks as they are read
+fn read_in_background(app: &mut App) {
+    app.loading = Some(Loading::default());
+    let file = app.store.file_path().to_owned();
+    let tx = app.action_tx.clone();
+    background(app, async move {
+        let result = file::stream_tasks(&file, |tasks, progress| {
+            let _ = tx.send(Action::TasksLoaded(tasks, progress));
+        })
+        .await;
+        let _ = tx.send(Action::LoadFinished(result.err().map(|e| e.to_string())));
+    });
+}
+
+fn log(app: &App, kind: HistoryKind, task: &TaskItem) {
+    let entry = HistoryEntry::new(kind, app.store.file_path(), &task.text);
+    append_history(app, entry.task_id(task.id()));
+}
+
+fn append_history(app: &App, entry: HistoryEntry) {
+    if let (true, Some(appender)) = (app.persist, &app.appender) {
+        appender.append(entry);
+    }
+}
+
+/// Runs the shell hook for `hook`, unless edits aren't written either
+fn hook(app: &App, hook: Hook, task: &TaskItem) {
+    if app.persist {
+        hooks::spawn(hook, app.store.file_path(), task);
+    }
+}
+
+/// Opens `file` in the editor and reads what it was saved as
+async fn edit_file(tui: &mut tui::Tui, file: &str) -> Action {
+    if let Err(e) = editor::edit_file(tui, file).await {
+        return Action::Notify(Severity::Error, e.to_string());
+    }
+    let tasks = match TaskStore::new(file.to_owned()).await {
+        Ok(store) => store.items,
+        Err(e) => {
+            let text = t_args("toast-reload-failed", &[("error", e.to_string().into())]);
+            return Action::Notify(Severity::Error, text);
+        }
+    };
+    let settings = front_matter::settings(file).await.unwrap_or_else(|e| {
+        warn!("{}", e);
+        FileSettings::default()
+    });
+    Action::FileEdited(tasks, modified(file), Box::new(settings))
+}
+
+/// Runs the interface on `file` until it is quit
+pub async fn run(
+    options: Options,
+    config: Config,
+    file: String,
+    remote: Option<Remote>,
+    warnings: Vec<String>,
+) -> Result<()> {
+    let mut toasts = Toasts::default();
+    for warning in warnings {
+        toasts.push(Severity::Warning, warning);
+    }
+
+    let (action_tx, mut action_rx) = mpsc::unbounded_channel(); // new
+
+    if options.accessible {
+        theme::set_accessible();
+    }
+    // asks the terminal, before anything else reads its input
+    theme::init(&config.theme);
+    let today = Local::now().date_naive();
+    for warning in rules::init(&config.theme.rules, &config.search, today) {
+        toasts.push(Severity::Warning, warning);
+    }
+
+    if options.stdin {
+        let content = cli::read_stdin().await?;
+        let tasks = import::parse_lines(&content, Local::now().date_naive());
+        file::append_tasks(&file, &tasks).await?;
+        for task in &tasks {
+            if let Err(e) = cli::log(HistoryKind::Add, &file, task).await {
+                let text = t_args("toast-history-failed", &[("error", e.to_string().into())]);
+                toasts.push(Severity::Warning, text);
+                break;
+            }
+        }
+    }
+
+    if config.git.pull {
+        if let Err(e) = git::pull(&file).await {
+            toasts.push(Severity::Error, e.to_string());
+        }
+    }
+
+    let Some(Opened {
+        store,
+        read_only,
+        stream,
+    }) = startup::open(file.clone(), &config).await?
+    else {
+        return Ok(());
+    };
+    // the remote belongs to the original file, not to one picked instead
+    let remote = remote.filter(|_| store.file_path() == file);
+    let session_file = match &remote {
+        Some(remote) => remote.to_string(),
+        None => store.file_path().to_owned(),
+    };
+    let mut store = store.auto_commit(config.git.auto_commit).remote(remote);
+    let settings = match front_matter::settings(store.file_path()).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            toasts.push(Severity::Warning, e.to_string());
+            FileSettings::default()
+        }
+    };
+    // a file still being read is sorted as it is written
+    if let (Some(order), false) = (settings.sort, stream) {
+        order.sort(&mut store.items, &config.priorities());
+    }
+    theme::set_accent(settings.accent.map(|a| a.0));
+    let disk_modified = modified(store.file_path());
+    // nothing is saved, so there would be nothing to undo on disk either
+    let undo = match read_only {
+        true => Undo::default(),
+        false => Undo::load(store.file_path()).await,
+    };
+    if read_only {
+        let file = store.file_path().to_owned();
+        toasts.push(
+            Severity::Warning,
+            t_args("toast-read-only", &[("file", file.into())]),
+        );
+    }
+
+    if let Err(e) = ipc::listen(store.file_path(), action_tx.clone()).await {
+        let text = t_args(
+            "toast-quick-add-unavailable",
+            &[("error", e.to_string().into())],
+        );
+        toasts.push(Severity::Warning, text);
+    }
+
+    let interface = &config.interface;
+    let tick_rate = rate(
+        "tick rate",
+        options.tick_rate.unwrap_or(interface.tick_rate),
+    )?;
+    let frame_rate = rate(
+        "frame rate",
+        options.frame_rate.unwrap_or(interface.frame_rate),
+    )?;
+    let idle_frame_rate = rate("idle frame rate", interface.idle_frame_rate)?.min(frame_rate);
+    let idle_after = Duration::from_secs(interface.idle_after);
+
+    // ratatui terminal
+    let mut tui = tui::Tui::new()?
+        .tick_rate(tick_rate)
+        .frame_rate(frame_rate)
+        .idle(idle_frame_rate, idle_after);
+    if options.inline {
+        let height = (store.items.len() + 3).clamp(MIN_HEIGHT as usize, INLINE_MAX_HEIGHT as usize);
+        let height = height as u16;
+        tui = tui.inline(height)?;
+    }
+    tui.enter()?;
+
+    let tx = action_tx.clone();
+    let appender = Appender::spawn(History::new(), move |e| {
+        let text = t_args("toast-history-failed", &[("error", e.to_string().into())]);
+        let _ = tx.send(Action::Notify(Severity::Warning, text));
+    });
+
+    let mut app = App {
+        counter: 0,
+        should_quit: false,
+        action_tx: action_tx.clone(),
+        new_task: Input::default(),
+        mode: Mode::Normal,
+        tasks: TaskList::default(),
+        store,
+        config,
+        tick_period: Duration::from_secs_f64(1.0 / tick_rate),
+        history: History::new(),
+        appender: Some(appender),
+        history_entries: vec![],
+        pomodoro: None,
+        inline: options.inline,
+        toasts,
+        conflicts: vec![],
+        persist: !read_only,
+        read_only,
+        debug: false,
+        timings: Timings::default(),
+        saves: 0,
+        saved: 0,
+        pending_save: None,
+        writer: None,
+        queued: None,
+        loading: None,
+        search: None,
+        sync: SyncStatus::Idle,
+        modals: Modals::default(),
+        disk_modified,
+        dirty: false,
+        changes: 0,
+        reloading: None,
+        reload_tried: None,
+        undo,
+        settings,
+        duplicate: None,
+        command: Input::default(),
+        view: ListView::default(),
+        sidebar: Sidebar::default(),
+    };
+
+    let session = Session::load().await;
+    if stream {
+        read_in_background(&mut app);
+    } else if let Some(view) = session.view(app.store.file_path()) {
+        restore_view(&mut app, view);
+    }
+    if !stream && stale::config().remind {
+        let today = Local::now().date_naive();
+        let stale = (app.store.items.iter())
+            .filter(|t| stale::is_stale(t, today))
+            .count();
+        if stale > 0 {
+            let text = t_args("toast-stale", &[("count", stale.into())]);
+            app.toasts.push(Severity::Info, text);
+        }
+    }
+
+    // the terminal may be gone by then, but the tasks can still be saved
+    let mut terminated = false;
+    loop {
+        let e = tui.next().await.unwrap();
+        match e {
+            tui::Event::Tick => action_tx.send(Action::Tick)?,
+            tui::Event::Render => action_tx.send(Action::Render)?,
+            // redraw right away instead of stretching the old frame until the next one
+            tui::Event::Resize => action_tx.send(Action::Render)?,
+            tui::Event::Suspend => action_tx.send(Action::Suspend)?,
+            tui::Event::Terminate => {
+                terminated = true;
+                break;
+            }
+            tui::Event::Key(..) => {
+                let action = get_action(&app, e);
+                action_tx.send(action.clone())?;
+            }
+            _ => {}
+        };
+
+        while let Ok(action) = action_rx.try_recv() {
+            let mut maybe_action = Some(action);
+
+            while let Some(act) = maybe_action {
+                let started = Instant::now();
+                let next_action = update(&mut app, act.clone());
+                if app.debug {
+                    app.timings.record_action(act.name(), started.elapsed());
+                }
+
+                if let Action::Suspend = act {
+                    tui.suspend()?;
+                }
+
+                if let Action::RunFileEditor = act {
+                    action_tx.send(edit_file(&mut tui, app.store.file_path()).await)?;
+                }
+
+                if let Action::RunEditor(task) = &act {
+                    let action = match editor::edit(&mut tui, &editor::task_buffer(task)).await {
+                        Ok(buffer) => Action::TaskEdited(task.clone(), buffer),
+                        Err(e) => Action::Notify(Severity::Error, e.to_string()),
+                    };
+                    action_tx.send(action)?;
+                }
+
+                if let Action::Render = act {
+                    let started = Instant::now();
+                    tui.draw(|f| {
+                        ui(f, &mut app);
+                    })?;
+                    app.timings.frame = started.elapsed();
+                }
+                maybe_action = next_action;
+            }
+        }
+
+        if app.should_quit {
+            break;
+        }
+    }
+
+    if app.inline && !terminated {
+        tui.draw(|f| {
+            list::ui(
+                f,
+                f.size(),
+                &app.store.items,
+                &mut TaskList::default(),
+                None,
+                &app.settings,
+                app.view,
+            );
+        })?;
+    }
+
+    match tui.exit() {
+        Err(e) if terminated => warn!("restoring the terminal failed: {}", e),
+        result => result?,
+    }
+    ipc::cleanup(app.store.file_path()).await;
+
+    let view = current_view(&app);
+    if let Err(e) = session
+        .save(&session_file, app.store.file_path(), view)
+        .await
+    {
+        warn!("saving the session failed: {}", e);
+    }
+
+    // changes made while loading can only be saved with the whole file read
+    while app.loading.as_ref().is_some_and(|l| l.save_pending) {
+        match action_rx.recv().await {
+            Some(action @ (Action::TasksLoaded(..) | Action::LoadFinished(_))) => {
+                update(&mut app, action);
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    // saves still running or waiting for a retry would be lost on exit
+    let file = app.store.file_path().to_owned();
+    let written = match app.writer.take() {
+        Some(writer) => writer.finish().await.max(app.saved),
+        None => app.saved,
+    };
+    if app.persist && terminated && app.dirty {
+        // the file changed elsewhere and there is nobody left to ask about saving over it
+        let unsaved = file::write_unsaved(&file, &app.store.items)
+            .await
+            .map_err(|e| eyre!("{} wasn't saved: {}", file, e))?;
+        warn!(
+            "{} changed elsewhere, the changes made here are in {}",
+            file, unsaved
+        );
+    } else if app.persist && (app.pending_save.is_some() || written < app.saves || app.dirty) {
+        app.store
+            .save()
+            .await
+            .map_err(|e| eyre!("{} wasn't saved: {}", file, e))?;
+    }
+    if let Some(appender) = app.appender.take() {
+        appender.finish().await;
+    }
+    if app.persist {
+        journal::compact(&file).await?;
+        if let Err(e) = app.undo.flush().await {
+            warn!("writing the undo journal failed: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Goes back to where `view` left the file, as far as its tasks still allow
+fn restore_view(app: &mut App, view: &View) {
+    let count = app.store.items.len();
+    let selected = view.selected.filter(|i| *i < count);
+    app.tasks.state.select(selected);
+    *app.tasks.state.offset_mut() = view.offset.min(count.saturating_sub(1));
+
+    if let Some(query) = &view.search {
+        let today = Local::now().date_naive();
+        let search = Search::new(query.clone(), view.filtering, today, &app.config.search);
+        app.search = Some(search).filter(|s| s.is_active());
+    }
+    if view.focus && selected.is_some() {
+        app.mode = Mode::Focus;
+    }
+}
+
+fn current_view(app: &App) -> View {
+    let search = app.search.as_ref().filter(|s| s.is_active());
+    View {
+        closed: Local::now(),
+        selected: app.tasks.state.selected(),
+        offset: app.tasks.state.offset(),
+        focus: app.mode == Mode::Focus,
+        search: search.map(|s| s.query().to_owned()),
+        filtering: search.is_some_and(|s| s.filtering),
+    }
+}
+
+/// `value` as a rate per second, which has to be above 0
+fn rate(name: &str, value: f64) -> Result<f64> {
+    match value > 0.0 && value.is_finite() {
+        true => Ok(value),
+        false => Err(eyre!("{} must be above 0, not {}", name, value)),
+    }
+}
+
+/// The state of the app between two actions
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub mode: Mode,
+    pub tasks: Vec<TaskItem>,
+    pub selected: Option<usize>,
+    /// Text typed into the create or edit input, or the search being typed
+    pub input: String,
+    pub message: Option<String>,
+    pub quit: bool,
+}
+
+/// Drives the app without a terminal, one key or action at a time
+///
+/// Edits aren't written to disk and nothing reaches outside the app, no hooks, history or
+/// syncing, `into_store` hands the tasks back for saving. Background work, like reloading the
+/// file, runs to the end one piece at a time before a key or action returns.
+pub struct Engine {
+    app: App,
+    action_rx: UnboundedReceiver<Action>,
+}
+
+impl Engine {
+    pub fn new(store: TaskStore, config: Config) -> Self {
+        let (action_tx, action_rx) = mpsc::unbounded_channel();
+        let app = App {
+            counter: 0,
+            should_quit: false,
+            action_tx,
+            new_task: Input::default(),
+            mode: Mode::Normal,
+            tasks: TaskList::default(),
+            store,
+            config,
+            // scripts let a second pass with each tick
+            tick_period: Duration::from_secs(1),
+            history: History::new(),
+            appender: None,
+            history_entries: vec![],
+            pomodoro: None,
+            inline: false,
+            toasts: Toasts::default(),
+            conflicts: vec![],
+            persist: false,
+            read_only: false,
+            debug: false,
+            timings: Timings::default(),
+            saves: 0,
+            saved: 0,
+            pending_save: None,
+            writer: None,
+            queued: Some(VecDeque::new()),
+            loading: None,
+            search: None,
+            sync: SyncStatus::Idle,
+            modals: Modals::default(),
+            disk_modified: None,
+            dirty: false,
+            changes: 0,
+            reloading: None,
+            reload_tried: None,
+            undo: Undo::default(),
+            settings: FileSettings::default(),
+            duplicate: None,
+            command: Input::default(),
+            view: ListView::default(),
+            sidebar: Sidebar::default(),
+        };
+
+        Self { app, action_rx }
+    }
+
+    /// Handles a key press the way the interface would in the current mode
+    pub async fn key(&mut self, key: event::KeyEvent) -> Snapshot {
+        let action = get_action(&self.app, Event::Key(key, event::Event::Key(key)));
+        self.dispatch(action).await
+    }
+
+    /// Runs `action` and whatever it leads to, including the background work it starts
+    pub async fn dispatch(&mut self, action: Action) -> Snapshot {
+        let mut next = Some(action);
+        loop {
+            while let Some(action) = next {
+                next = update(&mut self.app, action);
+            }
+            if let Ok(action) = self.action_rx.try_recv() {
+                next = Some(action);
+                continue;
+            }
+            match self.app.queued.as_mut().and_then(|q| q.pop_front()) {
+                Some(work) => work.await,
+                None => break,
+            }
+        }
+
+        self.snapshot()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            mode: current_mode(&self.app),
+            tasks: self.app.store.items.clone(),
+            selected: self.app.tasks.state.selected(),
+            input: match (&self.app.mode, &self.app.search) {
+                (Mode::Search, Some(search)) => search.query().to_owned(),
+                _ => self.app.new_task.value().to_owned(),
+            },
+            message: self.app.toasts.current().map(|t| t.text.clone()),
+            quit: self.app.should_quit,
+        }
+    }
+
+    /// Draws the interface into a `width` x `height` grid of text
+    pub fn render(&mut self, width: u16, height: u16) -> Result<String> {
+        let mut terminal = Terminal::new(backend::TestBackend::new(width, height))?;
+        terminal.draw(|f| ui(f, &mut self.app))?;
+
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = (0..height)
+            .map(|y| {
+                let line: String = (0..width).map(|x| buffer.get(x, y).symbol()).collect();
+                line.trim_end().to_owned()
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    pub fn into_store(self) -> TaskStore {
+        self.app.store
+    }
+}
@@ -0,0 +1,266 @@
+use chrono::NaiveDate;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    file::{format_task, parse_task},
+    i18n::{t, t_args},
+    task_item::TaskItem,
+    theme,
+};
+
+/// One change `:map` makes to each task
+#[derive(Clone, Debug)]
+pub enum Transform {
+    /// `s/pattern/replacement/` on the text, with `g` for every match and `i` to ignore case
+    Substitute {
+        regex: Regex,
+        replacement: String,
+        global: bool,
+    },
+    /// `+#tag`
+    AddTag(String),
+    /// `-#tag`
+    RemoveTag(String),
+    /// `pri:A`, or `pri:` to take it away
+    Priority(Option<String>),
+}
+
+impl Transform {
+    /// Reads what follows `map`: a substitution and tag and priority changes, separated by
+    /// spaces like `s/todo/done/ +#work -#later pri:B`
+    pub fn parse(input: &str) -> Result<Vec<Transform>, String> {
+        let input = input.trim();
+        let mut transforms = vec![];
+        let rest = match substitution_delimiter(input) {
+            Some(delimiter) => {
+                let (substitute, rest) = substitution(&input[2..], delimiter)?;
+                transforms.push(substitute);
+                rest
+            }
+            None => input,
+        };
+
+        for term in rest.split_whitespace() {
+            let transform = match term {
+                t if t.starts_with("+#") || t.starts_with("+@") => {
+                    Transform::AddTag(t[1..].to_owned())
+                }
+                t if t.starts_with("-#") || t.starts_with("-@") => {
+                    Transform::RemoveTag(t[1..].to_owned())
+                }
+                "pri:" | "pri:-" => Transform::Priority(None),
+                t => match t.strip_prefix("pri:").map(|p| p.to_uppercase()) {
+                    Some(p) if p.len() == 1 && p.chars().all(|c| c.is_ascii_uppercase()) => {
+                        Transform::Priority(Some(p))
+                    }
+                    _ => return Err(format!("unknown change `{}`", t)),
+                },
+            };
+            transforms.push(transform);
+        }
+        match transforms.is_empty() {
+            true => Err("nothing to change, e.g. `map s/old/new/` or `map +#tag`".to_owned()),
+            false => Ok(transforms),
+        }
+    }
+
+    fn apply(&self, task: &mut TaskItem, today: NaiveDate) {
+        match self {
+            Transform::Substitute {
+                regex,
+                replacement,
+                global,
+            } => {
+                let text = match global {
+                    true => regex.replace_all(&task.text, replacement.as_str()),
+                    false => regex.replace(&task.text, replacement.as_str()),
+                };
+                // a replacement can bring metadata along, like `due:friday`
+                let parsed = parse_task(&text, task.state.clone());
+                task.text = parsed.text;
+                for (key, value) in parsed.meta {
+                    task.set_meta(&key, Some(value));
+                }
+                task.resolve_dates(today);
+            }
+            Transform::AddTag(tag) => {
+                if !task.tags().iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    task.text = format!("{} {}", task.text, tag);
+                }
+            }
+            Transform::RemoveTag(tag) => {
+                let words = task
+                    .text
+                    .split(' ')
+                    .filter(|w| !w.eq_ignore_ascii_case(tag));
+                task.text = words.collect::<Vec<_>>().join(" ");
+            }
+            Transform::Priority(priority) => task.set_meta("pri", priority.clone()),
+        }
+    }
+}
+
+/// `s/…` with any punctuation in place of `/`, as in sed
+fn substitution_delimiter(input: &str) -> Option<char> {
+    let mut chars = input.chars();
+    match (chars.next(), chars.next()) {
+        (Some('s'), Some(d)) if d.is_ascii_punctuation() && d != '\\' => Some(d),
+        _ => None,
+    }
+}
+
+/// `pattern/replacement/flags` and what follows it, a `\` keeps the delimiter in a part
+fn substitution(input: &str, delimiter: char) -> Result<(Transform, &str), String> {
+    let mut parts = vec![String::new()];
+    let mut chars = input.char_indices();
+    let mut end = input.len();
+    while let Some((i, char)) = chars.next() {
+        match char {
+            '\\' if chars.clone().next().map(|(_, c)| c) == Some(delimiter) => {
+                parts.last_mut().unwrap().push(delimiter);
+                chars.next();
+            }
+            c if c == delimiter && parts.len() == 2 => {
+                end = i + c.len_utf8();
+                break;
+            }
+            c if c == delimiter => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    let [pattern, replacement] = <[String; 2]>::try_from(parts)
+        .map_err(|_| format!("expected s{0}pattern{0}replacement{0}", delimiter))?;
+
+    let rest = &input[end..];
+    let (flags, rest) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+    if let Some(flag) = flags.chars().find(|f| !"gi".contains(*f)) {
+        return Err(format!("unknown flag `{}`", flag));
+    }
+    if pattern.is_empty() {
+        return Err("empty pattern".to_owned());
+    }
+
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(flags.contains('i'))
+        .build()
+        .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_owned())?;
+    let substitute = Transform::Substitute {
+        regex,
+        replacement,
+        global: flags.contains('g'),
+    };
+    Ok((substitute, rest))
+}
+
+/// A task `:map` changes
+#[derive(Clone, Debug)]
+pub struct Change {
+    pub index: usize,
+    pub before: TaskItem,
+    pub after: TaskItem,
+}
+
+/// The changes of a `:map` command, shown before they are made
+#[derive(Clone, Debug)]
+pub struct Batch {
+    pub command: String,
+    pub changes: Vec<Change>,
+    /// The first change shown
+    scroll: usize,
+}
+
+impl Batch {
+    /// What `transforms` would make of the tasks at `targets`, the ones left as they are
+    /// aren't changes
+    pub fn new(
+        command: String,
+        transforms: &[Transform],
+        tasks: &[TaskItem],
+        targets: impl Iterator<Item = usize>,
+        today: NaiveDate,
+    ) -> Self {
+        let changes = targets
+            .filter_map(|index| {
+                let before = &tasks[index];
+                let mut after = before.clone();
+                for transform in transforms {
+                    transform.apply(&mut after, today);
+                }
+                (after != *before).then(|| Change {
+                    index,
+                    before: before.clone(),
+                    after,
+                })
+            })
+            .collect();
+        Self {
+            command,
+            changes,
+            scroll: 0,
+        }
+    }
+
+    pub fn scroll(&mut self, down: bool) {
+        self.scroll = match down {
+            true => (self.scroll + 1).min(self.changes.len().saturating_sub(1)),
+            false => self.scroll.saturating_sub(1),
+        };
+    }
+}
+
+/// The lines before and after each change, removed ones red and added ones green
+pub fn ui(f: &mut Frame, area: Rect, batch: &Batch) {
+    let title = t_args(
+        "batch-title",
+        &[
+            ("command", batch.command.clone().into()),
+            ("count", batch.changes.len().into()),
+        ],
+    );
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::palette().accent));
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let line = |sign: &str, task: &TaskItem, color: Color| {
+        let text = format_task(task)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        Line::styled(
+            format!("{} {}", sign, text.trim_start()),
+            Style::default().fg(color),
+        )
+    };
+    let lines: Vec<Line> = batch.changes[batch.scroll..]
+        .iter()
+        .flat_map(|c| {
+            [
+                line("-", &c.before, Color::Red),
+                line("+", &c.after, Color::Green),
+            ]
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), rows[0]);
+
+    f.render_widget(
+        Paragraph::new(t("batch-keys")).style(Style::default().fg(Color::DarkGray)),
+        rows[1],
+    );
+}
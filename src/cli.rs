@@ -0,0 +1,591 @@
+use std::{collections::BTreeMap, process::ExitCode};
+
+use chrono::Local;
+use clap::{Args, Subcommand, ValueEnum};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use tokio::io::AsyncReadExt;
+
+use crate::{
+    config::Config,
+    daemon, dates,
+    export::{self, Numbered},
+    file::{append_tasks, format_task, parse_task, TaskStore},
+    filter::{Filter, SearchConfig},
+    front_matter,
+    github::Github,
+    history::{History, HistoryEntry, HistoryKind},
+    hooks::{self, Hook},
+    import::{parse_ics, parse_lines},
+    ipc::{self, Request},
+    plugins::{self, Outcome},
+    script, secrets, serve, sync,
+    task_item::{TaskItem, TaskState},
+};
+
+/// Commands that work on the todo file without starting the interface
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Append a task to the file and exit
+    Add {
+        /// task text, may contain metadata like `due:tomorrow`
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
+    /// Append tasks from a file of markdown, todo.txt or plain lines, `-` reads stdin
+    Import {
+        /// file to read, `-` for stdin
+        source: String,
+    },
+    /// Print the tasks with their numbers
+    List {
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Plain)]
+        output: OutputFormat,
+    },
+    /// Write the tasks to stdout in another format
+    Export {
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[arg(short = 't', long, value_enum, default_value_t = ExportFormat::Markdown)]
+        format: ExportFormat,
+    },
+    /// Print how many tasks match, exiting with 1 when there are any
+    Count {
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Synchronize with the remote backend from the config
+    Sync,
+    /// Store a backend's password or token in the system keyring
+    Auth {
+        #[arg(value_enum)]
+        target: AuthTarget,
+    },
+    /// Pull GitHub issues assigned to you into the `[github]` file
+    Github,
+    /// Serve the tasks over a local HTTP API with change events
+    Serve {
+        /// address to listen on, `0.0.0.0` makes it reachable from the LAN
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(short, long, default_value_t = 7878)]
+        port: u16,
+        /// require `Authorization: Bearer <token>` on every request
+        #[arg(long, env = "TUITODO_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+    },
+    /// Run an action registered by a plugin on the tasks
+    Plugin { action: String },
+    /// Replay keys and checks from a script against the tasks, `-` reads stdin
+    Script {
+        /// script file, one command per line
+        path: String,
+        /// leave the todo file untouched
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Stay in the background and send reminders and a daily digest as notifications
+    Daemon,
+    /// Print today's and overdue tasks grouped by project
+    Agenda,
+    /// Print a short overdue/open summary for shell prompts
+    Prompt {
+        /// color the counts with ANSI escapes
+        #[arg(long)]
+        color: bool,
+    },
+    /// Mark a task as done
+    Done {
+        /// task number as printed by `list`, or its id
+        task: String,
+    },
+    /// Delete a task
+    Rm {
+        /// task number as printed by `list`, or its id
+        task: String,
+    },
+    /// Replace the text of a task
+    Edit {
+        /// task number as printed by `list`, or its id
+        task: String,
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum OutputFormat {
+    Plain,
+    Json,
+    Tsv,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Markdown,
+    TodoTxt,
+    Json,
+    Ics,
+    Csv,
+}
+
+/// Keyring entries `auth` can set
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum AuthTarget {
+    Caldav,
+    Webdav,
+    Github,
+    Todoist,
+    /// passphrase of `.age` todo files
+    Age,
+}
+
+/// Which tasks a command works on
+#[derive(Args, Debug)]
+pub struct FilterArgs {
+    /// only tasks that aren't done
+    #[arg(long, conflicts_with = "done")]
+    open: bool,
+    /// only tasks that are done
+    #[arg(long)]
+    done: bool,
+    /// only tasks containing this text (case-insensitive)
+    query: Option<String>,
+    /// only tasks matching an expression like `due:<1d and not done`
+    #[arg(long = "filter", value_name = "EXPR")]
+    expression: Option<String>,
+}
+
+impl FilterArgs {
+    fn matches(&self, task: &TaskItem, expression: Option<&Filter>) -> bool {
+        let state_matches = match task.state {
+            TaskState::Open => !self.done,
+            TaskState::Done => !self.open,
+        };
+        let text_matches = self
+            .query
+            .as_ref()
+            .is_none_or(|q| task.text.to_lowercase().contains(&q.to_lowercase()));
+        let expression_matches = expression.is_none_or(|e| e.matches(task));
+
+        state_matches && text_matches && expression_matches
+    }
+
+    fn apply<'a>(&self, store: &'a TaskStore, config: &SearchConfig) -> Result<Numbered<'a>> {
+        let expression = match &self.expression {
+            Some(e) => {
+                let today = Local::now().date_naive();
+                Some(Filter::parse(e, today, config).map_err(|e| eyre!(e))?)
+            }
+            None => None,
+        };
+
+        Ok(store
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| self.matches(task, expression.as_ref()))
+            .map(|(i, task)| (i + 1, task))
+            .collect())
+    }
+}
+
+/// Runs `command`, the exit code is for the ones that answer with it
+pub async fn run(command: Command, file: &str, config: &Config) -> Result<ExitCode> {
+    let result = match command {
+        Command::Sync => sync(file, config).await,
+        Command::Auth { target } => auth(target),
+        Command::Github => github(config).await,
+        Command::Add { text } => add(file, config, &text.join(" ")).await,
+        Command::Import { source } => import(file, &source).await,
+        Command::List { filter, output } => list(file, filter, output, config).await,
+        Command::Export { filter, format } => export(file, filter, format, config).await,
+        Command::Count { filter } => return count(file, filter, config).await,
+        Command::Serve { host, port, token } => serve::serve(file, &host, port, token).await,
+        Command::Plugin { action } => plugin(file, &action).await,
+        Command::Script { path, dry_run } => script::run(&path, file, config, dry_run).await,
+        Command::Daemon => daemon::run(file, &config.daemon).await,
+        Command::Agenda => agenda(file).await,
+        Command::Prompt { color } => prompt(file, color).await,
+        Command::Done { task } => done(file, config, &task).await,
+        Command::Rm { task } => remove(file, &task).await,
+        Command::Edit { task, text } => edit(file, &task, &text.join(" ")).await,
+    };
+    result.map(|_| ExitCode::SUCCESS)
+}
+
+async fn load(file: &str) -> Result<TaskStore> {
+    TaskStore::new(file.to_owned())
+        .await
+        .wrap_err_with(|| format!("could not load tasks from {}", file))
+}
+
+/// Finds the task with the id `task`, or else the 1-based task number, in `store.items`
+fn position(store: &TaskStore, task: &str) -> Result<usize> {
+    if let Some(i) = store.items.iter().position(|t| t.id() == Some(task)) {
+        return Ok(i);
+    }
+
+    match task.parse::<usize>() {
+        Ok(index) if index > 0 && index <= store.items.len() => Ok(index - 1),
+        _ => Err(eyre!("there is no task {}", task)),
+    }
+}
+
+pub(crate) async fn log(kind: HistoryKind, file: &str, task: &TaskItem) -> Result<()> {
+    let entry = HistoryEntry::new(kind, file, &task.text).task_id(task.id());
+    History::new().append(entry).await
+}
+
+/// Adds the task, or the tasks of a `/template`, the way the interface would
+async fn add(file: &str, config: &Config, text: &str) -> Result<()> {
+    // a running interface would overwrite the appended line with its next save
+    if ipc::send(file, Request::Add(text.to_owned())).await? {
+        return Ok(());
+    }
+
+    append(file, config.new_tasks(text)).await
+}
+
+/// Appends the tasks, tagged and dated the way the file asks for, and tells the hooks and the
+/// history about them
+async fn append(file: &str, mut tasks: Vec<TaskItem>) -> Result<()> {
+    let today = Local::now().date_naive();
+    let settings = front_matter::settings(file).await?;
+    for task in tasks.iter_mut() {
+        settings.tag(task);
+        task.resolve_dates(today);
+        task.mark_created(today);
+    }
+
+    append_tasks(file, &tasks).await?;
+    for task in &tasks {
+        hooks::fire(Hook::Add, file, Some(task)).await;
+        log(HistoryKind::Add, file, task).await?;
+    }
+    Ok(())
+}
+
+/// Reads everything from stdin, for `import -` and `--stdin`
+pub async fn read_stdin() -> Result<String> {
+    let mut content = String::new();
+    tokio::io::stdin().read_to_string(&mut content).await?;
+    Ok(content)
+}
+
+async fn import(file: &str, source: &str) -> Result<()> {
+    let content = match source {
+        "-" => read_stdin().await?,
+        path => tokio::fs::read_to_string(path).await?,
+    };
+
+    if content.trim_start().starts_with("BEGIN:VCALENDAR") {
+        return import_ics(file, &content).await;
+    }
+
+    let tasks = parse_lines(&content, Local::now().date_naive());
+    let count = tasks.len();
+    let lines = tasks.iter().map(format_task).collect();
+    // a running interface would overwrite the appended lines with its next save
+    if !ipc::send(file, Request::Import(lines)).await? {
+        append(file, tasks).await?;
+    }
+    eprintln!("imported {} tasks", count);
+
+    Ok(())
+}
+
+/// Merges VTODOs into the store, updating tasks that were imported with the same UID before
+async fn import_ics(file: &str, content: &str) -> Result<()> {
+    let mut store = load(file).await?;
+    let (mut added, mut updated) = (0, 0);
+
+    for task in parse_ics(content) {
+        let existing = task.get_meta("uid").and_then(|uid| {
+            store
+                .items
+                .iter_mut()
+                .find(|t| t.get_meta("uid") == Some(uid))
+        });
+
+        match existing {
+            Some(existing) => {
+                existing.text = task.text;
+                existing.state = task.state;
+                for (key, value) in task.meta {
+                    existing.set_meta(&key, Some(value));
+                }
+                updated += 1;
+            }
+            None => {
+                let mut task = task;
+                task.mark_created(Local::now().date_naive());
+                log(HistoryKind::Add, file, &task).await?;
+                store.items.push(task);
+                added += 1;
+            }
+        }
+    }
+
+    store.save().await?;
+    eprintln!("imported {} tasks, updated {}", added, updated);
+
+    Ok(())
+}
+
+async fn list(file: &str, filter: FilterArgs, output: OutputFormat, config: &Config) -> Result<()> {
+    let store = load(file).await?;
+    let tasks = filter.apply(&store, &config.search)?;
+
+    let out = match output {
+        OutputFormat::Plain => tasks
+            .iter()
+            .map(|(i, task)| format!("{:>3} {}\n", i, format_task(task)))
+            .collect(),
+        OutputFormat::Tsv => export::tsv(&tasks),
+        OutputFormat::Json => export::json(&tasks)?,
+    };
+    print!("{}", out);
+
+    Ok(())
+}
+
+async fn export(
+    file: &str,
+    filter: FilterArgs,
+    format: ExportFormat,
+    config: &Config,
+) -> Result<()> {
+    let store = load(file).await?;
+    let tasks = filter.apply(&store, &config.search)?;
+
+    let out = match format {
+        ExportFormat::Markdown => export::markdown(&tasks),
+        ExportFormat::TodoTxt => export::todo_txt(&tasks),
+        ExportFormat::Json => export::json(&tasks)?,
+        ExportFormat::Ics => export::ics(&tasks),
+        ExportFormat::Csv => export::csv(&tasks),
+    };
+    print!("{}", out);
+
+    Ok(())
+}
+
+async fn count(file: &str, filter: FilterArgs, config: &Config) -> Result<ExitCode> {
+    let store = load(file).await?;
+    let count = filter.apply(&store, &config.search)?.len();
+    println!("{}", count);
+
+    // lets shell prompts and cron jobs react with a plain `if`
+    match count {
+        0 => Ok(ExitCode::SUCCESS),
+        _ => Ok(ExitCode::FAILURE),
+    }
+}
+
+async fn sync(file: &str, config: &Config) -> Result<()> {
+    let mut store = load(file).await?;
+    let before = store.items.clone();
+    let result = sync::sync_configured(&config.sync, &mut store, true).await;
+    // what was synced before a failure, or tasks created remotely would be created again
+    if result.is_ok() || store.items != before {
+        store.save().await?;
+    }
+    let report = result?;
+
+    println!("{}", report.summary());
+    for conflict in &report.conflicts {
+        println!(
+            "conflict: kept remote \"{}\" over local \"{}\"",
+            conflict.remote.text, conflict.local.text
+        );
+    }
+
+    Ok(())
+}
+
+pub fn auth(target: AuthTarget) -> Result<()> {
+    let (name, prompt) = match target {
+        AuthTarget::Caldav => ("caldav", "CalDAV password: "),
+        AuthTarget::Webdav => ("webdav", "WebDAV password: "),
+        AuthTarget::Github => ("github", "GitHub token: "),
+        AuthTarget::Todoist => ("todoist", "Todoist token: "),
+        AuthTarget::Age => ("age", "age passphrase: "),
+    };
+
+    let secret = rpassword::prompt_password(prompt)?;
+    if secret.is_empty() {
+        return Err(eyre!("nothing entered, keeping the stored {} secret", name));
+    }
+    secrets::set(name, &secret)?;
+
+    println!("stored the {} secret in the keyring", name);
+    Ok(())
+}
+
+async fn github(config: &Config) -> Result<()> {
+    let Some(github) = &config.github else {
+        return Err(eyre!("no [github] section in the config"));
+    };
+
+    let mut store = load(&github.file()).await?;
+    let changed = Github::new(github)?.pull(github, &mut store).await?;
+    store.save().await?;
+
+    println!("{} issues changed", changed);
+    Ok(())
+}
+
+async fn plugin(file: &str, action: &str) -> Result<()> {
+    let plugins = plugins::get().ok_or_else(|| eyre!("plugins aren't loaded"))?;
+    let mut store = load(file).await?;
+
+    match plugins
+        .run(action, &store.items, None)
+        .map_err(|e| eyre!(e))?
+    {
+        Outcome::Tasks(tasks) => {
+            store.items = tasks;
+            store.save().await?;
+        }
+        Outcome::Message(message) => println!("{}", message),
+        Outcome::Nothing => {}
+    }
+
+    Ok(())
+}
+
+async fn agenda(file: &str) -> Result<()> {
+    let store = load(file).await?;
+    let today = Local::now().date_naive();
+
+    let open = store.items.iter().filter(|t| t.state == TaskState::Open);
+    let (overdue, due_today): (Vec<&TaskItem>, Vec<&TaskItem>) = open
+        .filter(|t| t.due().is_some_and(|due| due <= today))
+        .partition(|t| t.due().is_some_and(|due| due < today));
+
+    println!("Agenda for {}", today.format("%A, %Y-%m-%d"));
+
+    for (title, tasks) in [("Overdue", overdue), ("Today", due_today)] {
+        if tasks.is_empty() {
+            continue;
+        }
+        println!("\n{} ({})", title, tasks.len());
+
+        let mut projects: BTreeMap<Option<&str>, Vec<&TaskItem>> = BTreeMap::new();
+        for task in tasks {
+            projects.entry(task.project()).or_default().push(task);
+        }
+
+        for (project, tasks) in projects {
+            println!("  {}", project.unwrap_or("(no project)"));
+            for task in tasks {
+                match task.due() {
+                    Some(due) if due < today => {
+                        println!("    - {} (due {})", task.text, due.format(dates::FORMAT))
+                    }
+                    _ => println!("    - {}", task.text),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn prompt(file: &str, color: bool) -> Result<()> {
+    let store = load(file).await?;
+    let today = Local::now().date_naive();
+
+    let open: Vec<&TaskItem> = store
+        .items
+        .iter()
+        .filter(|t| t.state == TaskState::Open)
+        .collect();
+    let overdue = open
+        .iter()
+        .filter(|t| t.due().is_some_and(|due| due < today))
+        .count();
+
+    let paint = |code: &str, text: String| match color {
+        true => format!("\x1b[{}m{}\x1b[0m", code, text),
+        false => text,
+    };
+
+    let mut segments = vec![];
+    if overdue > 0 {
+        segments.push(paint("31", format!("\u{f071} {}", overdue)));
+    }
+    if !open.is_empty() {
+        segments.push(paint("36", format!("\u{f05d} {}", open.len())));
+    }
+
+    if !segments.is_empty() {
+        println!("{}", segments.join(" "));
+    }
+
+    Ok(())
+}
+
+async fn done(file: &str, config: &Config, task: &str) -> Result<()> {
+    let mut store = load(file).await?;
+    let i = position(&store, task)?;
+    // a running interface would overwrite the change with its next save
+    if ipc::send_for(file, &store.items[i], Request::Done).await? {
+        return Ok(());
+    }
+
+    let task = &mut store.items[i];
+    if let TaskState::Done = task.state {
+        return Ok(());
+    }
+
+    task.toggle_state();
+    task.stop_tracking();
+    task.record_state_change(Local::now().date_naive());
+    let task = task.clone();
+
+    if let (Some(issue), Some(github)) = (task.get_meta("issue"), &config.github) {
+        Github::new(github)?.set_state(issue, &task.state).await?;
+    }
+
+    store.save().await?;
+    hooks::fire(Hook::Complete, file, Some(&task)).await;
+    log(HistoryKind::Complete, file, &task).await
+}
+
+async fn remove(file: &str, task: &str) -> Result<()> {
+    let mut store = load(file).await?;
+    let i = position(&store, task)?;
+    if ipc::send_for(file, &store.items[i], Request::Remove).await? {
+        return Ok(());
+    }
+
+    let task = store.items.remove(i);
+    store.save().await?;
+    hooks::fire(Hook::Delete, file, Some(&task)).await;
+    log(HistoryKind::Delete, file, &task).await
+}
+
+async fn edit(file: &str, task: &str, text: &str) -> Result<()> {
+    let mut store = load(file).await?;
+    let i = position(&store, task)?;
+    if store.items[i].get_meta("issue").is_some() {
+        return Err(eyre!("issue titles are edited on GitHub"));
+    }
+    let request = |id| Request::Edit(id, text.to_owned());
+    if ipc::send_for(file, &store.items[i], request).await? {
+        return Ok(());
+    }
+
+    let edited = parse_task(text, TaskState::Open);
+    let task = &mut store.items[i];
+    task.text = edited.text;
+    for (key, value) in edited.meta {
+        task.set_meta(&key, Some(value));
+    }
+    task.resolve_dates(Local::now().date_naive());
+
+    store.save().await
+}
@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde_derive::Deserialize;
+use tokio::fs;
+
+use crate::{
+    app::InterfaceConfig,
+    confirm::ConfirmConfig,
+    crypt::AgeConfig,
+    daemon::DaemonConfig,
+    file::parse_task,
+    filter::SearchConfig,
+    git::GitConfig,
+    github::GithubConfig,
+    hooks::Hooks,
+    remote::WebDavConfig,
+    stale::StaleConfig,
+    sync::SyncConfig,
+    task_item::{TaskItem, TaskState},
+    theme::ThemeConfig,
+};
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Todo file used when `--file` isn't given
+    pub file: Option<String>,
+    /// Language of the interface and its dates like `de_DE`, `LANG` when not set
+    pub locale: Option<String>,
+    pub templates: Vec<Template>,
+    pub sync: SyncConfig,
+    pub github: Option<GithubConfig>,
+    pub git: GitConfig,
+    pub webdav: WebDavConfig,
+    pub age: AgeConfig,
+    pub daemon: DaemonConfig,
+    pub hooks: Hooks,
+    pub search: SearchConfig,
+    pub theme: ThemeConfig,
+    pub confirm: ConfirmConfig,
+    pub interface: InterfaceConfig,
+    pub stale: StaleConfig,
+    /// Priority levels from the highest down, `+` and `-` step through them and sorting by
+    /// priority follows them; `A`, `B` and `C` when not set
+    pub priorities: Vec<String>,
+}
+
+const DEFAULT_PRIORITIES: [&str; 3] = ["A", "B", "C"];
+
+/// A named group of tasks that is added in one go, e.g. a weekly review checklist
+#[derive(Clone, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub tasks: Vec<String>,
+}
+
+impl Template {
+    /// A parent task named after the template with the template tasks nested below it
+    pub fn instantiate(&self) -> Vec<TaskItem> {
+        let mut tasks = vec![TaskItem::new(self.name.clone(), TaskState::Open)];
+
+        for line in &self.tasks {
+            let mut task = parse_task(line, TaskState::Open);
+            task.depth = 1;
+            tasks.push(task);
+        }
+
+        tasks
+    }
+}
+
+impl Config {
+    pub async fn load() -> Result<Config> {
+        let path = config_dir().join("config.toml");
+        if (fs::metadata(&path).await).is_err() {
+            return Ok(Config::default());
+        }
+
+        let content = fs::read_to_string(&path).await?;
+        toml::from_str(&content).wrap_err_with(|| format!("invalid config {}", path.display()))
+    }
+
+    /// The configured todo file with a leading `~` expanded
+    pub fn file(&self) -> Option<String> {
+        self.file.as_deref().map(expand_home)
+    }
+
+    pub fn priorities(&self) -> Vec<&str> {
+        match self.priorities.is_empty() {
+            true => DEFAULT_PRIORITIES.to_vec(),
+            false => self.priorities.iter().map(String::as_str).collect(),
+        }
+    }
+
+    pub fn template(&self, name: &str) -> Option<&Template> {
+        self.templates
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// What adding `text` stands for: the tasks of the template it names as `/name`, or
+    /// the one task it describes
+    pub fn new_tasks(&self, text: &str) -> Vec<TaskItem> {
+        let template = text.strip_prefix('/').and_then(|name| self.template(name));
+        match template {
+            Some(template) => template.instantiate(),
+            None => vec![parse_task(text, TaskState::Open)],
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tuitodo`, falling back to `~/.config/tuitodo`
+pub fn config_dir() -> PathBuf {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".config"),
+    };
+
+    base.join("tuitodo")
+}
+
+/// `path` with a leading `~` expanded to the home directory
+pub fn expand_home(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => {
+            let home = std::env::var("HOME").unwrap_or_default();
+            format!("{}/{}", home, rest)
+        }
+        None => path.to_owned(),
+    }
+}
@@ -0,0 +1,166 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use serde_derive::Deserialize;
+
+use crate::{app::Action, i18n::t, theme};
+
+/// `[confirm]` section of the config, which actions ask before going ahead
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ConfirmConfig {
+    /// Deleting a single task, purging done tasks always asks
+    pub delete: bool,
+    /// Adding a task that looks like one already open, enter again adds it anyway
+    pub duplicates: bool,
+}
+
+impl Default for ConfirmConfig {
+    fn default() -> Self {
+        Self {
+            delete: true,
+            duplicates: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Answer {
+    Yes,
+    No,
+    Cancel,
+}
+
+/// A question that has to be answered before going on, with what each answer leads to
+#[derive(Clone, Debug)]
+pub struct Confirm {
+    message: String,
+    on_yes: Action,
+    on_no: Option<Action>,
+    /// Whether backing out is an answer of its own, otherwise it means no
+    cancel: bool,
+    /// The answer enter picks, moved with tab and the arrow keys
+    selected: Answer,
+}
+
+impl Confirm {
+    /// Asks `message`, no does nothing and is the default
+    pub fn new(message: String, on_yes: Action) -> Self {
+        Self {
+            message,
+            on_yes,
+            on_no: None,
+            cancel: false,
+            selected: Answer::No,
+        }
+    }
+
+    /// What no leads to
+    pub fn on_no(mut self, action: Action) -> Self {
+        self.on_no = Some(action);
+        self
+    }
+
+    /// Offers cancel besides yes and no, for when no does something too
+    pub fn with_cancel(mut self) -> Self {
+        self.cancel = true;
+        self
+    }
+
+    /// The answer enter picks until another one is selected
+    pub fn default_answer(mut self, answer: Answer) -> Self {
+        self.selected = answer;
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn answers(&self) -> &'static [Answer] {
+        match self.cancel {
+            true => &[Answer::Yes, Answer::No, Answer::Cancel],
+            false => &[Answer::Yes, Answer::No],
+        }
+    }
+
+    pub fn selected(&self) -> Answer {
+        self.selected
+    }
+
+    /// Moves the selection to the next answer, or the previous one going `back`
+    pub fn select_next(&mut self, back: bool) {
+        let answers = self.answers();
+        let i = answers
+            .iter()
+            .position(|a| *a == self.selected)
+            .unwrap_or(0);
+        let i = match back {
+            true => (i + answers.len() - 1) % answers.len(),
+            false => (i + 1) % answers.len(),
+        };
+        self.selected = answers[i];
+    }
+
+    /// What `answer` leads to, backing out without a cancel answer means no
+    pub fn answer(self, answer: Answer) -> Option<Action> {
+        match answer {
+            Answer::Yes => Some(self.on_yes),
+            Answer::No => self.on_no,
+            Answer::Cancel if self.cancel => None,
+            Answer::Cancel => self.on_no,
+        }
+    }
+}
+
+/// The question in a box over `area` with its answers below, the selected one highlighted
+pub fn ui(f: &mut Frame, area: Rect, confirm: &Confirm) {
+    let mut answers = vec![];
+    for answer in confirm.answers() {
+        let label = match answer {
+            Answer::Yes => t("confirm-yes"),
+            Answer::No => t("confirm-no"),
+            Answer::Cancel => t("confirm-cancel"),
+        };
+        let style = match *answer == confirm.selected {
+            true => theme::selection().add_modifier(Modifier::BOLD),
+            false => Style::default(),
+        };
+        answers.push(Span::styled(format!(" {} ", label), style));
+        answers.push(Span::raw("  "));
+    }
+    answers.pop();
+
+    let width = (confirm.message.chars().count() as u16 + 4)
+        .max(40)
+        .min(area.width);
+    let rect = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + area.height.saturating_sub(6) / 2,
+        width,
+        height: 6.min(area.height),
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::palette().accent));
+    let lines = vec![
+        Line::from(confirm.message.clone()),
+        Line::default(),
+        Line::from(answers),
+    ];
+
+    f.render_widget(Clear, rect);
+    f.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(block),
+        rect,
+    );
+}
@@ -0,0 +1,158 @@
+use chrono::Local;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    i18n::{self, t, t_args},
+    sync::{Conflict, RemoteTask},
+    task_item::TaskItem,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    Local,
+    Remote,
+    /// The remote version stays synced, the local one becomes a new task
+    Both,
+}
+
+/// Applies the choice to `items`, which hold the remote version after a sync. The next sync
+/// pushes whatever was kept locally.
+pub fn resolve(items: &mut Vec<TaskItem>, conflict: &Conflict, resolution: Resolution) {
+    let Some(index) = items
+        .iter()
+        .position(|t| t.get_meta("uid") == Some(conflict.remote.uid.as_str()))
+    else {
+        return;
+    };
+
+    match resolution {
+        Resolution::Local => items[index] = conflict.local.clone(),
+        Resolution::Remote => {}
+        Resolution::Both => {
+            let mut copy = conflict.local.clone();
+            // the copy is a task of its own
+            copy.set_meta("uid", None);
+            copy.set_meta("id", None);
+            copy.mark_created(Local::now().date_naive());
+            items.insert(index + 1, copy);
+        }
+    }
+}
+
+fn version(title: String, task: Option<&RemoteTask>, color: Color) -> Paragraph<'static> {
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color));
+
+    let Some(task) = task else {
+        return Paragraph::new(Span::styled(
+            t("conflict-new"),
+            Style::default().fg(Color::Gray),
+        ))
+        .block(block);
+    };
+
+    let labels = [
+        t("conflict-state"),
+        t("conflict-due"),
+        t("conflict-priority"),
+    ];
+    let width = labels.iter().map(|l| l.width()).max().unwrap_or_default() + 1;
+    let field = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(
+                format!("{:<width$}", label),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::raw(value),
+        ])
+    };
+    let state = match task.done {
+        true => t("conflict-done"),
+        false => t("conflict-open"),
+    };
+
+    let lines = vec![
+        Line::from(Span::styled(
+            task.text.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::default(),
+        field(&labels[0], state),
+        field(
+            &labels[1],
+            task.due.map(i18n::date).unwrap_or_else(|| "-".to_owned()),
+        ),
+        field(
+            &labels[2],
+            task.priority.clone().unwrap_or_else(|| "-".to_owned()),
+        ),
+    ];
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+}
+
+/// The last synced, local and remote version side by side
+pub fn ui(f: &mut Frame, area: Rect, conflict: &Conflict, remaining: usize) {
+    let local = RemoteTask::from_task(&conflict.remote.uid, &conflict.local);
+
+    let block = Block::default()
+        .title(format!(
+            " {} ",
+            t_args("conflict-title", &[("count", remaining.into())])
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red));
+    let inner = block.inner(area);
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3); 3])
+        .split(rows[0]);
+
+    f.render_widget(
+        version(t("conflict-base"), conflict.base.as_ref(), Color::Gray),
+        columns[0],
+    );
+    f.render_widget(
+        version(t("conflict-local"), Some(&local), Color::Yellow),
+        columns[1],
+    );
+    f.render_widget(
+        version(t("conflict-remote"), Some(&conflict.remote), Color::Cyan),
+        columns[2],
+    );
+
+    let key = |key: &'static str| Span::styled(key, Style::default().fg(Color::Yellow));
+    f.render_widget(
+        Paragraph::new(Line::from(vec![
+            key("l"),
+            Span::raw(format!(" {}  ", t("conflict-keep-local"))),
+            key("r"),
+            Span::raw(format!(" {}  ", t("conflict-keep-remote"))),
+            key("b"),
+            Span::raw(format!(" {}  ", t("conflict-keep-both"))),
+            key("esc"),
+            Span::raw(format!(" {}", t("conflict-later"))),
+        ])),
+        rows[1],
+    );
+}
@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+};
+
+use age::{scrypt, secrecy::SecretString, x25519};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde_derive::Deserialize;
+use tokio::fs;
+
+use crate::{config::expand_home, secrets};
+
+/// `[age]` section of the config, for todo files ending in `.age`
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AgeConfig {
+    /// age identity file, e.g. from `age-keygen`; without one the passphrase comes from the
+    /// keyring or is asked for
+    pub identity: Option<String>,
+}
+
+enum Key {
+    Passphrase(SecretString),
+    Identity(x25519::Identity),
+}
+
+/// Unlocked once per file and kept in memory for every save
+static KEYS: OnceLock<Mutex<HashMap<String, Arc<Key>>>> = OnceLock::new();
+
+fn keys() -> MutexGuard<'static, HashMap<String, Arc<Key>>> {
+    KEYS.get_or_init(Default::default).lock().unwrap()
+}
+
+pub fn is_encrypted(file_path: &str) -> bool {
+    file_path.ends_with(".age")
+}
+
+/// Gets the key from the identity file or a passphrase prompt and checks it against the
+/// file, if it exists yet. A file that is unlocked already keeps its key.
+pub async fn unlock(file_path: &str, config: &AgeConfig) -> Result<()> {
+    if key(file_path).is_ok() {
+        return Ok(());
+    }
+    let exists = fs::metadata(file_path).await.is_ok();
+
+    let key = match &config.identity {
+        Some(path) => {
+            let path = expand_home(path);
+            let content = fs::read_to_string(&path)
+                .await
+                .wrap_err_with(|| format!("could not read identity {}", path))?;
+            let identity = content
+                .lines()
+                .find(|l| l.starts_with("AGE-SECRET-KEY-"))
+                .ok_or_else(|| eyre!("no age identity in {}", path))?
+                .parse()
+                .map_err(|e| eyre!("invalid age identity in {}: {}", path, e))?;
+            Key::Identity(identity)
+        }
+        None => {
+            let passphrase = match secrets::get("age") {
+                Some(passphrase) if exists => passphrase,
+                _ => prompt(file_path, exists)?,
+            };
+            Key::Passphrase(passphrase.into())
+        }
+    };
+
+    let key = Arc::new(key);
+    if exists {
+        decrypt_with(key.clone(), fs::read(file_path).await?).await?;
+    }
+
+    keys().insert(file_path.to_owned(), key);
+    Ok(())
+}
+
+fn prompt(file_path: &str, exists: bool) -> Result<String> {
+    let passphrase = rpassword::prompt_password(format!("Passphrase for {}: ", file_path))?;
+    if !exists && rpassword::prompt_password("Repeat the passphrase: ")? != passphrase {
+        return Err(eyre!("the passphrases don't match"));
+    }
+    Ok(passphrase)
+}
+
+fn key(file_path: &str) -> Result<Arc<Key>> {
+    keys()
+        .get(file_path)
+        .cloned()
+        .ok_or_else(|| eyre!("{} is encrypted, but no key was unlocked for it", file_path))
+}
+
+/// scrypt takes its time on purpose, so the work is kept off the async threads
+async fn decrypt_with(key: Arc<Key>, ciphertext: Vec<u8>) -> Result<String> {
+    let plaintext = tokio::task::spawn_blocking(move || match key.as_ref() {
+        Key::Passphrase(passphrase) => {
+            age::decrypt(&scrypt::Identity::new(passphrase.clone()), &ciphertext)
+        }
+        Key::Identity(identity) => age::decrypt(identity, &ciphertext),
+    })
+    .await?
+    .map_err(|e| eyre!("could not decrypt: {}", e))?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Reads `file_path` and decrypts it with the key unlocked for it
+pub async fn read(file_path: &str) -> Result<String> {
+    decrypt_with(key(file_path)?, fs::read(file_path).await?).await
+}
+
+/// Encrypts `plaintext` for `file_path`, ASCII armored, so the file still diffs and syncs
+/// like text
+pub async fn encrypt(file_path: &str, plaintext: String) -> Result<Vec<u8>> {
+    let key = key(file_path)?;
+    let ciphertext = tokio::task::spawn_blocking(move || match key.as_ref() {
+        Key::Passphrase(passphrase) => age::encrypt_and_armor(
+            &scrypt::Recipient::new(passphrase.clone()),
+            plaintext.as_bytes(),
+        ),
+        Key::Identity(identity) => {
+            age::encrypt_and_armor(&identity.to_public(), plaintext.as_bytes())
+        }
+    })
+    .await?
+    .map_err(|e| eyre!("could not encrypt: {}", e))?;
+
+    Ok(ciphertext.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use age::secrecy::ExposeSecret;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn every_file_keeps_its_own_key() {
+        let dir = std::env::temp_dir().join(format!("tuitodo-crypt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+
+        let mut files = vec![];
+        for name in ["work", "home"] {
+            let identity = x25519::Identity::generate();
+            let identity_path = path(&format!("{}.key", name));
+            std::fs::write(&identity_path, identity.to_string().expose_secret()).unwrap();
+            let config = AgeConfig {
+                identity: Some(identity_path),
+            };
+
+            let file = path(&format!("{}.md.age", name));
+            unlock(&file, &config).await.unwrap();
+            let content = format!("- [ ] {}\n", name);
+            fs::write(&file, encrypt(&file, content.clone()).await.unwrap())
+                .await
+                .unwrap();
+            // reopening the file keeps its key instead of failing
+            unlock(&file, &config).await.unwrap();
+            files.push((file, content));
+        }
+
+        for (file, content) in files {
+            assert_eq!(read(&file).await.unwrap(), content);
+        }
+        assert!(read(&path("other.md.age")).await.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
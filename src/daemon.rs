@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime, NaiveTime};
+use color_eyre::eyre::{eyre, Result};
+use serde_derive::Deserialize;
+
+use crate::{
+    file::TaskStore,
+    notify,
+    task_item::{TaskItem, TaskState},
+};
+
+/// How often the file is checked for reminders that are due
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// How many task names a digest lists before summarizing the rest
+const DIGEST_TASKS: usize = 5;
+
+/// `[daemon]` section of the config
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Time of the daily summary of due and overdue tasks as `HH:MM`, empty to turn it off
+    pub digest: String,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            digest: "08:00".to_owned(),
+        }
+    }
+}
+
+/// Runs until killed, sending a notification for every open task with a `time:HH:MM` when
+/// that time is reached on its due date (today when it has none) and the daily digest
+pub async fn run(file: &str, config: &DaemonConfig) -> Result<()> {
+    let digest = match config.digest.as_str() {
+        "" => None,
+        digest => Some(
+            NaiveTime::parse_from_str(digest, "%H:%M")
+                .map_err(|_| eyre!("invalid digest time `{}`, expected HH:MM", digest))?,
+        ),
+    };
+
+    let mut last = Local::now().naive_local();
+    loop {
+        tokio::time::sleep(INTERVAL).await;
+        let now = Local::now().naive_local();
+
+        // the file may be missing for a moment while an editor saves it
+        let Ok(store) = TaskStore::new(file.to_owned()).await else {
+            continue;
+        };
+        let open: Vec<&TaskItem> = store
+            .items
+            .iter()
+            .filter(|t| t.state == TaskState::Open)
+            .collect();
+
+        // only what became due since the last check, so a restart doesn't repeat anything
+        let passed = |at: NaiveDateTime| last < at && at <= now;
+
+        for task in &open {
+            let Some(time) = task.time() else {
+                continue;
+            };
+            let date = task.due().unwrap_or(now.date());
+            if passed(date.and_time(time)) {
+                notify::desktop("Reminder", &task.text);
+            }
+        }
+
+        if let Some(digest) = digest {
+            if passed(now.date().and_time(digest)) {
+                send_digest(&open, now);
+            }
+        }
+
+        last = now;
+    }
+}
+
+fn send_digest(open: &[&TaskItem], now: NaiveDateTime) {
+    let today = now.date();
+    let due: Vec<&&TaskItem> = open
+        .iter()
+        .filter(|t| t.due().is_some_and(|due| due <= today))
+        .collect();
+    if due.is_empty() {
+        return;
+    }
+
+    let overdue = due.iter().filter(|t| t.due() < Some(today)).count();
+    let title = match overdue {
+        0 => format!("{} tasks due today", due.len()),
+        _ => format!("{} tasks due, {} overdue", due.len(), overdue),
+    };
+
+    let mut lines: Vec<String> = due
+        .iter()
+        .take(DIGEST_TASKS)
+        .map(|t| format!("• {}", t.text))
+        .collect();
+    if due.len() > DIGEST_TASKS {
+        lines.push(format!("and {} more", due.len() - DIGEST_TASKS));
+    }
+
+    notify::desktop(&title, &lines.join("\n"));
+}
@@ -0,0 +1,125 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+pub const FORMAT: &str = "%Y-%m-%d";
+
+/// Parses absolute (`2024-05-01`) and relative (`today`, `tomorrow`, `+3d`, `+2w`, `mon`) dates
+pub fn parse(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, FORMAT) {
+        return Some(date);
+    }
+
+    match value.to_lowercase().as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+
+    if let Some(offset) = value.strip_prefix('+') {
+        let count = |unit: char| offset.strip_suffix(unit)?.parse::<i64>().ok();
+        // far enough ahead to overflow isn't a date
+        let duration = match (count('d'), count('w')) {
+            (Some(days), _) => Duration::try_days(days)?,
+            (_, Some(weeks)) => Duration::try_weeks(weeks)?,
+            _ => return None,
+        };
+        return today.checked_add_signed(duration);
+    }
+
+    let weekday: Weekday = value.parse().ok()?;
+    Some(next_weekday(today, weekday))
+}
+
+/// The next `weekday` strictly after `today`
+pub fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+
+    today + Duration::days(if ahead == 0 { 7 } else { ahead })
+}
+
+/// A quick change to a due date, for going through a backlog
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shift {
+    Day,
+    Week,
+    /// The coming Monday, a week ahead on a Monday
+    Monday,
+    Clear,
+}
+
+impl Shift {
+    /// The due date after the shift; days and weeks count from `due`, or from `today` when
+    /// there is none or it is past, so an overdue task doesn't stay overdue
+    pub fn apply(self, due: Option<NaiveDate>, today: NaiveDate) -> Option<NaiveDate> {
+        let from = due.filter(|d| *d > today).unwrap_or(today);
+        match self {
+            Shift::Day => Some(from + Duration::days(1)),
+            Shift::Week => Some(from + Duration::weeks(1)),
+            Shift::Monday => Some(next_weekday(today, Weekday::Mon)),
+            Shift::Clear => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, FORMAT).unwrap()
+    }
+
+    #[test]
+    fn parses_absolute_and_relative_dates() {
+        let today = day("2024-05-01"); // a Wednesday
+        assert_eq!(parse("2024-06-30", today), Some(day("2024-06-30")));
+        assert_eq!(parse("Today", today), Some(today));
+        assert_eq!(parse("tomorrow", today), Some(day("2024-05-02")));
+        assert_eq!(parse("+3d", today), Some(day("2024-05-04")));
+        assert_eq!(parse("+2w", today), Some(day("2024-05-15")));
+        assert_eq!(parse("wed", today), Some(day("2024-05-08")));
+        assert_eq!(parse("fri", today), Some(day("2024-05-03")));
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_date() {
+        let today = day("2024-05-01");
+        for value in [
+            "",
+            "+",
+            "+d",
+            "+3",
+            "+3x",
+            "+3é",
+            "+é",
+            "soon",
+            "2024-13-01",
+        ] {
+            assert_eq!(parse(value, today), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn overflowing_offsets_are_no_date() {
+        let today = day("2024-05-01");
+        assert_eq!(parse("+99999999999999d", today), None);
+        assert_eq!(parse("+9999999999999999w", today), None);
+        assert_eq!(parse("+9223372036854775807d", today), None);
+    }
+
+    #[test]
+    fn shifts_count_from_the_due_date_or_today() {
+        let today = day("2024-05-01");
+        assert_eq!(
+            Shift::Day.apply(Some(day("2024-05-10")), today),
+            Some(day("2024-05-11"))
+        );
+        assert_eq!(
+            Shift::Week.apply(Some(day("2024-04-01")), today),
+            Some(day("2024-05-08"))
+        );
+        assert_eq!(Shift::Monday.apply(None, today), Some(day("2024-05-06")));
+        assert_eq!(Shift::Clear.apply(Some(today), today), None);
+    }
+}
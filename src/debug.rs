@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{i18n::t, logging};
+
+/// How long the app spent on the latest action and frame
+#[derive(Default)]
+pub struct Timings {
+    pub events: u64,
+    pub action: Option<(&'static str, Duration)>,
+    pub frame: Duration,
+}
+
+impl Timings {
+    /// `action` is the name of the action's variant
+    pub fn record_action(&mut self, action: &'static str, took: Duration) {
+        self.events += 1;
+        // these come in constantly and would hide everything else
+        if action != "Tick" && action != "Render" {
+            self.action = Some((action, took));
+        }
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+pub fn ui(f: &mut Frame, area: Rect, timings: &Timings) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let (action, took) = match &timings.action {
+        Some((name, took)) => (*name, millis(*took)),
+        None => ("-", "-".to_owned()),
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(format!("{} ", t("debug-frame")), dim),
+            Span::raw(millis(timings.frame)),
+            Span::styled(format!("  {} ", t("debug-last-action")), dim),
+            Span::raw(format!("{} {}", action, took)),
+            Span::styled(format!("  {} ", t("debug-events")), dim),
+            Span::raw(timings.events.to_string()),
+        ]),
+        Line::default(),
+    ];
+
+    // newest lines at the bottom, as many as fit
+    let room = area.height.saturating_sub(2 + lines.len() as u16) as usize;
+    let recent = logging::recent();
+    let skip = recent.len().saturating_sub(room);
+    lines.extend(recent.into_iter().skip(skip).map(Line::from));
+
+    let block = Block::default()
+        .title(format!(" {} ", t("debug-title")))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta));
+
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
@@ -0,0 +1,118 @@
+use chrono::{Duration, Local};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::{
+    i18n::{self, t_args},
+    recurrence::Unit,
+    task_item::{format_duration, TaskItem},
+    theme,
+};
+
+/// Days shown in the completion heat strip
+const STRIP_DAYS: i64 = 28;
+
+pub const HEIGHT: u16 = 3;
+
+fn heat_strip(task: &TaskItem) -> Vec<Span<'static>> {
+    let today = Local::now().date_naive();
+    let completions = task.completions();
+
+    if theme::accessible() {
+        let done = (0..STRIP_DAYS)
+            .filter(|offset| completions.contains(&(today - Duration::days(*offset))))
+            .count();
+        let text = t_args(
+            "detail-completions",
+            &[("count", done.into()), ("days", STRIP_DAYS.into())],
+        );
+        return vec![Span::raw(text)];
+    }
+
+    (0..STRIP_DAYS)
+        .rev()
+        .map(|offset| {
+            let day = today - Duration::days(offset);
+            match completions.contains(&day) {
+                true => Span::styled("■", Style::default().fg(Color::Green)),
+                false => Span::styled("·", Style::default().fg(Color::DarkGray)),
+            }
+        })
+        .collect()
+}
+
+pub fn ui(f: &mut Frame, area: Rect, task: &TaskItem) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![];
+
+    if let Some(rec) = task.recurrence() {
+        let unit = match rec.unit {
+            Unit::Day => "d",
+            Unit::Week => "w",
+        };
+        let streak = task.streak(Local::now().date_naive());
+        let streak_text = match theme::accessible() {
+            true => t_args("detail-streak", &[("days", streak.into())]),
+            false => format!("{} {}", theme::glyphs().streak, streak),
+        };
+
+        let mut spans = vec![
+            Span::styled(
+                format!(
+                    "{}{}  ",
+                    theme::glyph(&theme::glyphs().recurrence),
+                    t_args(
+                        "detail-every",
+                        &[("interval", format!("{}{}", rec.every, unit).into())]
+                    )
+                ),
+                dim,
+            ),
+            Span::styled(
+                format!("{} ", streak_text),
+                Style::default().fg(if streak > 0 {
+                    Color::Yellow
+                } else {
+                    Color::Gray
+                }),
+            ),
+        ];
+        spans.extend(heat_strip(task));
+        lines.push(Line::from(spans));
+    }
+
+    let mut spans = vec![];
+    let tracked = task.tracked();
+    if tracked > 0 {
+        let duration = format_duration(tracked);
+        spans.push(Span::styled(
+            format!(
+                "{}{}  ",
+                theme::glyph(&theme::glyphs().timer),
+                t_args("detail-tracked", &[("duration", duration.into())])
+            ),
+            dim,
+        ));
+    }
+    if let Some(created) = task.created() {
+        let date = i18n::date(created);
+        spans.push(Span::styled(
+            t_args("detail-created", &[("date", date.into())]),
+            dim,
+        ));
+    }
+    if !spans.is_empty() {
+        lines.push(Line::from(spans));
+    }
+    if let Some(note) = task.notes.first() {
+        lines.push(Line::styled(note.clone(), dim));
+    }
+
+    let block = Block::default().borders(Borders::TOP).border_style(dim);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
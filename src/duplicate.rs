@@ -0,0 +1,72 @@
+use crate::task_item::{TaskItem, TaskState};
+
+/// How alike two tasks have to be to count as the same, from 0 to 1
+const THRESHOLD: f64 = 0.85;
+
+/// A task about to be added that looks like one already open
+#[derive(Clone, Debug)]
+pub struct Duplicate {
+    /// The line as it was typed, adding it again goes ahead
+    pub value: String,
+    /// The open task it looks like
+    pub index: usize,
+}
+
+/// The open task most like `text`, if one is alike enough to be the same
+pub fn find(tasks: &[TaskItem], text: &str) -> Option<usize> {
+    let wanted = normalize(text);
+    if wanted.is_empty() {
+        return None;
+    }
+
+    tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.state == TaskState::Open)
+        .map(|(i, t)| (i, similarity(&wanted, &normalize(&t.text))))
+        .filter(|(_, s)| *s >= THRESHOLD)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| i)
+}
+
+/// The words of `text` in lower case and in order, without punctuation or the `#` of tags,
+/// so `Buy milk!` and `#buy  Milk` are alike
+fn normalize(text: &str) -> Vec<char> {
+    let text: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    words.sort_unstable();
+    words.join(" ").chars().collect()
+}
+
+/// 1 minus the edit distance relative to the longer text
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    // the distance is at least the difference in length, most tasks are ruled out by it
+    let lowest = a.len().abs_diff(b.len()) as f64 / longest as f64;
+    if 1.0 - lowest < THRESHOLD {
+        return 0.0;
+    }
+    1.0 - distance(a, b) as f64 / longest as f64
+}
+
+/// Levenshtein distance, one row at a time
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
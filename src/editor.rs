@@ -0,0 +1,107 @@
+use std::{env, path::Path};
+
+use color_eyre::eyre::{eyre, Result};
+use tokio::{fs, io::AsyncWriteExt, process::Command};
+
+use crate::{crypt, file::parse_task, journal, task_item::TaskItem, tui::Tui};
+
+/// The editor of the user, `$VISUAL` or `$EDITOR` as git picks it, `vi` without either
+fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_owned())
+}
+
+/// Opens `path` in the editor with the terminal handed over to it, until it is closed
+pub async fn open(tui: &mut Tui, path: &Path) -> Result<()> {
+    let editor = editor();
+    // the variable may hold arguments too, e.g. `code --wait`
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg(&editor)
+        .arg(path);
+
+    let status = tui.run(&mut command).await?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(eyre!("{} exited with {}", editor, status)),
+    }
+}
+
+/// Lets the user change `content` in a temporary file, handing back what it was saved as
+pub async fn edit(tui: &mut Tui, content: &str) -> Result<String> {
+    let path = env::temp_dir().join(format!("tuitodo-{}.md", std::process::id()));
+    write_private(&path, content).await?;
+
+    let result = open(tui, &path).await;
+    let edited = fs::read_to_string(&path).await;
+    let _ = fs::remove_file(&path).await;
+    result?;
+    Ok(edited?)
+}
+
+/// Opens the todo file itself, one that is encrypted through a decrypted copy
+pub async fn edit_file(tui: &mut Tui, file: &str) -> Result<()> {
+    // the editor has to show everything, not the file without the changes in its journal
+    journal::compact(file).await?;
+
+    if !crypt::is_encrypted(file) {
+        return open(tui, Path::new(file)).await;
+    }
+    let content = crypt::read(file).await?;
+    let edited = edit(tui, &content).await?;
+    if edited != content {
+        fs::write(file, crypt::encrypt(file, edited).await?).await?;
+    }
+    Ok(())
+}
+
+/// Tasks of an encrypted file are no one else's business either
+async fn write_private(path: &Path, content: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    file.write_all(content.as_bytes()).await?;
+    Ok(())
+}
+
+/// A task the way it is edited: its text and metadata on the first line, as in the file,
+/// and its notes after a blank line
+pub fn task_buffer(task: &TaskItem) -> String {
+    let mut line = task.text.clone();
+    for (key, value) in &task.meta {
+        line.push_str(&format!(" {}:{}", key, value));
+    }
+
+    let mut buffer = line + "\n";
+    if !task.notes.is_empty() {
+        buffer.push('\n');
+        for note in &task.notes {
+            buffer.push_str(note);
+            buffer.push('\n');
+        }
+    }
+    buffer
+}
+
+/// `task` changed to what `buffer` says, `None` when the buffer has no text left so the
+/// edit is dropped
+pub fn apply_buffer(task: &TaskItem, buffer: &str) -> Option<TaskItem> {
+    let mut lines = buffer.lines().map(str::trim);
+    let first = lines.find(|l| !l.is_empty())?;
+
+    let parsed = parse_task(first, task.state.clone());
+    let mut edited = task.clone();
+    edited.text = parsed.text;
+    edited.meta = parsed.meta;
+    // the id stays, whatever became of it in the editor
+    edited.set_meta("id", task.id().map(str::to_owned));
+    edited.notes = lines.filter(|l| !l.is_empty()).map(str::to_owned).collect();
+    Some(edited)
+}
@@ -0,0 +1,221 @@
+use chrono::{NaiveDate, Utc};
+use color_eyre::eyre::Result;
+use serde_derive::Serialize;
+
+use crate::{
+    dates,
+    file::format_task,
+    hash,
+    task_item::{TaskItem, TaskState},
+};
+
+/// A task as written by the JSON and TSV outputs
+#[derive(Serialize)]
+pub struct TaskOutput<'a> {
+    index: usize,
+    id: Option<&'a str>,
+    state: &'static str,
+    text: &'a str,
+    depth: usize,
+    tags: Vec<&'a str>,
+    priority: Option<&'a str>,
+    due: Option<String>,
+}
+
+impl<'a> TaskOutput<'a> {
+    pub fn new(index: usize, task: &'a TaskItem) -> Self {
+        Self {
+            index,
+            id: task.id(),
+            state: task.state.name(),
+            text: &task.text,
+            depth: task.depth,
+            tags: task.tags(),
+            priority: task.priority(),
+            due: task.due().map(|d| d.format(dates::FORMAT).to_string()),
+        }
+    }
+
+    pub fn tsv(&self) -> String {
+        [
+            self.index.to_string(),
+            self.state.to_owned(),
+            self.priority.unwrap_or_default().to_owned(),
+            self.due.clone().unwrap_or_default(),
+            self.tags.join(","),
+            self.text.replace('\t', " "),
+        ]
+        .join("\t")
+    }
+}
+
+/// `(index, task)` pairs, where index is the 1-based task number
+pub type Numbered<'a> = Vec<(usize, &'a TaskItem)>;
+
+pub fn markdown(tasks: &Numbered) -> String {
+    tasks
+        .iter()
+        .map(|(_, task)| format_task(task) + "\n")
+        .collect()
+}
+
+/// todo.txt lines, priority as `(A)` and the remaining metadata as `key:value`
+pub fn todo_txt(tasks: &Numbered) -> String {
+    let mut out = String::new();
+
+    for (_, task) in tasks {
+        let mut line = String::new();
+        if task.state == TaskState::Done {
+            line.push_str("x ");
+        }
+        if let Some(priority) = task.priority() {
+            line.push_str(&format!("({}) ", priority));
+        }
+        line.push_str(&task.text);
+        for (key, value) in task.meta.iter().filter(|(k, _)| k != "pri") {
+            line.push_str(&format!(" {}:{}", key, value));
+        }
+
+        out.push_str(&line);
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn json(tasks: &Numbered) -> Result<String> {
+    let tasks: Vec<TaskOutput> = tasks.iter().map(|(i, t)| TaskOutput::new(*i, t)).collect();
+    Ok(serde_json::to_string_pretty(&tasks)? + "\n")
+}
+
+pub fn tsv(tasks: &Numbered) -> String {
+    tasks
+        .iter()
+        .map(|(i, task)| TaskOutput::new(*i, task).tsv() + "\n")
+        .collect()
+}
+
+/// Comma separated values with a header row and all task metadata
+pub fn csv(tasks: &Numbered) -> String {
+    let date = |d: Option<NaiveDate>| d.map(|d| d.format(dates::FORMAT).to_string());
+    let mut out =
+        String::from("index,state,text,priority,tags,due,created,completed,tracked_seconds\r\n");
+
+    for (i, task) in tasks {
+        let fields = [
+            i.to_string(),
+            task.state.name().to_owned(),
+            task.text.clone(),
+            task.priority().unwrap_or_default().to_owned(),
+            task.tags().join(" "),
+            date(task.due()).unwrap_or_default(),
+            date(task.created()).unwrap_or_default(),
+            date(task.completed()).unwrap_or_default(),
+            task.tracked().to_string(),
+        ];
+
+        let row: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+
+    out
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// iCalendar with one VTODO per task
+pub fn ics(tasks: &Numbered) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//tuitodo//tuitodo//EN".to_owned(),
+    ];
+
+    for (_, task) in tasks {
+        lines.push("BEGIN:VTODO".to_owned());
+        lines.push(format!("UID:{}", uid(task)));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("SUMMARY:{}", ics_escape(&task.text)));
+
+        match task.state {
+            TaskState::Open => lines.push("STATUS:NEEDS-ACTION".to_owned()),
+            TaskState::Done => {
+                lines.push("STATUS:COMPLETED".to_owned());
+                if let Some(completed) = task.completed() {
+                    lines.push(format!("COMPLETED:{}T000000Z", completed.format("%Y%m%d")));
+                }
+            }
+        }
+        if let Some(scheduled) = task.scheduled() {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", scheduled.format("%Y%m%d")));
+        }
+        if let Some(due) = task.due() {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+        }
+        if let Some(priority) = task.priority().and_then(ics_priority) {
+            lines.push(format!("PRIORITY:{}", priority));
+        }
+        let tags = task.tags();
+        if !tags.is_empty() {
+            let categories: Vec<String> = tags.iter().map(|t| ics_escape(&t[1..])).collect();
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+
+        lines.push("END:VTODO".to_owned());
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    lines.iter().map(|l| fold(l) + "\r\n").collect()
+}
+
+/// The imported UID or the task's `id` if it has one, otherwise a hash of its text
+fn uid(task: &TaskItem) -> String {
+    if let Some(uid) = task.get_meta("uid") {
+        return uid.to_owned();
+    }
+    if let Some(id) = task.get_meta("id") {
+        return format!("{}@tuitodo", id);
+    }
+
+    format!("{:016x}@tuitodo", hash::stable(task.text.as_bytes()))
+}
+
+/// `A`..`I` map to iCalendar's 1 (highest) to 9 (lowest)
+fn ics_priority(priority: &str) -> Option<u8> {
+    match priority.as_bytes() {
+        [p @ b'A'..=b'Z'] => Some((p - b'A' + 1).min(9)),
+        _ => None,
+    }
+}
+
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Content lines longer than 75 octets continue on the next line after a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for char in line.chars() {
+        if length + char.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(char);
+        length += char.len_utf8();
+    }
+
+    folded
+}
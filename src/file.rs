@@ -1,33 +1,173 @@
+use std::collections::HashSet;
+
 use tokio::{
-    fs::{self, File, OpenOptions},
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
+    fs::{self, OpenOptions},
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
 };
 
-use crate::task_item::{TaskItem, TaskState};
-use color_eyre::eyre::Result;
+use crate::{
+    crypt, front_matter, git,
+    hooks::{self, Hook},
+    journal,
+    remote::Remote,
+    task_item::{self, TaskItem, TaskState},
+};
+use color_eyre::eyre::{eyre, Result};
+use tracing::{debug, info, trace};
 
 #[derive(Clone)]
 pub struct TaskStore {
     pub items: Vec<TaskItem>,
     file_path: String,
+    auto_commit: bool,
+    remote: Option<Remote>,
 }
 
 impl TaskStore {
-    pub async fn new(file: String) -> Result<TaskStore, ()> {
-        let Ok(items) = load_tasks(&file).await else {
-            return Err(());
-        };
+    /// Loads `file`, a missing file is an empty list
+    pub async fn new(file: String) -> Result<TaskStore> {
+        let items = load_tasks(&file).await?;
 
         Ok(Self {
             items,
             file_path: file,
+            auto_commit: false,
+            remote: None,
         })
     }
 
+    /// A store for `file` whose tasks are added later, see `stream_tasks`
+    pub fn unloaded(file: String) -> TaskStore {
+        Self {
+            items: vec![],
+            file_path: file,
+            auto_commit: false,
+            remote: None,
+        }
+    }
+
+    /// Commit the file to its git repository after every save
+    pub fn auto_commit(mut self, auto_commit: bool) -> Self {
+        self.auto_commit = auto_commit;
+        self
+    }
+
+    /// Upload the file to where it was fetched from after every save
+    pub fn remote(mut self, remote: Option<Remote>) -> Self {
+        self.remote = remote;
+        self
+    }
+
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
     pub async fn save(self) -> Result<()> {
-        write_tasks(&self.file_path, self.items).await?;
+        let message = self.commit_message();
+        hooks::fire(Hook::PreSave, &self.file_path, None).await;
+        let count = self.items.len();
+        // the journal leaves the file behind, which git, remotes and encryption can't have
+        let journaled =
+            !self.auto_commit && self.remote.is_none() && !crypt::is_encrypted(&self.file_path);
+        // a push that doesn't go through leaves the local copy ahead of the remote file
+        if let Some(remote) = &self.remote {
+            remote.mark_unpushed().await?;
+        }
+        match journaled {
+            true => journal::save(&self.file_path, self.items).await?,
+            false => write_tasks(&self.file_path, &self.items).await?,
+        }
+        info!(file = %self.file_path, tasks = count, "saved");
+
+        if self.auto_commit {
+            git::commit(&self.file_path, &message).await?;
+        }
+        if let Some(remote) = &self.remote {
+            remote
+                .push()
+                .await
+                .map_err(|e| eyre!("offline, {} not updated: {}", remote, e))?;
+        }
+
+        hooks::fire(Hook::PostSave, &self.file_path, None).await;
         Ok(())
     }
+
+    fn commit_message(&self) -> String {
+        let done = self
+            .items
+            .iter()
+            .filter(|t| t.state == TaskState::Done)
+            .count();
+        let name = std::path::Path::new(&self.file_path)
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+
+        format!(
+            "Update {}: {} open, {} done",
+            name,
+            self.items.len() - done,
+            done
+        )
+    }
+}
+
+/// Lines parsed before the tasks so far are handed over
+const CHUNK_LINES: usize = 5000;
+
+/// Reads the tasks of a big file bit by bit, calling `chunk` with every batch of tasks and
+/// how much of the file has been read, from 0 to 1
+pub async fn stream_tasks(
+    file_path: &str,
+    mut chunk: impl FnMut(Vec<TaskItem>, f64),
+) -> Result<()> {
+    // encrypted files can only be read as a whole, journals only apply to all of it
+    if crypt::is_encrypted(file_path) || journal::exists(file_path).await {
+        let tasks = load_tasks(file_path).await?;
+        let total = tasks.len().max(1);
+        let mut read = 0;
+        for tasks in tasks.chunks(CHUNK_LINES) {
+            read += tasks.len();
+            chunk(tasks.to_vec(), read as f64 / total as f64);
+        }
+        return Ok(());
+    }
+
+    let total = fs::metadata(file_path).await?.len().max(1);
+    let mut lines = BufReader::new(fs::File::open(file_path).await?).lines();
+    let mut read = 0;
+    // settings rather than tasks, whatever they look like
+    if let Some(block) = front_matter::read(file_path).await? {
+        for _ in 0..block.lines().count() {
+            lines.next_line().await?;
+        }
+        read = block.len() as u64;
+    }
+    let mut tasks = vec![];
+    let mut parsed = 0;
+    let mut ids = HashSet::new();
+
+    while let Some(line) = lines.next_line().await? {
+        read += line.len() as u64 + 1;
+        push_line(&mut tasks, &line);
+        parsed += 1;
+
+        if parsed == CHUNK_LINES {
+            // the notes of the last task may still follow
+            let last = tasks.pop();
+            task_item::assign_ids(&mut tasks, &mut ids, file_path);
+            chunk(std::mem::take(&mut tasks), read as f64 / total as f64);
+            tasks.extend(last);
+            parsed = 0;
+            // give the interface a turn between chunks
+            tokio::task::yield_now().await;
+        }
+    }
+
+    task_item::assign_ids(&mut tasks, &mut ids, file_path);
+    chunk(tasks, 1.0);
+    Ok(())
 }
 
 async fn load_tasks(file_path: &str) -> Result<Vec<TaskItem>> {
@@ -35,61 +175,241 @@ async fn load_tasks(file_path: &str) -> Result<Vec<TaskItem>> {
         return Ok(vec![]);
     }
 
-    let file = File::open(&file_path).await?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
-    let mut items = Vec::new();
+    let content = match crypt::is_encrypted(file_path) {
+        true => crypt::read(file_path).await?,
+        false => fs::read_to_string(file_path).await?,
+    };
 
-    while let Some(line) = lines.next_line().await? {
-        let state_char = match line.chars().nth(3) {
-            Some(char) => char,
-            None => continue,
-        };
+    let (_, content) = front_matter::split(&content);
+    let lines = content.lines().count();
+    let mut tasks = parse_tasks(content);
+    debug!(
+        file = file_path,
+        tasks = tasks.len(),
+        skipped = lines - tasks.len(),
+        "loaded"
+    );
 
-        let state = match state_char {
-            'x' => TaskState::Done,
-            _ => TaskState::Open,
-        };
+    journal::load(file_path, &mut tasks).await?;
+    // tasks written by hand or by older versions have none yet, they are saved with the rest
+    task_item::assign_ids(&mut tasks, &mut HashSet::new(), file_path);
+    Ok(tasks)
+}
 
-        let text = match line.split("] ").nth(1) {
-            Some(text) => text.to_owned(),
-            None => continue,
-        };
+/// Parses the tasks of markdown checkbox lines along with their notes
+pub fn parse_tasks(content: &str) -> Vec<TaskItem> {
+    let mut tasks = vec![];
+    for line in content.lines() {
+        push_line(&mut tasks, line);
+    }
+    tasks
+}
 
-        items.push(TaskItem::new(text, state))
+/// Adds the task on `line` to `tasks`, or the note on it to the last task when it is
+/// indented below it
+fn push_line(tasks: &mut Vec<TaskItem>, line: &str) {
+    if let Some(task) = parse_line(line) {
+        tasks.push(task);
+        return;
+    }
+    match tasks.last_mut() {
+        Some(task) if indentation(line) > task.depth && !line.trim().is_empty() => {
+            task.notes.push(line.trim().to_owned())
+        }
+        _ => {}
     }
+}
+
+/// Parses a markdown checkbox line (`- [x] text`), `None` for anything else
+pub fn parse_line(line: &str) -> Option<TaskItem> {
+    let depth = indentation(line);
+    let line = line.trim_start();
 
-    Ok(items)
+    let checkbox = line
+        .strip_prefix("- [")
+        .or_else(|| line.strip_prefix("* ["));
+    let Some((state_char, text)) = checkbox.and_then(|rest| {
+        let mut chars = rest.chars();
+        let state = chars.next()?;
+        Some((state, chars.as_str().strip_prefix("] ")?))
+    }) else {
+        trace!(line, "not a task, skipped");
+        return None;
+    };
+
+    let state = match state_char {
+        ' ' => TaskState::Open,
+        'x' | 'X' => TaskState::Done,
+        _ => {
+            trace!(line, "not a task, skipped");
+            return None;
+        }
+    };
+
+    let mut item = parse_task(text, state);
+    item.depth = depth;
+    Some(item)
 }
 
-fn get_state_char(state: &TaskState) -> String {
-    match state {
-        TaskState::Done => "x".to_owned(),
-        TaskState::Open => " ".to_owned(),
+/// Adds tasks to the end of the file without rewriting the rest of it
+pub async fn append_tasks(file_path: &str, tasks: &[TaskItem]) -> Result<()> {
+    // encrypted files can only be written as a whole
+    if crypt::is_encrypted(file_path) {
+        let mut items = load_tasks(file_path).await?;
+        items.extend_from_slice(tasks);
+        return write_tasks(file_path, &items).await;
     }
-}
 
-async fn write_tasks(file_path: &str, tasks: Vec<TaskItem>) -> Result<()> {
-    let file = OpenOptions::new()
-        .write(true)
+    // the journal's positions don't account for lines added behind its back
+    journal::compact(file_path).await?;
+
+    let needs_newline = match fs::read(file_path).await {
+        Ok(content) => content.last().is_some_and(|c| *c != b'\n'),
+        Err(_) => false,
+    };
+
+    let mut file = OpenOptions::new()
+        .append(true)
         .create(true)
-        .truncate(true)
         .open(file_path)
         .await?;
 
-    let mut writer = BufWriter::new(file);
+    let mut content = String::new();
+    if needs_newline {
+        content.push('\n');
+    }
+    for task in tasks {
+        content.push_str(&format_task(task));
+        content.push('\n');
+    }
+
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await?;
+
+    Ok(())
+}
+
+/// Metadata keys that are read from and written back to the end of a task line
+const META_KEYS: &[&str] = &[
+    "due",
+    "scheduled",
+    "bucket",
+    "pri",
+    "tracked",
+    "started",
+    "rec",
+    "done",
+    "created",
+    "completed",
+    "uid",
+    "id",
+    "issue",
+    "time",
+    "updated",
+];
+
+pub fn parse_task(line: &str, state: TaskState) -> TaskItem {
+    let mut words = Vec::new();
+    let mut meta = Vec::new();
+
+    for word in line.split(' ') {
+        match word.split_once(':') {
+            Some((key, value)) if META_KEYS.contains(&key) && !value.is_empty() => {
+                meta.push((key.to_owned(), value.to_owned()))
+            }
+            Some((key, _)) => {
+                trace!(key, "unknown metadata key, kept in the text");
+                words.push(word)
+            }
+            _ => words.push(word),
+        }
+    }
+
+    let mut item = TaskItem::new(words.join(" "), state);
+    item.meta = meta;
+    item
+}
+
+/// Two spaces or one tab per nesting level
+fn indentation(line: &str) -> usize {
+    let mut spaces = 0;
+    for char in line.chars() {
+        match char {
+            ' ' => spaces += 1,
+            '\t' => spaces += 2,
+            _ => break,
+        }
+    }
+
+    spaces / 2
+}
+
+pub fn format_task(task: &TaskItem) -> String {
+    let mut line = format!(
+        "{}- [{}] {}",
+        "  ".repeat(task.depth),
+        get_state_char(&task.state),
+        task.text
+    );
+
+    for (key, value) in &task.meta {
+        line.push_str(&format!(" {}:{}", key, value));
+    }
+    for note in &task.notes {
+        line.push_str(&format!("\n{}{}", "  ".repeat(task.depth + 1), note));
+    }
+
+    line
+}
 
-    let lines: Vec<String> = tasks
-        .iter()
-        .map(|task| format!("- [{}] {}", get_state_char(&task.state), task.text))
-        .collect();
+fn get_state_char(state: &TaskState) -> String {
+    match state {
+        TaskState::Done => "x".to_owned(),
+        TaskState::Open => " ".to_owned(),
+    }
+}
 
-    for line in lines {
-        writer.write_all(line.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
+/// What the file holds with `tasks` in it, encrypted if it is
+async fn render(file_path: &str, tasks: &[TaskItem]) -> Result<Vec<u8>> {
+    // the block at the top belongs to the file, not to its tasks
+    let mut content = front_matter::read(file_path).await?.unwrap_or_default();
+    content.extend(tasks.iter().map(|t| format_task(t) + "\n"));
+    match crypt::is_encrypted(file_path) {
+        true => crypt::encrypt(file_path, content).await,
+        false => Ok(content.into_bytes()),
     }
+}
 
-    writer.flush().await?;
+pub(crate) async fn write_tasks(file_path: &str, tasks: &[TaskItem]) -> Result<()> {
+    fs::write(file_path, render(file_path, tasks).await?).await?;
+    journal::clear(file_path).await;
 
     Ok(())
 }
+
+/// Writes `tasks` to `<file>.unsaved` next to the file, for changes that would overwrite
+/// someone else's. Returns where they went.
+pub(crate) async fn write_unsaved(file_path: &str, tasks: &[TaskItem]) -> Result<String> {
+    let path = format!("{}.unsaved", file_path);
+    fs::write(&path, render(file_path, tasks).await?).await?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_checkbox_lines_are_tasks() {
+        let tasks = parse_tasks("- [ ] open\n  see [docs] here\n  - [x] done\n* [X] also done\n");
+        let texts: Vec<_> = tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, ["open", "done", "also done"]);
+        assert_eq!(tasks[0].notes, ["see [docs] here"]);
+        assert_eq!(tasks[1].state, TaskState::Done);
+        assert_eq!(tasks[1].depth, 1);
+
+        for line in ["ab] cd", "- [?] odd", "- [ ]no space", "1. [ ] numbered"] {
+            assert!(parse_line(line).is_none(), "{line}");
+        }
+    }
+}
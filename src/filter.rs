@@ -0,0 +1,418 @@
+use std::{cmp::Ordering, ops::Range};
+
+use chrono::NaiveDate;
+use serde_derive::Deserialize;
+
+use crate::{
+    dates, plugins, stale,
+    task_item::{Bucket, TaskItem, TaskState},
+};
+
+/// `[search]` section of the config, used by the search in the interface and `--filter`
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Words and patterns with capitals match only that case, all others ignore case
+    pub smart_case: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self { smart_case: true }
+    }
+}
+
+impl SearchConfig {
+    /// Whether `query` has to match with the same case
+    pub fn case_sensitive(&self, query: &str) -> bool {
+        self.smart_case && has_uppercase(query)
+    }
+}
+
+/// Whether `pattern` has capitals outside of escapes like `\S` and classes like `\p{Lu}`
+fn has_uppercase(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                if let Some('p' | 'P') = chars.next() {
+                    // `\pL` or `\p{Lu}`
+                    if chars.next() == Some('{') {
+                        chars.by_ref().find(|c| *c == '}');
+                    }
+                }
+            }
+            c if c.is_uppercase() => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// A parsed filter expression like `due:<1d and not done or #work`
+///
+/// Terms are `done`, `open`, `stale`, `waiting`, `someday`, `due:<op><date>`,
+/// `scheduled:<op><date>`,
+/// `pri:<op><letter>`, `#tag`,
+/// `@context` and plain words matched against the text. They combine with `and` (also implied between
+/// terms), `or`, `not` and parentheses. Plugins can add terms as `is:name`.
+#[derive(Clone, Debug)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    State(TaskState),
+    /// Untouched for longer than `stale.days` as of that day
+    Stale(NaiveDate),
+    Bucket(Bucket),
+    Due(Ordering, bool, NaiveDate),
+    Scheduled(Ordering, bool, NaiveDate),
+    Priority(Ordering, bool, String),
+    Tag(String),
+    /// A word and whether it only matches that exact case
+    Text(String, bool),
+    /// `is:name`, a filter registered by a plugin
+    Plugin(String),
+}
+
+impl Filter {
+    pub fn parse(input: &str, today: NaiveDate, config: &SearchConfig) -> Result<Filter, String> {
+        let tokens = tokenize(input);
+        if tokens.is_empty() {
+            return Err("empty filter".to_owned());
+        }
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            today,
+            config,
+        };
+        let filter = parser.or()?;
+
+        match parser.peek() {
+            Some(token) => Err(format!("unexpected `{}`", token)),
+            None => Ok(filter),
+        }
+    }
+
+    pub fn matches(&self, task: &TaskItem) -> bool {
+        match self {
+            Filter::And(a, b) => a.matches(task) && b.matches(task),
+            Filter::Or(a, b) => a.matches(task) || b.matches(task),
+            Filter::Not(a) => !a.matches(task),
+            Filter::State(state) => task.state == *state,
+            Filter::Stale(today) => stale::is_stale(task, *today),
+            Filter::Bucket(bucket) => task.bucket() == Some(*bucket),
+            Filter::Due(ordering, or_equal, date) => task
+                .due()
+                .is_some_and(|due| compare(due.cmp(date), *ordering, *or_equal)),
+            Filter::Scheduled(ordering, or_equal, date) => task
+                .scheduled()
+                .is_some_and(|s| compare(s.cmp(date), *ordering, *or_equal)),
+            Filter::Priority(ordering, or_equal, priority) => task
+                .priority()
+                .is_some_and(|p| compare(p.cmp(priority.as_str()), *ordering, *or_equal)),
+            Filter::Tag(tag) => task.tags().iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Filter::Text(text, true) => task.text.contains(text.as_str()),
+            Filter::Text(text, false) => task.text.to_lowercase().contains(&text.to_lowercase()),
+            Filter::Plugin(name) => plugins::get().is_some_and(|p| p.matches(name, task)),
+        }
+    }
+
+    /// The parts of `text` matched by word and tag terms, sorted and merged where they
+    /// overlap; terms under `not` match nothing worth showing
+    pub fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+        self.collect_highlights(text, &mut ranges);
+        ranges.sort_by_key(|r| r.start);
+
+        let mut merged: Vec<Range<usize>> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    fn collect_highlights(&self, text: &str, ranges: &mut Vec<Range<usize>>) {
+        match self {
+            Filter::And(a, b) | Filter::Or(a, b) => {
+                a.collect_highlights(text, ranges);
+                b.collect_highlights(text, ranges);
+            }
+            Filter::Tag(tag) => {
+                let mut start = 0;
+                for word in text.split(' ') {
+                    if word.eq_ignore_ascii_case(tag) {
+                        ranges.push(start..start + word.len());
+                    }
+                    start += word.len() + 1;
+                }
+            }
+            Filter::Text(term, case_sensitive) => {
+                for (start, _) in text.char_indices() {
+                    if let Some(len) = match_len(&text[start..], term, *case_sensitive) {
+                        ranges.push(start..start + len);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// How many bytes at the start of `text` match `term`, if they do
+fn match_len(text: &str, term: &str, case_sensitive: bool) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for wanted in term.chars() {
+        let (_, char) = chars.next()?;
+        let same = match case_sensitive {
+            true => char == wanted,
+            false => char.to_lowercase().eq(wanted.to_lowercase()),
+        };
+        if !same {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(text.len(), |(i, _)| i))
+}
+
+fn compare(actual: Ordering, wanted: Ordering, or_equal: bool) -> bool {
+    actual == wanted || (or_equal && actual == Ordering::Equal)
+}
+
+fn tokenize(input: &str) -> Vec<String> {
+    input
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .split_whitespace()
+        .map(|s| s.to_owned())
+        .collect()
+}
+
+struct Parser<'a> {
+    tokens: Vec<String>,
+    position: usize,
+    today: NaiveDate,
+    config: &'a SearchConfig,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|s| s.as_str())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        while self.peek() == Some("or") {
+            self.next();
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.unary()?;
+        loop {
+            match self.peek() {
+                Some("and") => {
+                    self.next();
+                }
+                Some("or") | Some(")") | None => return Ok(filter),
+                _ => {}
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        match self.next().as_deref() {
+            Some("not") => Ok(Filter::Not(Box::new(self.unary()?))),
+            Some("(") => {
+                let filter = self.or()?;
+                match self.next().as_deref() {
+                    Some(")") => Ok(filter),
+                    _ => Err("missing `)`".to_owned()),
+                }
+            }
+            Some(token) => self.term(token),
+            None => Err("filter ends unexpectedly".to_owned()),
+        }
+    }
+
+    fn term(&self, token: &str) -> Result<Filter, String> {
+        match token {
+            "done" => return Ok(Filter::State(TaskState::Done)),
+            "open" => return Ok(Filter::State(TaskState::Open)),
+            "stale" => return Ok(Filter::Stale(self.today)),
+            "waiting" => return Ok(Filter::Bucket(Bucket::Waiting)),
+            "someday" => return Ok(Filter::Bucket(Bucket::Someday)),
+            _ => {}
+        }
+
+        if let Some(value) = token.strip_prefix("due:") {
+            let (ordering, or_equal, value) = comparison(value);
+            let date =
+                parse_date(value, self.today).ok_or_else(|| format!("invalid date `{}`", value))?;
+            return Ok(Filter::Due(ordering, or_equal, date));
+        }
+        if let Some(value) = token.strip_prefix("scheduled:") {
+            let (ordering, or_equal, value) = comparison(value);
+            let date =
+                parse_date(value, self.today).ok_or_else(|| format!("invalid date `{}`", value))?;
+            return Ok(Filter::Scheduled(ordering, or_equal, date));
+        }
+
+        if let Some(value) = token.strip_prefix("pri:") {
+            let (ordering, or_equal, value) = comparison(value);
+            return Ok(Filter::Priority(ordering, or_equal, value.to_uppercase()));
+        }
+
+        if let Some(name) = token.strip_prefix("is:") {
+            return match plugins::get().is_some_and(|p| p.has_filter(name)) {
+                true => Ok(Filter::Plugin(name.to_owned())),
+                false => Err(format!("no plugin filter `{}`", name)),
+            };
+        }
+
+        if token.len() > 1 && (token.starts_with('#') || token.starts_with('@')) {
+            return Ok(Filter::Tag(token.to_owned()));
+        }
+
+        let case_sensitive = self.config.case_sensitive(token);
+        Ok(Filter::Text(token.to_owned(), case_sensitive))
+    }
+}
+
+/// Splits a leading `<`, `<=`, `>`, `>=` or `=` off a value
+fn comparison(value: &str) -> (Ordering, bool, &str) {
+    for (prefix, ordering, or_equal) in [
+        ("<=", Ordering::Less, true),
+        (">=", Ordering::Greater, true),
+        ("<", Ordering::Less, false),
+        (">", Ordering::Greater, false),
+        ("=", Ordering::Equal, false),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (ordering, or_equal, rest);
+        }
+    }
+
+    (Ordering::Equal, false, value)
+}
+
+/// Like `dates::parse`, but `1d`/`2w` count from today without needing a `+`
+fn parse_date(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    dates::parse(value, today).or_else(|| match value.strip_prefix('-') {
+        Some(past) => dates::parse(&format!("+{}", past), today)
+            .and_then(|future| today.checked_sub_signed(future - today)),
+        None => dates::parse(&format!("+{}", value), today),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::parse_task;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+    }
+
+    fn filter(input: &str) -> Filter {
+        Filter::parse(input, today(), &SearchConfig::default()).unwrap()
+    }
+
+    /// Which of `lines` the filter keeps, by text
+    fn kept(input: &str, lines: &[&str]) -> Vec<String> {
+        let filter = filter(input);
+        (lines.iter())
+            .map(|line| parse_task(line, TaskState::Open))
+            .filter(|task| filter.matches(task))
+            .map(|task| task.text)
+            .collect()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let lines = ["a #work", "b #home", "c #work #home", "d"];
+        assert_eq!(kept("#work #home or d", &lines), ["c #work #home", "d"]);
+        assert_eq!(kept("#work and (#home or c)", &lines), ["c #work #home"]);
+        assert_eq!(kept("not #work", &lines), ["b #home", "d"]);
+        assert_eq!(kept("not (#work or #home)", &lines), ["d"]);
+    }
+
+    #[test]
+    fn compares_dates_relative_to_today() {
+        let lines = [
+            "past due:2024-04-30",
+            "today due:2024-05-01",
+            "soon due:2024-05-03",
+            "never",
+        ];
+        assert_eq!(kept("due:<today", &lines), ["past"]);
+        assert_eq!(kept("due:<=today", &lines), ["past", "today"]);
+        assert_eq!(kept("due:>1d", &lines), ["soon"]);
+        assert_eq!(kept("due:-1d", &lines), ["past"]);
+        assert_eq!(kept("due:2024-05-03", &lines), ["soon"]);
+    }
+
+    #[test]
+    fn compares_priorities_by_letter() {
+        let lines = ["a pri:A", "b pri:B", "c pri:C", "none"];
+        assert_eq!(kept("pri:a", &lines), ["a"]);
+        assert_eq!(kept("pri:<=b", &lines), ["a", "b"]);
+        assert_eq!(kept("pri:>B", &lines), ["c"]);
+    }
+
+    #[test]
+    fn words_use_smart_case() {
+        let lines = ["Call Mom", "call the bank"];
+        assert_eq!(kept("call", &lines), ["Call Mom", "call the bank"]);
+        assert_eq!(kept("Call", &lines), ["Call Mom"]);
+
+        let config = SearchConfig { smart_case: false };
+        let filter = Filter::parse("Call", today(), &config).unwrap();
+        assert!(filter.matches(&parse_task("call the bank", TaskState::Open)));
+    }
+
+    #[test]
+    fn rejects_broken_expressions() {
+        let config = SearchConfig::default();
+        for (input, error) in [
+            ("", "empty filter"),
+            ("(done", "missing `)`"),
+            ("done )", "unexpected `)`"),
+            ("done or", "filter ends unexpectedly"),
+            ("due:soon", "invalid date `soon`"),
+            ("is:nothing", "no plugin filter `nothing`"),
+        ] {
+            let result = Filter::parse(input, today(), &config);
+            assert_eq!(result.unwrap_err(), error, "{}", input);
+        }
+    }
+
+    #[test]
+    fn highlights_words_and_tags_but_not_negations() {
+        let text = "Buy milk #shop milk";
+        assert_eq!(filter("milk").highlights(text), [4..8, 15..19]);
+        assert_eq!(filter("#shop or buy").highlights(text), [0..3, 9..14]);
+        assert_eq!(filter("uy mil").highlights(text), [1..3, 4..7, 15..18]);
+        assert!(filter("not milk").highlights(text).is_empty());
+    }
+
+    #[test]
+    fn smart_case_skips_escapes() {
+        assert!(!has_uppercase(r"\S+\p{Lu}\pL"));
+        assert!(has_uppercase(r"\sA"));
+    }
+}
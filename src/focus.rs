@@ -0,0 +1,75 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    pomodoro::Pomodoro,
+    task_item::{format_duration, TaskItem, TaskState},
+    theme,
+};
+
+pub fn ui(f: &mut Frame, area: Rect, task: &TaskItem, pomodoro: Option<&Pomodoro>) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(7),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    let area = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(15),
+            Constraint::Percentage(70),
+            Constraint::Percentage(15),
+        ])
+        .split(rows[1])[1];
+
+    let text_style = Style::default()
+        .fg(theme::palette().text)
+        .add_modifier(Modifier::BOLD)
+        .patch(theme::state_style(task));
+
+    let timer = match pomodoro {
+        Some(pomodoro) => {
+            let remaining = pomodoro.remaining();
+            let glyph = theme::glyph(&theme::glyphs().timer);
+            format!("{}{:02}:{:02}", glyph, remaining / 60, remaining % 60)
+        }
+        None if task.tracked() > 0 => {
+            let glyph = theme::glyph(&theme::glyphs().timer);
+            format!("{}{}", glyph, format_duration(task.tracked()))
+        }
+        None => String::new(),
+    };
+
+    // crossing out isn't read out
+    let text = match (theme::accessible(), &task.state) {
+        (true, TaskState::Done) => format!("[x] {}", task.text),
+        (true, TaskState::Open) => format!("[ ] {}", task.text),
+        (false, _) => task.text.clone(),
+    };
+    let lines = vec![
+        Line::from(Span::styled(text, text_style)),
+        Line::default(),
+        Line::from(Span::styled(timer, Style::default().fg(Color::Green))),
+    ];
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .padding(Padding::uniform(1));
+
+    f.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(block),
+        area,
+    );
+}
@@ -0,0 +1,294 @@
+use color_eyre::eyre::{eyre, Result};
+use ratatui::style::Color;
+use serde_derive::Deserialize;
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt, BufReader},
+};
+
+use crate::{
+    crypt,
+    task_item::{TaskItem, TaskState},
+};
+
+/// Lines looked through for the end of a block; a file that starts with `---` for another
+/// reason isn't read any further
+const MAX_LINES: usize = 200;
+
+/// Settings a todo file carries for itself in a block at its top, YAML between `---` lines
+/// or TOML between `+++` lines:
+///
+/// ```text
+/// ---
+/// tags: [work]
+/// sort: due
+/// hide_done: true
+/// accent: magenta
+/// progress: gauge
+/// complete_parents: true
+/// ---
+/// ```
+///
+/// Keys other tools put there are left alone, and the block is written back as it is.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FileSettings {
+    /// Added to every new task that doesn't mention them yet, `#` is added when missing
+    pub tags: Vec<String>,
+    /// The order tasks are put in when the file is opened
+    pub sort: Option<SortOrder>,
+    /// Lists only the open tasks
+    pub hide_done: bool,
+    /// Used instead of the theme's, to tell files apart
+    pub accent: Option<Accent>,
+    /// How the share of done subtasks is shown next to their parent
+    pub progress: Progress,
+    /// Completes a parent once all its subtasks are done and reopens it with an open one
+    pub complete_parents: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Progress {
+    /// `2/5`
+    #[default]
+    Fraction,
+    /// A bar of five cells
+    Gauge,
+    Off,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Earliest due date first, tasks without one last
+    Due,
+    /// The highest of the configured priorities first, tasks without one last
+    Priority,
+    Text,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Accent(pub Color);
+
+impl TryFrom<String> for Accent {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse()
+            .map(Accent)
+            .map_err(|_| format!("unknown colour `{}`", value))
+    }
+}
+
+impl FileSettings {
+    /// Mentions the default tags in `task` unless it already does
+    pub fn tag(&self, task: &mut TaskItem) {
+        for tag in &self.tags {
+            let tag = match tag.starts_with(['#', '@']) {
+                true => tag.clone(),
+                false => format!("#{}", tag),
+            };
+            if !task.tags().iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                task.text = format!("{} {}", task.text, tag);
+            }
+        }
+    }
+
+    /// Whether `task` is listed at all
+    pub fn lists(&self, task: &TaskItem) -> bool {
+        !(self.hide_done && task.state == TaskState::Done)
+    }
+}
+
+impl SortOrder {
+    /// Sorts `tasks` keeping subtasks below their parent, tasks that compare equal stay
+    /// in the order they were in; `priorities` go from the highest down
+    pub fn sort(self, tasks: &mut Vec<TaskItem>, priorities: &[&str]) {
+        let mut groups: Vec<Vec<TaskItem>> = vec![];
+        for task in tasks.drain(..) {
+            match groups.last_mut() {
+                Some(group) if task.depth > 0 => group.push(task),
+                _ => groups.push(vec![task]),
+            }
+        }
+
+        match self {
+            SortOrder::Due => groups.sort_by_key(|g| {
+                let due = g[0].due();
+                (due.is_none(), due)
+            }),
+            SortOrder::Priority => groups.sort_by_key(|g| {
+                let priority = g[0].priority().map(|p| p.to_owned());
+                // ones that aren't configured come after the ones that are
+                let level = (priority.as_deref()).map(|p| {
+                    priorities
+                        .iter()
+                        .position(|l| *l == p)
+                        .unwrap_or(usize::MAX)
+                });
+                (priority.is_none(), level, priority)
+            }),
+            SortOrder::Text => groups.sort_by_key(|g| g[0].text.to_lowercase()),
+        }
+        tasks.extend(groups.into_iter().flatten());
+    }
+}
+
+/// The block at the top of `content` with its delimiters and line breaks, and what follows
+pub fn split(content: &str) -> (Option<&str>, &str) {
+    let mut lines = content.split_inclusive('\n');
+    let Some(delimiter) = lines
+        .next()
+        .map(|l| l.trim_end())
+        .filter(|l| is_delimiter(l))
+    else {
+        return (None, content);
+    };
+
+    let mut end = content
+        .split_inclusive('\n')
+        .next()
+        .unwrap_or_default()
+        .len();
+    for line in lines.take(MAX_LINES) {
+        end += line.len();
+        if line.trim_end() == delimiter {
+            return (Some(&content[..end]), &content[end..]);
+        }
+    }
+    (None, content)
+}
+
+fn is_delimiter(line: &str) -> bool {
+    line == "---" || line == "+++"
+}
+
+/// The block at the top of `file` exactly as it is written, `None` when there is none or
+/// no file yet
+pub async fn read(file: &str) -> Result<Option<String>> {
+    if fs::metadata(file).await.is_err() {
+        return Ok(None);
+    }
+    if crypt::is_encrypted(file) {
+        let content = crypt::read(file).await?;
+        return Ok(split(&content).0.map(|b| b.to_owned()));
+    }
+
+    // only as far as the block can go, the tasks of a big file aren't needed
+    let mut reader = BufReader::new(fs::File::open(file).await?);
+    let mut head = String::new();
+    for _ in 0..MAX_LINES + 2 {
+        if reader.read_line(&mut head).await? == 0 {
+            break;
+        }
+        if let (Some(block), _) = split(&head) {
+            return Ok(Some(block.to_owned()));
+        }
+    }
+    Ok(None)
+}
+
+/// The settings in the block at the top of `file`, the defaults without one
+pub async fn settings(file: &str) -> Result<FileSettings> {
+    match read(file).await? {
+        Some(block) => parse(&block).map_err(|e| eyre!("front matter of {}: {}", file, e)),
+        None => Ok(FileSettings::default()),
+    }
+}
+
+/// Reads a block with its delimiters, TOML between `+++` and YAML between `---`
+pub fn parse(block: &str) -> Result<FileSettings, String> {
+    let mut lines: Vec<&str> = block.lines().collect();
+    let toml = lines.first() == Some(&"+++");
+    lines.remove(0);
+    lines.pop();
+    let inner = lines.join("\n");
+
+    let table = match toml {
+        true => toml::from_str(&inner).map_err(|e| e.message().to_owned())?,
+        false => yaml(&inner)?,
+    };
+    toml::Value::Table(table)
+        .try_into()
+        .map_err(|e: toml::de::Error| e.message().to_owned())
+}
+
+/// The flat YAML front matter usually is: `key: value` with strings, numbers, booleans and
+/// lists, either `[a, b]` or one `- item` per line
+fn yaml(inner: &str) -> Result<toml::Table, String> {
+    let mut table = toml::Table::new();
+    let mut list: Option<String> = None;
+
+    for line in inner.lines() {
+        let line = strip_comment(line).trim_end();
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            let Some(toml::Value::Array(items)) = list.as_ref().and_then(|k| table.get_mut(k))
+            else {
+                return Err(format!("list item outside a list: `{}`", line.trim()));
+            };
+            items.push(scalar(item.trim()));
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            return Err(format!(
+                "nested values need TOML front matter between +++ lines: `{}`",
+                line.trim()
+            ));
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(format!("expected `key: value`, got `{}`", line));
+        };
+        let key = key.trim().to_owned();
+        let value = value.trim();
+        list = None;
+        let value = match value {
+            "" => {
+                list = Some(key.clone());
+                toml::Value::Array(vec![])
+            }
+            flow if flow.starts_with('[') && flow.ends_with(']') => {
+                let items = &flow[1..flow.len() - 1];
+                let items = items.split(',').map(str::trim).filter(|i| !i.is_empty());
+                toml::Value::Array(items.map(scalar).collect())
+            }
+            value => scalar(value),
+        };
+        table.insert(key, value);
+    }
+
+    Ok(table)
+}
+
+fn scalar(value: &str) -> toml::Value {
+    let quoted = ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)));
+    if let Some(text) = quoted {
+        return toml::Value::String(text.to_owned());
+    }
+    match value {
+        "true" => toml::Value::Boolean(true),
+        "false" => toml::Value::Boolean(false),
+        _ => match value.parse() {
+            Ok(number) => toml::Value::Integer(number),
+            Err(_) => toml::Value::String(value.to_owned()),
+        },
+    }
+}
+
+/// `line` without a ` # comment`, a `#` inside a word like `a#b` stays
+fn strip_comment(line: &str) -> &str {
+    match line.find(" #") {
+        Some(i) if !line[..i].contains(['"', '\'']) => &line[..i],
+        _ if line.starts_with('#') => "",
+        _ => line,
+    }
+}
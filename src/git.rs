@@ -0,0 +1,83 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Output,
+};
+
+use color_eyre::eyre::{eyre, Result};
+use serde_derive::Deserialize;
+use tokio::{process::Command, sync::Mutex};
+
+/// `[git]` section of the config, only used when the todo file is inside a git repository
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Commit the todo file after every save
+    pub auto_commit: bool,
+    /// `git pull --rebase` when the interface starts
+    pub pull: bool,
+}
+
+/// Saves run concurrently, but git only allows one writer of the index at a time
+static LOCK: Mutex<()> = Mutex::const_new(());
+
+fn dir(file: &str) -> PathBuf {
+    match Path::new(file).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    }
+}
+
+async fn output(file: &str, args: &[&str]) -> Result<Output> {
+    Ok(Command::new("git")
+        .arg("-C")
+        .arg(dir(file))
+        .args(args)
+        .output()
+        .await?)
+}
+
+async fn git(file: &str, args: &[&str]) -> Result<bool> {
+    Ok(output(file, args).await?.status.success())
+}
+
+async fn git_checked(file: &str, args: &[&str]) -> Result<()> {
+    let output = output(file, args).await?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(eyre!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+/// Rebases onto the upstream branch, if the file is in a repository that has one
+pub async fn pull(file: &str) -> Result<()> {
+    let _lock = LOCK.lock().await;
+    if !git(file, &["rev-parse", "--abbrev-ref", "@{upstream}"]).await? {
+        return Ok(());
+    }
+
+    git_checked(file, &["pull", "--rebase", "--autostash", "--quiet"]).await
+}
+
+/// Commits the current content of `file` when it is in a repository and has changes
+pub async fn commit(file: &str, message: &str) -> Result<()> {
+    let _lock = LOCK.lock().await;
+    if !git(file, &["rev-parse", "--is-inside-work-tree"]).await? {
+        return Ok(());
+    }
+
+    let path = Path::new(file)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(file);
+
+    git_checked(file, &["add", "--", path]).await?;
+    if git(file, &["diff", "--cached", "--quiet", "--", path]).await? {
+        return Ok(());
+    }
+
+    git_checked(file, &["commit", "--quiet", "-m", message, "--", path]).await
+}
@@ -0,0 +1,208 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use color_eyre::eyre::{eyre, Result};
+use reqwest::Client;
+use serde_derive::Deserialize;
+use serde_json::json;
+use tokio::fs;
+
+use crate::{
+    config::expand_home,
+    file::TaskStore,
+    hash,
+    history::data_dir,
+    secrets,
+    task_item::{TaskItem, TaskState},
+};
+
+const API: &str = "https://api.github.com";
+
+/// `[github]` section of the config, issues assigned to you are mirrored into `file`
+#[derive(Clone, Deserialize)]
+pub struct GithubConfig {
+    /// Todo file that holds the issues
+    pub file: String,
+    /// Repositories as `owner/name`
+    pub repos: Vec<String>,
+    /// Personal access token, the keyring or `$GITHUB_TOKEN` are used when not set
+    pub token: Option<String>,
+}
+
+impl GithubConfig {
+    pub fn file(&self) -> String {
+        expand_home(&self.file)
+    }
+}
+
+#[derive(Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    pull_request: Option<serde_json::Value>,
+}
+
+pub struct Github {
+    client: Client,
+    token: String,
+}
+
+impl Github {
+    pub fn new(config: &GithubConfig) -> Result<Self> {
+        let token = config
+            .token
+            .clone()
+            .or_else(|| secrets::get("github"))
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .ok_or_else(|| eyre!("no GitHub token, run `tuitodo auth github`"))?;
+
+        Ok(Self {
+            client: Client::builder().user_agent("tuitodo").build()?,
+            token,
+        })
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        Ok(self
+            .client
+            .get(url)
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Open issues assigned to the token's user, as `(owner/name#number, title)`
+    async fn assigned(&self, repos: &[String]) -> Result<Vec<(String, String)>> {
+        let user: User = self.get(&format!("{}/user", API)).await?;
+        let mut issues = vec![];
+
+        for repo in repos {
+            for page in 1.. {
+                let url = format!(
+                    "{}/repos/{}/issues?assignee={}&state=open&per_page=100&page={}",
+                    API, repo, user.login, page
+                );
+                let batch: Vec<Issue> = self.get(&url).await?;
+                let last = batch.len() < 100;
+
+                issues.extend(
+                    batch
+                        .into_iter()
+                        .filter(|i| i.pull_request.is_none())
+                        .map(|i| (format!("{}#{}", repo, i.number), i.title)),
+                );
+                if last {
+                    break;
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+
+    /// Closes or reopens the issue behind an `issue:owner/name#number` reference
+    pub async fn set_state(&self, issue: &str, state: &TaskState) -> Result<()> {
+        let (repo, number) = issue
+            .split_once('#')
+            .ok_or_else(|| eyre!("invalid issue `{}`", issue))?;
+        let state = match state {
+            TaskState::Done => "closed",
+            TaskState::Open => "open",
+        };
+
+        self.client
+            .patch(format!("{}/repos/{}/issues/{}", API, repo, number))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .json(&json!({ "state": state }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Brings `store` in line with the assigned issues: new ones are added, titles are
+    /// updated, issues that were closed or unassigned are marked done and ones reopened
+    /// since the last pull are opened again, issues completed locally get closed. Returns
+    /// how many tasks changed.
+    pub async fn pull(&self, config: &GithubConfig, store: &mut TaskStore) -> Result<usize> {
+        let issues = self.assigned(&config.repos).await?;
+        let path = pulled_path(store.file_path());
+        let pulled = load_pulled(&path).await;
+        let open: BTreeSet<String> = issues.iter().map(|(issue, _)| issue.clone()).collect();
+        let mut changed = 0;
+
+        for task in store.items.iter_mut() {
+            let Some(issue) = task.get_meta("issue") else {
+                continue;
+            };
+
+            match issues.iter().find(|(i, _)| i == issue) {
+                // reopened on GitHub since the last pull
+                Some(_)
+                    if task.state == TaskState::Done
+                        && pulled.as_ref().is_some_and(|p| !p.contains(issue)) =>
+                {
+                    task.toggle_state();
+                    changed += 1;
+                }
+                // completed here while the issue couldn't be closed
+                Some(_) if task.state == TaskState::Done => {
+                    self.set_state(issue, &task.state).await?;
+                }
+                Some((_, title)) if task.text != *title => {
+                    task.text = title.clone();
+                    changed += 1;
+                }
+                None if task.state == TaskState::Open => {
+                    task.toggle_state();
+                    changed += 1;
+                }
+                _ => {}
+            }
+        }
+
+        for (issue, title) in issues {
+            if store
+                .items
+                .iter()
+                .all(|t| t.get_meta("issue") != Some(issue.as_str()))
+            {
+                let mut task = TaskItem::new(title, TaskState::Open);
+                task.set_meta("issue", Some(issue));
+                store.items.push(task);
+                changed += 1;
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::write(&path, serde_json::to_string(&open)?).await?;
+
+        Ok(changed)
+    }
+}
+
+/// Where the issues that were open at the last pull into `file_path` are kept
+fn pulled_path(file_path: &str) -> PathBuf {
+    let hash = hash::stable(file_path.as_bytes());
+    data_dir()
+        .join("github")
+        .join(format!("{:016x}.json", hash))
+}
+
+/// `None` before the first pull
+async fn load_pulled(path: &PathBuf) -> Option<BTreeSet<String>> {
+    let content = fs::read_to_string(path).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
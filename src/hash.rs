@@ -0,0 +1,51 @@
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+/// FNV-1a, which unlike `DefaultHasher` gives the same value with every Rust release and on
+/// every platform, for hashes that end up in file names or files
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(OFFSET_BASIS)
+    }
+}
+
+impl StableHasher {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(PRIME);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The stable hash of `bytes`
+pub fn stable(bytes: &[u8]) -> u64 {
+    let mut hasher = StableHasher::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_hash_is_fnv_1a() {
+        assert_eq!(stable(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(stable(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn hashing_in_parts_is_hashing_at_once() {
+        let mut hasher = StableHasher::default();
+        hasher.write(b"foo");
+        hasher.write(b"bar");
+        assert_eq!(hasher.finish(), stable(b"foobar"));
+    }
+}
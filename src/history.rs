@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use color_eyre::eyre::Result;
+use serde_derive::{Deserialize, Serialize};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    sync::mpsc::{self, UnboundedSender},
+    task::JoinHandle,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    Add,
+    Complete,
+    Uncomplete,
+    Delete,
+    Focus,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Local>,
+    pub kind: HistoryKind,
+    pub file: String,
+    pub text: String,
+    /// The id of the task, which finds it after its text changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(kind: HistoryKind, file: &str, text: &str) -> Self {
+        Self {
+            timestamp: Local::now(),
+            kind,
+            file: file.to_owned(),
+            text: text.to_owned(),
+            id: None,
+        }
+    }
+
+    pub fn task_id(mut self, id: Option<&str>) -> Self {
+        self.id = id.map(str::to_owned);
+        self
+    }
+}
+
+/// Append-only log of everything that happened to the tasks, one JSON object per line.
+#[derive(Clone)]
+pub struct History {
+    file_path: PathBuf,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            file_path: data_dir().join("history.jsonl"),
+        }
+    }
+
+    pub async fn append(self, entry: HistoryEntry) -> Result<()> {
+        if let Some(dir) = self.file_path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.file_path)
+            .await?;
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    pub async fn load(self) -> Result<Vec<HistoryEntry>> {
+        if (fs::metadata(&self.file_path).await).is_err() {
+            return Ok(vec![]);
+        }
+
+        let file = File::open(&self.file_path).await?;
+        let mut lines = BufReader::new(file).lines();
+        let mut entries = Vec::new();
+
+        while let Some(line) = lines.next_line().await? {
+            // skip lines we can't parse instead of refusing the whole log
+            if let Ok(entry) = serde_json::from_str(&line) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Appends entries in the background one after the other, in the order they were logged
+pub struct Appender {
+    tx: UnboundedSender<HistoryEntry>,
+    task: JoinHandle<()>,
+}
+
+impl Appender {
+    /// `failed` hears about every entry that couldn't be appended
+    pub fn spawn(history: History, failed: impl Fn(color_eyre::Report) + Send + 'static) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(async move {
+            while let Some(entry) = rx.recv().await {
+                if let Err(e) = history.clone().append(entry).await {
+                    failed(e);
+                }
+            }
+        });
+        Self { tx, task }
+    }
+
+    pub fn append(&self, entry: HistoryEntry) {
+        let _ = self.tx.send(entry);
+    }
+
+    /// Waits for the entries logged so far
+    pub async fn finish(self) {
+        let Self { tx, task } = self;
+        drop(tx);
+        let _ = task.await;
+    }
+}
+
+/// `$XDG_DATA_HOME/tuitodo`, falling back to `~/.local/share/tuitodo`
+pub fn data_dir() -> PathBuf {
+    let base = match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".local/share"),
+    };
+
+    base.join("tuitodo")
+}
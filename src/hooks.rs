@@ -0,0 +1,118 @@
+use std::{process::Stdio, sync::OnceLock};
+
+use serde_derive::Deserialize;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{
+    file::format_task,
+    task_item::{TaskItem, TaskState},
+};
+
+/// `[hooks]` section of the config, shell commands run on task events
+///
+/// Commands get the task as `$TUITODO_TEXT`, `$TUITODO_STATE`, `$TUITODO_DUE`,
+/// `$TUITODO_SCHEDULED`, `$TUITODO_PRIORITY` and `$TUITODO_TAGS`, and its line from the file on stdin. Every
+/// command gets `$TUITODO_EVENT` and `$TUITODO_FILE`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    pub on_add: Option<String>,
+    pub on_complete: Option<String>,
+    pub on_delete: Option<String>,
+    /// Runs before the file is written, which waits for it to finish
+    pub pre_save: Option<String>,
+    pub post_save: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Hook {
+    Add,
+    Complete,
+    Delete,
+    PreSave,
+    PostSave,
+}
+
+impl Hook {
+    fn name(&self) -> &'static str {
+        match self {
+            Hook::Add => "add",
+            Hook::Complete => "complete",
+            Hook::Delete => "delete",
+            Hook::PreSave => "pre-save",
+            Hook::PostSave => "post-save",
+        }
+    }
+}
+
+static HOOKS: OnceLock<Hooks> = OnceLock::new();
+
+/// Sets the hooks from the config, once at startup
+pub fn init(hooks: Hooks) {
+    let _ = HOOKS.set(hooks);
+}
+
+/// Runs the command for `hook`, if one is configured. Save hooks are waited for, the others
+/// are left running in the background.
+pub async fn fire(hook: Hook, file: &str, task: Option<&TaskItem>) {
+    let Some(hooks) = HOOKS.get() else {
+        return;
+    };
+    let command = match hook {
+        Hook::Add => &hooks.on_add,
+        Hook::Complete => &hooks.on_complete,
+        Hook::Delete => &hooks.on_delete,
+        Hook::PreSave => &hooks.pre_save,
+        Hook::PostSave => &hooks.post_save,
+    };
+    let Some(command) = command else {
+        return;
+    };
+
+    let mut process = Command::new("sh");
+    process
+        .arg("-c")
+        .arg(command)
+        .env("TUITODO_EVENT", hook.name())
+        .env("TUITODO_FILE", file)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    if let Some(task) = task {
+        let state = match task.state {
+            TaskState::Open => "open",
+            TaskState::Done => "done",
+        };
+        process
+            .env("TUITODO_TEXT", &task.text)
+            .env("TUITODO_ID", task.id().unwrap_or_default())
+            .env("TUITODO_STATE", state)
+            .env("TUITODO_DUE", task.get_meta("due").unwrap_or_default())
+            .env(
+                "TUITODO_SCHEDULED",
+                task.get_meta("scheduled").unwrap_or_default(),
+            )
+            .env("TUITODO_PRIORITY", task.priority().unwrap_or_default())
+            .env("TUITODO_TAGS", task.tags().join(" "));
+    }
+
+    // a failing hook must not get in the way of editing tasks
+    let Ok(mut child) = process.spawn() else {
+        return;
+    };
+    if let (Some(mut stdin), Some(task)) = (child.stdin.take(), task) {
+        let _ = stdin.write_all((format_task(task) + "\n").as_bytes()).await;
+    }
+
+    if let Hook::PreSave | Hook::PostSave = hook {
+        let _ = child.wait().await;
+    }
+}
+
+/// `fire` from code that can't await
+pub fn spawn(hook: Hook, file: &str, task: &TaskItem) {
+    let file = file.to_owned();
+    let task = task.clone();
+    tokio::spawn(async move { fire(hook, &file, Some(&task)).await });
+}
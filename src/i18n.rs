@@ -0,0 +1,103 @@
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use tracing::{debug, warn};
+use unic_langid::LanguageIdentifier;
+
+/// Translations shipped with the binary by language tag
+const TRANSLATIONS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("en-US", include_str!("../locales/en-US.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+/// Messages missing from a translation are taken from here
+const FALLBACK: &str = "en";
+
+/// The bundles messages are looked up in, most specific first
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// Picks the language from `locale` (e.g. `de_DE.UTF-8`), falling back to `LC_ALL`,
+/// `LC_MESSAGES` and `LANG`
+pub fn init(locale: Option<&str>) {
+    let _ = BUNDLES.set(load(locale));
+}
+
+fn bundles() -> &'static [FluentBundle<FluentResource>] {
+    BUNDLES.get_or_init(|| load(None))
+}
+
+fn load(locale: Option<&str>) -> Vec<FluentBundle<FluentResource>> {
+    let locale = locale
+        .map(|l| l.to_owned())
+        .or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|value| !value.is_empty())
+        })
+        .unwrap_or_default();
+
+    // `de_DE.UTF-8@euro` is `de-DE`
+    let tag = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-");
+    let language = tag.split('-').next().unwrap_or_default().to_owned();
+    debug!(locale, tag, "picked language");
+
+    let mut tags = vec![tag, language, FALLBACK.to_owned()];
+    tags.dedup();
+    tags.iter()
+        .filter_map(|tag| TRANSLATIONS.iter().find(|(t, _)| t == tag))
+        .filter_map(|(tag, source)| bundle(tag, source))
+        .collect()
+}
+
+fn bundle(tag: &str, source: &str) -> Option<FluentBundle<FluentResource>> {
+    let language: LanguageIdentifier = tag.parse().ok()?;
+    let resource = FluentResource::try_new(source.to_owned())
+        .map_err(|(_, errors)| warn!(tag, ?errors, "broken translation"))
+        .ok()?;
+
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // isolation marks around arguments would show up in the terminal
+    bundle.set_use_isolating(false);
+    bundle.add_resource(resource).ok()?;
+    Some(bundle)
+}
+
+/// The message `id` in the user's language
+pub fn t(id: &str) -> String {
+    t_args(id, &[])
+}
+
+/// The message `id` in the user's language, with its `{ $name }` placeables filled in
+pub fn t_args(id: &str, args: &[(&str, FluentValue)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+
+    for bundle in bundles() {
+        let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) else {
+            continue;
+        };
+        let mut errors = vec![];
+        let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+        if !errors.is_empty() {
+            warn!(id, ?errors, "message not formatted");
+        }
+        return text.into_owned();
+    }
+
+    warn!(id, "message missing");
+    id.to_owned()
+}
+
+/// `date` written the way the user's language does
+pub fn date(date: NaiveDate) -> String {
+    date.format(&t("date-format")).to_string()
+}
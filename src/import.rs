@@ -0,0 +1,160 @@
+use chrono::NaiveDate;
+
+use crate::{
+    dates,
+    file::{parse_line, parse_task},
+    task_item::{TaskItem, TaskState},
+};
+
+/// Reads tasks from markdown checkboxes, todo.txt lines or plain lines of text
+pub fn parse_lines(content: &str, today: NaiveDate) -> Vec<TaskItem> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let checkbox = ["- [", "* ["]
+                .iter()
+                .any(|p| line.trim_start().starts_with(p));
+            let parsed = if checkbox { parse_line(line) } else { None };
+            let mut task = parsed.unwrap_or_else(|| parse_plain(line.trim()));
+            task.resolve_dates(today);
+            task.mark_created(today);
+            task
+        })
+        .collect()
+}
+
+fn parse_plain(line: &str) -> TaskItem {
+    // todo.txt: `x 2024-05-01 2024-04-28 (A) text`
+    let (state, rest) = match line.strip_prefix("x ") {
+        Some(rest) => (TaskState::Done, skip_dates(rest)),
+        None => (TaskState::Open, line),
+    };
+
+    let (priority, rest) = match rest.as_bytes() {
+        [b'(', p @ b'A'..=b'Z', b')', b' ', ..] => (Some((*p as char).to_string()), &rest[4..]),
+        _ => (None, rest),
+    };
+    let rest = if state == TaskState::Open {
+        skip_dates(rest)
+    } else {
+        rest
+    };
+
+    // plain bullet lists
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| rest.strip_prefix(bullet))
+        .unwrap_or(rest);
+
+    let mut task = parse_task(rest, state);
+    if priority.is_some() {
+        task.set_meta("pri", priority);
+    }
+    task
+}
+
+/// Drops the completion/creation dates todo.txt puts in front of the text
+fn skip_dates(mut line: &str) -> &str {
+    while let Some((word, rest)) = line.split_once(' ') {
+        if NaiveDate::parse_from_str(word, dates::FORMAT).is_err() {
+            break;
+        }
+        line = rest;
+    }
+
+    line
+}
+
+/// Tasks from the VTODOs of an iCalendar file, the original UID kept as `uid` metadata
+pub fn parse_ics(content: &str) -> Vec<TaskItem> {
+    let mut tasks = vec![];
+    let mut current: Option<TaskItem> = None;
+
+    for line in unfold(content) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // parameters like `DUE;VALUE=DATE` don't matter here
+        let name = name.split(';').next().unwrap_or_default().to_uppercase();
+
+        match (name.as_str(), value, current.as_mut()) {
+            ("BEGIN", "VTODO", _) => current = Some(TaskItem::new(String::new(), TaskState::Open)),
+            ("END", "VTODO", Some(_)) => tasks.extend(current.take()),
+            ("UID", uid, Some(task)) => task.set_meta("uid", Some(uid.replace(' ', "_"))),
+            ("SUMMARY", summary, Some(task)) => {
+                let tags: Vec<String> = task.tags().iter().map(|t| t.to_string()).collect();
+                task.text = unescape(summary).replace('\n', " ");
+                for tag in tags {
+                    task.text.push(' ');
+                    task.text.push_str(&tag);
+                }
+            }
+            ("STATUS", status, Some(task)) if status.eq_ignore_ascii_case("COMPLETED") => {
+                task.state = TaskState::Done
+            }
+            ("DUE", due, Some(task)) => task.set_meta("due", ics_date(due)),
+            ("DTSTART", start, Some(task)) => task.set_meta("scheduled", ics_date(start)),
+            ("COMPLETED", completed, Some(task)) => task.set_meta("completed", ics_date(completed)),
+            ("PRIORITY", priority, Some(task)) => {
+                let priority = match priority.trim().parse::<u8>() {
+                    Ok(p @ 1..=9) => Some(((b'A' + p - 1) as char).to_string()),
+                    _ => None,
+                };
+                task.set_meta("pri", priority);
+            }
+            ("CATEGORIES", categories, Some(task)) => {
+                for category in unescape(categories).split(',') {
+                    let tag = format!("#{}", category.trim().replace(' ', "-"));
+                    if tag.len() > 1 && !task.tags().contains(&tag.as_str()) {
+                        task.text = format!("{} {}", task.text, tag).trim().to_owned();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tasks
+}
+
+/// Joins continuation lines, which start with a space or tab
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+
+    while let Some(char) = chars.next() {
+        match (char, chars.clone().next()) {
+            ('\\', Some('n' | 'N')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some(escaped)) => {
+                out.push(escaped);
+                chars.next();
+            }
+            _ => out.push(char),
+        }
+    }
+
+    out
+}
+
+/// `20240501` or `20240501T120000Z` as `2024-05-01`
+fn ics_date(value: &str) -> Option<String> {
+    let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+    Some(date.format(dates::FORMAT).to_string())
+}
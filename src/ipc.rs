@@ -0,0 +1,200 @@
+use std::{
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::mpsc::UnboundedSender,
+};
+
+use crate::{
+    app::Action,
+    file::parse_tasks,
+    hash,
+    history::data_dir,
+    task_item::{TaskItem, TaskState},
+};
+
+/// One socket per todo file, in `$XDG_RUNTIME_DIR` when there is one
+fn socket_path(file: &str) -> PathBuf {
+    let path = std::fs::canonicalize(file).unwrap_or_else(|_| PathBuf::from(file));
+    let hash = hash::stable(path.as_os_str().as_encoded_bytes());
+
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("tuitodo"),
+        _ => data_dir().join("run"),
+    };
+    dir.join(format!("{:016x}.sock", hash))
+}
+
+/// What another tuitodo process asks the running instance to do, tasks are named by id
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    Add(String),
+    /// Tasks as they are written to the file, with their notes
+    Import(Vec<String>),
+    Done(String),
+    Reopen(String),
+    Remove(String),
+    Edit(String, String),
+}
+
+impl Request {
+    fn line(&self) -> String {
+        let line = match self {
+            Request::Add(text) => format!("add {}", text),
+            Request::Import(lines) => {
+                format!(
+                    "import {}",
+                    serde_json::to_string(lines).unwrap_or_default()
+                )
+            }
+            Request::Done(id) => format!("done {}", id),
+            Request::Reopen(id) => format!("reopen {}", id),
+            Request::Remove(id) => format!("rm {}", id),
+            Request::Edit(id, text) => format!("edit {} {}", id, text),
+        };
+        line.replace('\n', " ") + "\n"
+    }
+
+    fn parse(line: &str) -> Option<Request> {
+        let (command, rest) = line.split_once(' ')?;
+        match command {
+            "add" => Some(Request::Add(rest.to_owned())),
+            "import" => serde_json::from_str(rest).ok().map(Request::Import),
+            "done" => Some(Request::Done(rest.to_owned())),
+            "reopen" => Some(Request::Reopen(rest.to_owned())),
+            "rm" => Some(Request::Remove(rest.to_owned())),
+            "edit" => {
+                let (id, text) = rest.split_once(' ')?;
+                Some(Request::Edit(id.to_owned(), text.to_owned()))
+            }
+            _ => None,
+        }
+    }
+
+    fn action(self) -> Action {
+        match self {
+            Request::Add(text) => Action::InsertTask(text),
+            Request::Import(lines) => {
+                Action::ImportTasks(lines.iter().flat_map(|l| parse_tasks(l)).collect())
+            }
+            Request::Done(id) => Action::SetTaskState(id, TaskState::Done),
+            Request::Reopen(id) => Action::SetTaskState(id, TaskState::Open),
+            Request::Remove(id) => Action::RemoveTask(id),
+            Request::Edit(id, text) => Action::EditTask(id, text),
+        }
+    }
+}
+
+/// Accepts request lines from other tuitodo processes and turns them into actions
+pub async fn listen(file: &str, tx: UnboundedSender<Action>) -> Result<()> {
+    let path = socket_path(file);
+    if let Some(dir) = path.parent() {
+        private_dir(dir).await?;
+    }
+    // left behind by an instance that didn't exit cleanly
+    if UnixStream::connect(&path).await.is_err() {
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let (reader, mut writer) = stream.into_split();
+                let mut lines = BufReader::new(reader).lines();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let reply = match Request::parse(&line) {
+                        Some(request) => {
+                            let _ = tx.send(request.action());
+                            "ok\n"
+                        }
+                        None => "unknown command\n",
+                    };
+                    let _ = writer.write_all(reply.as_bytes()).await;
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+/// Creates `dir` so only this user can reach the sockets in it, one that someone else owns
+/// is refused and one others can get into is closed
+async fn private_dir(dir: &Path) -> Result<()> {
+    let mut builder = tokio::fs::DirBuilder::new();
+    builder.recursive(true).mode(0o700);
+    builder.create(dir).await?;
+
+    let metadata = tokio::fs::symlink_metadata(dir).await?;
+    if !metadata.is_dir() || metadata.uid() != unsafe { libc::getuid() } {
+        return Err(eyre!("{} isn't a directory of your own", dir.display()));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        let permissions = std::fs::Permissions::from_mode(0o700);
+        tokio::fs::set_permissions(dir, permissions).await?;
+    }
+    Ok(())
+}
+
+pub async fn cleanup(file: &str) {
+    let _ = tokio::fs::remove_file(socket_path(file)).await;
+}
+
+/// Hands the request to a running instance, `false` when there is none
+pub async fn send(file: &str, request: Request) -> Result<bool> {
+    let Ok(stream) = UnixStream::connect(socket_path(file)).await else {
+        return Ok(false);
+    };
+
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(request.line().as_bytes()).await?;
+
+    let mut reply = String::new();
+    BufReader::new(reader).read_line(&mut reply).await?;
+    Ok(reply.trim() == "ok")
+}
+
+/// Hands the request for `task` to a running instance, `false` when there is none
+pub async fn send_for(
+    file: &str,
+    task: &TaskItem,
+    request: impl FnOnce(String) -> Request,
+) -> Result<bool> {
+    match task.id() {
+        Some(id) => send(file, request(id.to_owned())).await,
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_read_back_as_they_were_sent() {
+        let requests = [
+            Request::Add("buy milk due:tomorrow".to_owned()),
+            Request::Import(vec![
+                "- [x] bought bread".to_owned(),
+                "- [ ] bake".to_owned(),
+            ]),
+            Request::Done("8b63255e".to_owned()),
+            Request::Reopen("8b63255e".to_owned()),
+            Request::Remove("8b63255e".to_owned()),
+            Request::Edit("8b63255e".to_owned(), "buy oat milk".to_owned()),
+        ];
+        for request in requests {
+            let line = request.line();
+            assert_eq!(Request::parse(line.trim_end()), Some(request));
+        }
+        assert_eq!(Request::parse("edit 8b63255e"), None);
+    }
+}
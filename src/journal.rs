@@ -0,0 +1,473 @@
+use std::{
+    collections::HashMap,
+    io::SeekFrom,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex as StdMutex, OnceLock},
+    time::SystemTime,
+};
+
+use color_eyre::eyre::{eyre, Result};
+use serde_derive::{Deserialize, Serialize};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    sync::Mutex,
+};
+use tracing::debug;
+
+use crate::{
+    file::{format_task, parse_tasks, write_tasks, TaskStore},
+    front_matter,
+    hash::StableHasher,
+    task_item::TaskItem,
+};
+
+/// Smaller lists are never journaled, their changed lines are patched in the file instead
+const MIN_TASKS: usize = 5000;
+/// Entries after which the file is rewritten and the journal dropped
+const COMPACT_AFTER: usize = 1000;
+
+/// Saves run concurrently, each one has to diff against what the previous one wrote
+static LOCK: Mutex<()> = Mutex::const_new(());
+
+/// The tasks as the file and its journal currently describe them, by file
+static WRITTEN: OnceLock<StdMutex<HashMap<String, Written>>> = OnceLock::new();
+
+#[derive(Clone)]
+struct Written {
+    items: Arc<Vec<TaskItem>>,
+    entries: usize,
+    /// When the file was last changed, a later change means someone else edited it
+    modified: Option<SystemTime>,
+}
+
+/// One change to the task list, applied in order on top of the file
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Entry {
+    Set { index: usize, line: String },
+    Insert { index: usize, line: String },
+    Remove { index: usize },
+}
+
+fn written() -> std::sync::MutexGuard<'static, HashMap<String, Written>> {
+    WRITTEN.get_or_init(Default::default).lock().unwrap()
+}
+
+/// `.todo.md.journal` next to `todo.md`
+pub fn path(file: &str) -> PathBuf {
+    let file = Path::new(file);
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+    file.with_file_name(format!(".{}.journal", name))
+}
+
+pub async fn exists(file: &str) -> bool {
+    fs::metadata(path(file)).await.is_ok()
+}
+
+async fn modified(file: &str) -> Option<SystemTime> {
+    fs::metadata(file).await.and_then(|m| m.modified()).ok()
+}
+
+/// Applies the journal of `file` to the tasks read from it
+pub async fn load(file: &str, items: &mut Vec<TaskItem>) -> Result<()> {
+    let mut entries = 0;
+    if let Ok(content) = fs::read_to_string(path(file)).await {
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            apply(items, serde_json::from_str(line)?)
+                .map_err(|e| eyre!("broken journal {}: {}", path(file).display(), e))?;
+            entries += 1;
+        }
+    }
+
+    if items.len() >= MIN_TASKS {
+        let written_now = Written {
+            items: Arc::new(items.clone()),
+            entries,
+            modified: modified(file).await,
+        };
+        written().insert(file.to_owned(), written_now);
+    }
+    Ok(())
+}
+
+fn apply(items: &mut Vec<TaskItem>, entry: Entry) -> Result<()> {
+    // a line comes with the notes of its task
+    let task = |line: &str| {
+        (parse_tasks(line).into_iter().next()).ok_or_else(|| eyre!("not a task: {}", line))
+    };
+    match entry {
+        Entry::Set { index, line } if index < items.len() => items[index] = task(&line)?,
+        Entry::Insert { index, line } if index <= items.len() => items.insert(index, task(&line)?),
+        Entry::Remove { index } if index < items.len() => {
+            items.remove(index);
+        }
+        _ => return Err(eyre!("entry out of range")),
+    }
+    Ok(())
+}
+
+/// The part of `old` and of `new` that differs, between their common start and end
+pub(crate) fn changed(old: &[TaskItem], new: &[TaskItem]) -> (Range<usize>, Range<usize>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix..old.len() - suffix, prefix..new.len() - suffix)
+}
+
+/// The entries that turn `old` into `new`, changing only what lies between their common
+/// start and end
+fn diff(old: &[TaskItem], new: &[TaskItem]) -> Vec<Entry> {
+    let (old_range, new_range) = changed(old, new);
+    let prefix = old_range.start;
+    let old = &old[old_range];
+    let new = &new[new_range];
+
+    if old.len() == new.len() {
+        return old
+            .iter()
+            .zip(new)
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, (_, b))| Entry::Set {
+                index: prefix + i,
+                line: format_task(b),
+            })
+            .collect();
+    }
+
+    let removed = old.iter().map(|_| Entry::Remove { index: prefix });
+    let inserted = new.iter().enumerate().map(|(i, task)| Entry::Insert {
+        index: prefix + i,
+        line: format_task(task),
+    });
+    removed.chain(inserted).collect()
+}
+
+/// Writes `items` to `file`, as journal entries when the list is big and the file was
+/// read or written before, by patching the changed lines when it is small and was written
+/// before, otherwise in full
+pub async fn save(file: &str, items: Vec<TaskItem>) -> Result<()> {
+    let _lock = LOCK.lock().await;
+    let previous = written().get(file).cloned();
+    let previous = match previous {
+        Some(previous) if previous.modified == modified(file).await => previous,
+        _ => return write_full(file, items).await,
+    };
+
+    let entries = diff(&previous.items, &items);
+    if items.len() < MIN_TASKS {
+        // lines can only be patched while every task stays where it is, and a journal left
+        // from when the list was bigger has to be folded in first
+        let in_place = entries.iter().all(|e| matches!(e, Entry::Set { .. }));
+        return match in_place && previous.entries == 0 {
+            true => patch(file, &previous.items, items, &entries).await,
+            false => write_full(file, items).await,
+        };
+    }
+    let total = previous.entries + entries.len();
+    if total > COMPACT_AFTER {
+        return write_full(file, items).await;
+    }
+
+    let mut content = String::new();
+    for entry in &entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    let mut journal = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path(file))
+        .await?;
+    journal.write_all(content.as_bytes()).await?;
+    journal.flush().await?;
+    debug!(file, entries = entries.len(), total, "journaled");
+
+    let written_now = Written {
+        items: Arc::new(items),
+        entries: total,
+        modified: previous.modified,
+    };
+    written().insert(file.to_owned(), written_now);
+    Ok(())
+}
+
+/// Writes the lines of the tasks `entries` set over the ones of `old` in `file`, in place
+/// when they keep their length and along with everything after them otherwise
+async fn patch(
+    file: &str,
+    old: &[TaskItem],
+    items: Vec<TaskItem>,
+    entries: &[Entry],
+) -> Result<()> {
+    let changed: Vec<(usize, &str)> = (entries.iter())
+        .filter_map(|e| match e {
+            Entry::Set { index, line } => Some((*index, line.as_str())),
+            _ => None,
+        })
+        .collect();
+    let Some((first, _)) = changed.first().copied() else {
+        return Ok(());
+    };
+
+    // where the line of each task starts, below the block at the top
+    let mut start = front_matter::read(file).await?.unwrap_or_default().len() as u64;
+    let mut offsets = Vec::with_capacity(old.len());
+    for task in old {
+        offsets.push(start);
+        start += format_task(task).len() as u64 + 1;
+    }
+
+    let same_length = (changed.iter()).all(|(i, line)| line.len() == format_task(&old[*i]).len());
+    // what is about to be written over, a change in the same tick as the last write
+    // leaves the time the file was modified the same
+    let replaced: Vec<(u64, String)> = match same_length {
+        true => (changed.iter())
+            .map(|(i, _)| (offsets[*i], format_task(&old[*i])))
+            .collect(),
+        false => {
+            let rest = old[first..].iter().map(|t| format_task(t) + "\n");
+            vec![(offsets[first], rest.collect())]
+        }
+    };
+
+    let mut handle = OpenOptions::new().read(true).write(true).open(file).await?;
+    if handle.metadata().await?.len() != start || !holds(&mut handle, &replaced).await? {
+        debug!(file, "changed by someone else, written in full");
+        drop(handle);
+        return write_full(file, items).await;
+    }
+    let bytes = match same_length {
+        true => {
+            for (index, line) in &changed {
+                handle.seek(SeekFrom::Start(offsets[*index])).await?;
+                handle.write_all(line.as_bytes()).await?;
+            }
+            changed.iter().map(|(_, line)| line.len()).sum()
+        }
+        false => {
+            let rest: String = items[first..]
+                .iter()
+                .map(|t| format_task(t) + "\n")
+                .collect();
+            handle.seek(SeekFrom::Start(offsets[first])).await?;
+            handle.write_all(rest.as_bytes()).await?;
+            handle.set_len(offsets[first] + rest.len() as u64).await?;
+            rest.len()
+        }
+    };
+    handle.flush().await?;
+    debug!(file, tasks = changed.len(), bytes, "patched");
+
+    let written_now = Written {
+        items: Arc::new(items),
+        entries: 0,
+        modified: modified(file).await,
+    };
+    written().insert(file.to_owned(), written_now);
+    Ok(())
+}
+
+/// Whether `handle` holds the given content at each offset
+async fn holds(handle: &mut File, ranges: &[(u64, String)]) -> Result<bool> {
+    let (mut found, mut wanted) = (StableHasher::default(), StableHasher::default());
+    for (offset, content) in ranges {
+        let mut bytes = vec![0; content.len()];
+        handle.seek(SeekFrom::Start(*offset)).await?;
+        handle.read_exact(&mut bytes).await?;
+        found.write(&bytes);
+        wanted.write(content.as_bytes());
+    }
+    Ok(found.finish() == wanted.finish())
+}
+
+async fn write_full(file: &str, items: Vec<TaskItem>) -> Result<()> {
+    write_tasks(file, &items).await?;
+    // what is on disk now, which the next save only has to patch or journal
+    let written_now = Written {
+        items: Arc::new(items),
+        entries: 0,
+        modified: modified(file).await,
+    };
+    written().insert(file.to_owned(), written_now);
+    Ok(())
+}
+
+/// Drops the journal after `file` was written in full
+pub async fn clear(file: &str) {
+    written().remove(file);
+    let _ = fs::remove_file(path(file)).await;
+}
+
+/// Folds the journal into `file`, so other programs see the current tasks
+pub async fn compact(file: &str) -> Result<()> {
+    let _lock = LOCK.lock().await;
+    if !exists(file).await {
+        return Ok(());
+    }
+
+    let items = written().get(file).map(|w| w.items.to_vec());
+    let items = match items {
+        Some(items) => items,
+        None => TaskStore::new(file.to_owned()).await?.items,
+    };
+    write_full(file, items).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(texts: &[&str]) -> Vec<TaskItem> {
+        texts
+            .iter()
+            .map(|text| TaskItem::new(text.to_string(), crate::task_item::TaskState::Open))
+            .collect()
+    }
+
+    fn scratch(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("tuitodo-journal-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(name);
+        let _ = std::fs::remove_file(&file);
+        let _ = std::fs::remove_file(path(file.to_str().unwrap()));
+        file.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn applying_the_diff_gives_the_new_tasks() {
+        let old = tasks(&["a", "b", "c", "d"]);
+        let mut noted = tasks(&["b2"]);
+        noted[0].notes.push("a longer description".to_owned());
+
+        for new in [
+            old.clone(),
+            tasks(&["a", "B", "c", "D"]),
+            tasks(&["a", "b", "x", "c", "d"]),
+            tasks(&["a", "d"]),
+            tasks(&["x", "y"]),
+            tasks(&[]),
+            [&old[..1], &noted, &old[2..]].concat(),
+        ] {
+            let mut items = old.clone();
+            for entry in diff(&old, &new) {
+                apply(&mut items, entry).unwrap();
+            }
+            assert_eq!(items, new);
+        }
+    }
+
+    #[test]
+    fn diff_keeps_what_is_unchanged_at_both_ends() {
+        let entries = diff(&tasks(&["a", "b", "c"]), &tasks(&["a", "x", "y", "c"]));
+        let json: Vec<String> = (entries.iter())
+            .map(|e| serde_json::to_string(e).unwrap())
+            .collect();
+        assert_eq!(
+            json,
+            [
+                r#"{"op":"remove","index":1}"#,
+                r#"{"op":"insert","index":1,"line":"- [ ] x"}"#,
+                r#"{"op":"insert","index":2,"line":"- [ ] y"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn entries_out_of_range_are_refused() {
+        let mut items = tasks(&["a"]);
+        assert!(apply(&mut items, Entry::Remove { index: 1 }).is_err());
+        let line = "- [ ] b".to_owned();
+        assert!(apply(&mut items, Entry::Set { index: 1, line }).is_err());
+        assert!(apply(
+            &mut items,
+            Entry::Insert {
+                index: 1,
+                line: "no task".to_owned()
+            }
+        )
+        .is_err());
+        assert_eq!(items, tasks(&["a"]));
+    }
+
+    #[tokio::test]
+    async fn small_lists_are_patched_below_the_front_matter() {
+        let file = scratch("patched.md");
+        std::fs::write(&file, "---\ntags: [home]\n---\n").unwrap();
+        save(&file, tasks(&["aaa", "bbb", "ccc"])).await.unwrap();
+
+        save(&file, tasks(&["aaa", "BBB", "ccc"])).await.unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert_eq!(
+            content,
+            "---\ntags: [home]\n---\n- [ ] aaa\n- [ ] BBB\n- [ ] ccc\n"
+        );
+
+        save(&file, tasks(&["aaa", "BBB", "longer"])).await.unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert_eq!(
+            content,
+            "---\ntags: [home]\n---\n- [ ] aaa\n- [ ] BBB\n- [ ] longer\n"
+        );
+
+        save(&file, tasks(&["aaa"])).await.unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert_eq!(content, "---\ntags: [home]\n---\n- [ ] aaa\n");
+        assert!(!exists(&file).await);
+    }
+
+    #[tokio::test]
+    async fn files_changed_within_the_same_tick_are_written_in_full() {
+        let file = scratch("same-tick.md");
+        save(&file, tasks(&["aaa", "bbb", "ccc"])).await.unwrap();
+
+        // edited elsewhere, with a modification time as coarse as some filesystems keep
+        std::fs::write(&file, "- [ ] aaaa\n- [ ] bbb\n- [ ] ccc\n").unwrap();
+        let at = modified(&file).await;
+        written().get_mut(&file).unwrap().modified = at;
+
+        save(&file, tasks(&["aaa", "BBB", "ccc"])).await.unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert_eq!(content, "- [ ] aaa\n- [ ] BBB\n- [ ] ccc\n");
+
+        // the same length, but not the line about to be written over
+        std::fs::write(&file, "- [ ] aaa\n- [ ] BBB\n- [ ] ccX\n").unwrap();
+        let at = modified(&file).await;
+        written().get_mut(&file).unwrap().modified = at;
+
+        save(&file, tasks(&["aaa", "BBB", "CCC"])).await.unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert_eq!(content, "- [ ] aaa\n- [ ] BBB\n- [ ] CCC\n");
+    }
+
+    #[tokio::test]
+    async fn big_lists_are_journaled_until_compacted() {
+        let file = scratch("journaled.md");
+        let texts: Vec<String> = (0..MIN_TASKS).map(|i| format!("task {}", i)).collect();
+        let old = tasks(&texts.iter().map(String::as_str).collect::<Vec<_>>());
+        save(&file, old.clone()).await.unwrap();
+        let written = std::fs::read_to_string(&file).unwrap();
+
+        let mut new = old.clone();
+        new.insert(10, tasks(&["inserted"]).remove(0));
+        new.remove(100);
+        new[200].text = "changed".to_owned();
+        save(&file, new.clone()).await.unwrap();
+        assert!(exists(&file).await);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), written);
+
+        let mut loaded = parse_tasks(&written);
+        load(&file, &mut loaded).await.unwrap();
+        assert_eq!(loaded, new);
+
+        compact(&file).await.unwrap();
+        assert!(!exists(&file).await);
+        assert_eq!(parse_tasks(&std::fs::read_to_string(&file).unwrap()), new);
+    }
+}
@@ -0,0 +1,61 @@
+//! Markdown todo lists: the task model and file format, parsers and exporters, sync
+//! backends and the terminal interface, for tools that want to embed them.
+
+pub mod app;
+mod batch;
+pub mod cli;
+pub mod config;
+pub mod confirm;
+pub mod conflict;
+pub mod crypt;
+pub mod daemon;
+pub mod dates;
+mod debug;
+mod detail;
+mod duplicate;
+mod editor;
+pub mod export;
+pub mod file;
+pub mod filter;
+mod focus;
+pub mod front_matter;
+pub mod git;
+pub mod github;
+mod hash;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod import;
+pub mod ipc;
+mod journal;
+mod list;
+pub mod logging;
+mod modal;
+pub mod notify;
+pub mod plugins;
+pub mod pomodoro;
+pub mod recurrence;
+pub mod remote;
+mod rules;
+pub mod script;
+mod search;
+pub mod secrets;
+pub mod serve;
+pub mod session;
+mod sidebar;
+pub mod stale;
+mod startup;
+mod stats;
+mod status;
+pub mod sync;
+pub mod task_item;
+mod text_input;
+pub mod theme;
+mod title;
+mod toast;
+mod tui;
+mod undo;
+mod view;
+
+pub use file::TaskStore;
+pub use task_item::{TaskItem, TaskState};
@@ -1,14 +1,50 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+};
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use chrono::{Local, NaiveDate};
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Rect},
     style::{Color, Modifier, Style},
-    widgets::{List, ListDirection, ListItem, ListState},
+    text::{Line, Span},
+    widgets::{
+        Cell, List, ListDirection, ListItem, ListState, Row, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Table, TableState,
+    },
     Frame,
 };
 
-use crate::task_item::{TaskItem, TaskState};
+use crate::{
+    front_matter::{FileSettings, Progress},
+    i18n::{self, t, t_args},
+    plugins, rules,
+    search::Search,
+    stale,
+    task_item::{self, format_duration, Bucket, TaskItem, TaskState},
+    theme,
+    view::ListView,
+};
+
+/// Tasks formatted above and below the visible ones, so scrolling a little reuses lines
+const OVERSCAN: usize = 20;
+/// Width of the bar showing how many subtasks are done
+const GAUGE_CELLS: usize = 5;
+/// Widest the tags column gets, longer tags are cut
+const TAGS_WIDTH: usize = 24;
 
+#[derive(Default)]
 pub struct TaskList {
     pub state: ListState,
+    /// Formatted lines around the visible window by task index, with the fingerprint of
+    /// what they were formatted from
+    lines: HashMap<usize, (u64, ListItem<'static>)>,
+    /// Lays the tasks out in aligned columns instead of one line each
+    pub columns: bool,
 }
 
 impl TaskList {
@@ -40,27 +76,483 @@ impl TaskList {
         };
         self.state.select(Some(i));
     }
+
+    /// Selects the next task after the selected one that `matches`, wrapping around
+    pub fn next_match(&mut self, length: usize, matches: impl Fn(usize) -> bool) {
+        let start = self.state.selected().map_or(0, |i| i + 1).min(length);
+        if let Some(i) = (start..length).chain(0..start).find(|i| matches(*i)) {
+            self.state.select(Some(i));
+        }
+    }
+
+    /// Selects the closest task before the selected one that `matches`, wrapping around
+    pub fn previous_match(&mut self, length: usize, matches: impl Fn(usize) -> bool) {
+        let start = self.state.selected().unwrap_or(length).min(length);
+        if let Some(i) = (0..start)
+            .rev()
+            .chain((start..length).rev())
+            .find(|i| matches(*i))
+        {
+            self.state.select(Some(i));
+        }
+    }
+
+    /// The line for the task at `index`, formatted again only when the task or the search
+    /// changed
+    fn item(
+        &mut self,
+        index: usize,
+        task: &TaskItem,
+        today: NaiveDate,
+        search: Option<&Search>,
+        subtasks: Option<Subtasks>,
+        width: usize,
+    ) -> ListItem<'static> {
+        let highlights = || search.map_or(vec![], |s| s.highlights(&task.text));
+
+        // a running timer changes the line every second
+        if task.started().is_some() {
+            return item_ui(task, &highlights(), subtasks, width);
+        }
+
+        let fingerprint = fingerprint(task, today, search.map(|s| s.query()), subtasks, width);
+        match self.lines.get(&index) {
+            Some((cached, item)) if *cached == fingerprint => item.clone(),
+            _ => {
+                let item = item_ui(task, &highlights(), subtasks, width);
+                self.lines.insert(index, (fingerprint, item.clone()));
+                item
+            }
+        }
+    }
+}
+
+/// How many subtasks of a task are done, and how that is shown
+#[derive(Clone, Copy, Hash)]
+struct Subtasks {
+    done: usize,
+    total: usize,
+    look: Progress,
+}
+
+impl Subtasks {
+    /// The subtasks of the task at `index`, `None` when it has none or they aren't shown
+    fn of(tasks: &[TaskItem], index: usize, look: Progress) -> Option<Self> {
+        if look == Progress::Off {
+            return None;
+        }
+        let (done, total) = task_item::progress(tasks, index)?;
+        Some(Self { done, total, look })
+    }
+
+    fn ui(self) -> Span<'static> {
+        let glyphs = theme::glyphs();
+        let text = match (self.look, theme::accessible()) {
+            (_, true) => t_args(
+                "list-progress",
+                &[("done", self.done.into()), ("total", self.total.into())],
+            ),
+            (Progress::Gauge, false) => {
+                let filled = (self.done * GAUGE_CELLS + self.total / 2) / self.total;
+                let empty = GAUGE_CELLS - filled;
+                format!(
+                    "{}{}",
+                    glyphs.gauge_full.repeat(filled),
+                    glyphs.gauge_empty.repeat(empty)
+                )
+            }
+            _ => format!("{}/{}", self.done, self.total),
+        };
+        let color = match self.done == self.total {
+            true => Color::Green,
+            false => Color::DarkGray,
+        };
+        Span::styled(format!(" {}", text), Style::default().fg(color))
+    }
+}
+
+fn fingerprint(
+    task: &TaskItem,
+    today: NaiveDate,
+    query: Option<&str>,
+    subtasks: Option<Subtasks>,
+    width: usize,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    task.text.hash(&mut hasher);
+    (task.state == TaskState::Done).hash(&mut hasher);
+    task.depth.hash(&mut hasher);
+    task.meta.hash(&mut hasher);
+    // overdue dates turn red
+    today.hash(&mut hasher);
+    query.hash(&mut hasher);
+    subtasks.hash(&mut hasher);
+    width.hash(&mut hasher);
+    theme::is_light().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How much of `text` fits in `width` columns, cut between graphemes
+fn truncate(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, grapheme) in text.grapheme_indices(true) {
+        used += grapheme.width();
+        if used > width {
+            return &text[..i];
+        }
+    }
+    text
 }
 
-fn item_ui(item: &TaskItem) -> ListItem {
-    let state_char = match item.state {
-        TaskState::Done => "\u{f058}",
-        TaskState::Open => "\u{f05d}",
+/// The line of `item` in `width` columns, the text is shortened to keep its date, timer
+/// and decorations in view
+fn item_ui(
+    item: &TaskItem,
+    highlights: &[Range<usize>],
+    subtasks: Option<Subtasks>,
+    width: usize,
+) -> ListItem<'static> {
+    let glyphs = theme::glyphs();
+    let state_char = match (&item.state, theme::accessible()) {
+        (TaskState::Done, false) => glyphs.done.as_str(),
+        (TaskState::Open, false) => glyphs.open.as_str(),
+        (TaskState::Done, true) => "[x]",
+        (TaskState::Open, true) => "[ ]",
+    };
+    let star = match (item.priority(), theme::accessible()) {
+        (None, _) => String::new(),
+        (Some(_), false) => format!("{} ", glyphs.star),
+        (Some(priority), true) => {
+            format!(
+                "{} ",
+                t_args("list-priority", &[("priority", priority.into())])
+            )
+        }
+    };
+    let prefix = Span::raw(format!(
+        "{}{} {}",
+        "  ".repeat(item.depth),
+        state_char,
+        star
+    ));
+
+    let mut suffix: Vec<Span> = subtasks.map(Subtasks::ui).into_iter().collect();
+    if let Some(due) = item.due() {
+        let overdue = matches!(item.state, TaskState::Open) && due < Local::now().date_naive();
+        // the colour alone doesn't tell a screen reader the task is late
+        let date = i18n::date(due);
+        let text = match (theme::accessible(), overdue) {
+            (false, _) => format!("{} {}", glyphs.due, date),
+            (true, false) => t_args("list-due", &[("date", date.into())]),
+            (true, true) => t_args("list-overdue", &[("date", date.into())]),
+        };
+        suffix.push(Span::styled(
+            format!(" {}", text),
+            Style::default().fg(if overdue { Color::Red } else { Color::DarkGray }),
+        ));
+    }
+
+    let today = Local::now().date_naive();
+    if let Some(scheduled) = item.scheduled().filter(|_| item.is_scheduled_later(today)) {
+        let date = i18n::date(scheduled);
+        let text = match theme::accessible() {
+            true => t_args("list-scheduled", &[("date", date.into())]),
+            false => format!("{} {}", glyphs.scheduled, date),
+        };
+        suffix.push(Span::styled(
+            format!(" {}", text),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if let Some(bucket) = item.bucket() {
+        let text = match bucket {
+            Bucket::Waiting => t("list-waiting"),
+            Bucket::Someday => t("list-someday"),
+        };
+        suffix.push(Span::styled(
+            format!(" {}", text),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if stale::is_stale(item, today) {
+        let text = match theme::accessible() {
+            true => t("list-stale"),
+            false => glyphs.stale.clone(),
+        };
+        suffix.push(Span::styled(
+            format!(" {}", text),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    let tracked = item.tracked();
+    if tracked > 0 {
+        let running = item.started().is_some();
+        let duration = format_duration(tracked);
+        let text = match (theme::accessible(), running) {
+            (false, _) => format!("{} {}", glyphs.timer, duration),
+            (true, false) => t_args("detail-tracked", &[("duration", duration.into())]),
+            (true, true) => t_args("list-running", &[("duration", duration.into())]),
+        };
+        suffix.push(Span::styled(
+            format!(" {}", text),
+            Style::default().fg(if running {
+                Color::Green
+            } else {
+                Color::DarkGray
+            }),
+        ));
+    }
+
+    if let Some(plugins) = plugins::get() {
+        for decoration in plugins.decorations(item) {
+            suffix.push(Span::styled(
+                format!(" {}", decoration),
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+    }
+
+    let available = width
+        .saturating_sub(prefix.width())
+        .saturating_sub(suffix.iter().map(|s| s.width()).sum());
+    let (text, ellipsis) = match item.text.width() > available {
+        true => (
+            truncate(&item.text, available.saturating_sub(1)),
+            "\u{2026}",
+        ),
+        false => (item.text.as_str(), ""),
     };
 
-    ListItem::from(format!("{} {}", state_char, item.text.clone()))
+    let style = theme::state_style(item).patch(rules::style(item, today));
+    let mut spans = vec![prefix];
+    let mut end = 0;
+    for range in highlights.iter().filter(|r| r.start < text.len()) {
+        let range = range.start..range.end.min(text.len());
+        spans.push(Span::styled(text[end..range.start].to_owned(), style));
+        spans.push(Span::styled(
+            text[range.clone()].to_owned(),
+            style.patch(theme::palette().matched),
+        ));
+        end = range.end;
+    }
+    spans.push(Span::styled(format!("{}{}", &text[end..], ellipsis), style));
+    spans.extend(suffix);
+
+    ListItem::from(Line::from(spans))
+}
+
+/// The state, priority, due date, tags and text of the tasks at `shown` in cells, the
+/// columns as wide as their widest cell; `start` is the row of the first one
+fn columns_ui(
+    tasks: &[TaskItem],
+    shown: &[usize],
+    start: usize,
+    today: NaiveDate,
+    progress: Progress,
+) -> Table<'static> {
+    let glyphs = theme::glyphs();
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut widths = [1, 0, 0, 0];
+
+    let rows: Vec<Row> = (start..)
+        .zip(shown)
+        .map(|(row, i)| {
+            let task = &tasks[*i];
+            let state = match (&task.state, theme::accessible()) {
+                (TaskState::Done, false) => glyphs.done.clone(),
+                (TaskState::Open, false) => glyphs.open.clone(),
+                (TaskState::Done, true) => "[x]".to_owned(),
+                (TaskState::Open, true) => "[ ]".to_owned(),
+            };
+            let priority = task.priority().unwrap_or_default().to_owned();
+            let overdue = task.state == TaskState::Open && task.due().is_some_and(|d| d < today);
+            let due = task.due().map(i18n::date).unwrap_or_default();
+            let tags = task.tags().join(" ");
+            let tags: String = tags.chars().take(TAGS_WIDTH).collect();
+
+            // the tags have a column of their own
+            let words = task.text.split(' ').filter(|w| !task.tags().contains(w));
+            let text = format!(
+                "{}{}",
+                "  ".repeat(task.depth),
+                words.collect::<Vec<_>>().join(" ")
+            );
+            let style = theme::state_style(task).patch(rules::style(task, today));
+            let mut text = vec![Span::styled(text, style)];
+            text.extend(Subtasks::of(tasks, *i, progress).map(Subtasks::ui));
+
+            for (width, cell) in widths.iter_mut().zip([&state, &priority, &due, &tags]) {
+                *width = (*width).max(cell.width());
+            }
+            Row::new([
+                Cell::from(state),
+                Cell::from(priority).style(Style::default().fg(Color::Yellow)),
+                Cell::from(due).style(if overdue {
+                    Style::default().fg(Color::Red)
+                } else {
+                    dim
+                }),
+                Cell::from(tags).style(dim),
+                Cell::from(Line::from(text)),
+            ])
+            .style(theme::row_style(row))
+        })
+        .collect();
+
+    let headers = [
+        String::new(),
+        t("column-priority"),
+        t("column-due"),
+        t("column-tags"),
+        t("column-task"),
+    ];
+    for (width, header) in widths.iter_mut().zip(&headers).skip(1) {
+        // an empty column isn't worth its header
+        if *width > 0 {
+            *width = (*width).max(header.width());
+        }
+    }
+    let header = Row::new(headers).style(dim.add_modifier(Modifier::UNDERLINED));
+    let widths = (widths.iter().map(|w| Constraint::Length(*w as u16))).chain([Constraint::Min(0)]);
+    Table::new(rows, widths).header(header)
 }
 
-pub fn ui(f: &mut Frame, area: Rect, tasks: &[TaskItem], list_state: &mut ListState) {
-    let items: Vec<ListItem> = tasks.iter().map(|i| item_ui(i)).collect();
-    let list = List::new(items)
-        .style(
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_style(Style::default().fg(Color::Cyan))
-        .direction(ListDirection::TopToBottom);
-
-    f.render_stateful_widget(list, area, list_state);
+/// Draws only the tasks that fit in `area`, scrolled to keep the selection visible; a
+/// filtering search hides the tasks it doesn't match, the file's settings may hide done
+/// ones and the view the ones scheduled later
+pub fn ui(
+    f: &mut Frame,
+    area: Rect,
+    tasks: &[TaskItem],
+    task_list: &mut TaskList,
+    search: Option<&Search>,
+    settings: &FileSettings,
+    view: ListView,
+) {
+    // the columns have a header above them
+    let header = usize::from(task_list.columns);
+    let height = (area.height as usize).saturating_sub(header);
+    if height == 0 {
+        return;
+    }
+
+    let search = search.filter(|s| s.is_active());
+    // the task index of every row, `None` when all tasks are rows
+    let filter = search.filter(|s| s.filters());
+    let today = Local::now().date_naive();
+    let filtered = filter.is_some() || settings.hide_done || view != ListView::All;
+    let rows: Option<Vec<usize>> = filtered.then(|| {
+        (0..tasks.len())
+            .filter(|i| {
+                let task = &tasks[*i];
+                view.lists(task, today)
+                    && settings.lists(task)
+                    && filter.is_none_or(|s| s.matches(task))
+            })
+            .collect()
+    });
+    let row_count = rows.as_ref().map_or(tasks.len(), |r| r.len());
+    let task_at = |row: usize| rows.as_ref().map_or(row, |r| r[row]);
+
+    // the last column shows how far the list is scrolled when it doesn't fit
+    let scrollbar_area = match row_count > height && !theme::accessible() && area.width > 1 {
+        true => Some(Rect {
+            x: area.right() - 1,
+            width: 1,
+            ..area
+        }),
+        false => None,
+    };
+    let area = Rect {
+        width: area.width - scrollbar_area.map_or(0, |a| a.width),
+        ..area
+    };
+
+    // a task that stopped matching can't stay selected
+    if let Some(rows) = &rows {
+        let selected = task_list.state.selected();
+        if selected.is_some_and(|i| rows.binary_search(&i).is_err()) {
+            task_list.next_match(tasks.len(), |i| rows.binary_search(&i).is_ok());
+        }
+        if rows.is_empty() {
+            task_list.state.select(None);
+        }
+    }
+
+    let selected = task_list
+        .state
+        .selected()
+        .filter(|i| *i < tasks.len())
+        .map(|i| match &rows {
+            Some(rows) => rows.binary_search(&i).unwrap_or_default(),
+            None => i,
+        });
+    let mut offset = task_list
+        .state
+        .offset()
+        .min(row_count.saturating_sub(height));
+    match selected {
+        Some(i) if i < offset => offset = i,
+        Some(i) if i >= offset + height => offset = i + 1 - height,
+        _ => {}
+    }
+    *task_list.state.offset_mut() = offset;
+
+    let start = offset.saturating_sub(OVERSCAN);
+    let end = (offset + height + OVERSCAN).min(row_count);
+    let shown: Vec<usize> = (start..end).map(task_at).collect();
+    task_list.lines.retain(|i, _| shown.contains(i));
+
+    let text_style = Style::default()
+        .fg(theme::palette().text)
+        .add_modifier(Modifier::BOLD);
+    if task_list.columns {
+        let mut window = TableState::default()
+            .with_offset(offset - start)
+            .with_selected(selected.map(|i| i - start));
+        let table = columns_ui(tasks, &shown, start, today, settings.progress)
+            .style(text_style)
+            .highlight_style(theme::selection());
+        f.render_stateful_widget(table, area, &mut window);
+    } else {
+        let items: Vec<ListItem> = (start..end)
+            .zip(&shown)
+            .map(|(row, i)| {
+                let subtasks = Subtasks::of(tasks, *i, settings.progress);
+                let width = area.width as usize;
+                let item = task_list.item(*i, &tasks[*i], today, search, subtasks, width);
+                item.style(theme::row_style(row))
+            })
+            .collect();
+        let mut window = ListState::default()
+            .with_offset(offset - start)
+            .with_selected(selected.map(|i| i - start));
+
+        let list = List::new(items)
+            .style(text_style)
+            .highlight_style(theme::selection())
+            .direction(ListDirection::TopToBottom);
+
+        f.render_stateful_widget(list, area, &mut window);
+    }
+
+    if let Some(scrollbar_area) = scrollbar_area {
+        let glyphs = theme::glyphs();
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_symbol(Some(&glyphs.scrollbar_track))
+            .thumb_symbol(&glyphs.scrollbar_thumb)
+            .track_style(Style::default().fg(Color::DarkGray));
+        let mut state = ScrollbarState::new(row_count - height)
+            .position(offset)
+            .viewport_content_length(height);
+        f.render_stateful_widget(scrollbar, scrollbar_area, &mut state);
+    }
+
+    // screen readers and braille displays follow the cursor rather than the highlight
+    if let (true, Some(row)) = (theme::accessible(), selected) {
+        f.set_cursor(area.x, area.y + (header + row - offset) as u16);
+    }
 }
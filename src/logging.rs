@@ -0,0 +1,74 @@
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    io,
+    sync::{Mutex, OnceLock},
+};
+
+use color_eyre::eyre::{Result, WrapErr};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Log lines kept for the debug overlay
+const RECENT_LINES: usize = 200;
+
+static RECENT: OnceLock<Mutex<VecDeque<String>>> = OnceLock::new();
+
+/// Collects formatted log lines for the debug overlay
+struct Recent;
+
+impl io::Write for Recent {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut recent = RECENT.get_or_init(Default::default).lock().unwrap();
+        for line in String::from_utf8_lossy(buf).lines() {
+            if recent.len() == RECENT_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_owned());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sends log events to `log_file` and the debug overlay, `RUST_LOG` picks the levels
+pub fn init(log_file: Option<&str>) -> Result<()> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("tuitodo=info"));
+
+    let file = match log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .wrap_err_with(|| format!("could not open log file {}", path))?;
+            Some(fmt::layer().with_ansi(false).with_writer(Mutex::new(file)))
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file)
+        .with(
+            fmt::layer()
+                .with_ansi(false)
+                .without_time()
+                .with_target(false)
+                .with_writer(|| Recent),
+        )
+        .try_init()?;
+
+    Ok(())
+}
+
+/// The latest log lines, oldest first
+pub fn recent() -> Vec<String> {
+    match RECENT.get() {
+        Some(recent) => recent.lock().unwrap().iter().cloned().collect(),
+        None => vec![],
+    }
+}
@@ -1,297 +1,120 @@
-mod file;
-mod list;
-mod task_item;
-mod tui;
+use std::process::ExitCode;
 
 use clap::Parser;
-use core::panic;
-use std::time::Duration;
-
-use color_eyre::eyre::Result;
-use crossterm::event::{
-    self,
-    KeyCode::{self, Char},
+use color_eyre::eyre::{eyre, Result};
+use tuitodo::{
+    app::{self, Options},
+    cli::{self, Command},
+    config::Config,
+    crypt, hooks, i18n, logging, plugins,
+    remote::Remote,
+    session::Session,
+    stale,
 };
-use file::TaskStore;
-use list::TaskList;
-use ratatui::{prelude::*, widgets::*};
-use task_item::TaskItem;
-use tokio::sync::mpsc::{self, UnboundedSender};
-use tui::Event;
-use tui_input::{backend::crossterm::EventHandler, Input};
-
-// App state
-struct App {
-    counter: i64,
-    should_quit: bool,
-    action_tx: UnboundedSender<Action>,
-    mode: Mode,
-    new_task: Input,
-    tasks: TaskList,
-    store: TaskStore,
-}
-
-#[derive(PartialEq, Clone)]
-pub enum Mode {
-    Normal,
-    Edit,
-    Create,
-}
-
-// App actions
-#[derive(Clone)]
-pub enum Action {
-    Tick,
-    Increment,
-    Decrement,
-    NetworkRequestAndThenIncrement, // new
-    NetworkRequestAndThenDecrement, // new
-    Quit,
-    Render,
-    None,
-    NextTask,
-    PreviousTask,
-    ToggleTaskState,
-    HandleInputKey(event::Event),
-    AddTask,
-    ClearNewTask,
-    SaveTask,
-    SwitchMode(Mode),
-}
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// file path of the todo file to load
-    #[arg(short, long)]
-    file: String,
-}
+    /// file path of the todo file to load, defaults to `file` from the config
+    #[arg(short, long, global = true)]
+    file: Option<String>,
 
-fn ui(f: &mut Frame, app: &mut App) {
-    let center = centered_rect(f.size(), 80, 30);
+    /// render a compact list below the cursor instead of taking over the screen
+    #[arg(long)]
+    inline: bool,
 
-    let task_count = app.store.items.len() as u16;
-    let layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(vec![Constraint::Length(task_count), Constraint::Length(1)])
-        .split(center);
+    /// append tasks piped to stdin before starting
+    #[arg(long)]
+    stdin: bool,
 
-    list::ui(f, layout[0], &app.store.items, &mut app.tasks.state);
+    /// plain text for screen readers: no colours or icons, changes announced on the last line
+    #[arg(long)]
+    accessible: bool,
 
-    if app.mode != Mode::Create {
-        return;
-    }
-
-    let input = Paragraph::new(app.new_task.value());
-
-    let input_line = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(vec![Constraint::Length(2), Constraint::Min(1)])
-        .split(layout[1]);
-
-    f.render_widget(Paragraph::new("\u{f460}"), input_line[0]);
-    f.render_widget(input, input_line[1]);
+    /// ticks per second, which count timers down, overrides `interface.tick_rate`
+    #[arg(long)]
+    tick_rate: Option<f64>,
 
-    let width = layout[0].width.max(3) - 1;
-    let scroll = app.new_task.visual_scroll(width as usize);
+    /// frames drawn per second, overrides `interface.frame_rate`
+    #[arg(long)]
+    frame_rate: Option<f64>,
 
-    f.set_cursor(
-        layout[1].x + ((app.new_task.visual_cursor()).max(scroll) - scroll) as u16 + 2,
-        layout[1].y,
-    )
-}
-
-fn centered_rect(r: Rect, percent_x: u16, percent_y: u16) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
-
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1]
-}
+    /// append log lines to this file, `RUST_LOG` sets the levels
+    #[arg(long, global = true)]
+    log_file: Option<String>,
 
-fn get_action(app: &App, event: Event) -> Action {
-    match event {
-        Event::Error => Action::None,
-        Event::Tick => Action::Tick,
-        Event::Render => Action::Render,
-        Event::Key(key, event) => match app.mode {
-            Mode::Normal => match key.code {
-                Char('j') => Action::NextTask,
-                Char('k') => Action::PreviousTask,
-                Char(' ') => Action::ToggleTaskState,
-                Char('q') => Action::Quit,
-                Char('e') => Action::SwitchMode(Mode::Edit),
-                KeyCode::Enter => Action::SwitchMode(Mode::Create),
-                _ => Action::None,
-            },
-            Mode::Create => match key.code {
-                KeyCode::Esc => Action::ClearNewTask,
-                KeyCode::Enter => Action::AddTask,
-                _ => Action::HandleInputKey(event),
-            },
-            Mode::Edit => match key.code {
-                KeyCode::Esc => Action::ClearNewTask,
-                KeyCode::Enter => Action::SaveTask,
-                _ => Action::HandleInputKey(event),
-            },
-        },
-        _ => Action::None,
-    }
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-fn update(app: &mut App, action: Action) -> Option<Action> {
-    match action {
-        Action::Increment => {
-            app.counter += 1;
-        }
-        Action::Decrement => {
-            app.counter -= 1;
-        }
-        Action::NetworkRequestAndThenIncrement => {
-            let tx = app.action_tx.clone();
-            tokio::spawn(async move {
-                tokio::time::sleep(Duration::from_secs(5)).await; // simulate network request
-                tx.send(Action::Increment).unwrap();
-            });
-        }
-        Action::NetworkRequestAndThenDecrement => {
-            let tx = app.action_tx.clone();
-            tokio::spawn(async move {
-                tokio::time::sleep(Duration::from_secs(1)).await; // simulate network request
-                tx.send(Action::Decrement).unwrap();
-            });
-        }
-
-        Action::NextTask => {
-            app.tasks.next(app.store.items.len());
-        }
-        Action::PreviousTask => {
-            app.tasks.previous(app.store.items.len());
-        }
-
-        Action::ClearNewTask => {
-            app.new_task.reset();
-            app.mode = Mode::Normal
-        }
-
-        Action::AddTask => {
-            app.store.items.push(TaskItem::new(
-                app.new_task.value().into(),
-                task_item::TaskState::Open,
-            ));
-            app.new_task.reset();
-
-            let store = app.store.clone();
-            tokio::spawn(async move { store.save().await });
-        }
-
-        Action::HandleInputKey(event) => {
-            app.new_task.handle_event(&event);
-        }
-
-        Action::ToggleTaskState => {
-            if let Some(index) = app.tasks.state.selected() {
-                app.store.items[index].toggle_state();
-
-                let store = app.store.clone();
-                tokio::spawn(async move { store.save().await });
-            }
-        }
-
-        Action::SwitchMode(mode) => {
-            if mode == Mode::Edit && app.tasks.state.selected().is_none() {
-                return None;
-            }
-
-            app.mode = mode;
-        }
-
-        Action::Quit => app.should_quit = true,
-        _ => {}
-    };
-
-    None
-}
-
-async fn run() -> Result<()> {
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
     let args = Args::parse();
+    logging::init(args.log_file.as_deref())?;
+    let config = Config::load().await?;
+    hooks::init(config.hooks.clone());
+    stale::init(config.stale.clone());
+    i18n::init(config.locale.as_deref());
+
+    // doesn't need a todo file
+    if let Some(Command::Auth { target }) = args.command {
+        return cli::auth(target).map(|_| ExitCode::SUCCESS);
+    }
 
-    let (action_tx, mut action_rx) = mpsc::unbounded_channel(); // new
-
-    // ratatui terminal
-    let mut tui = tui::Tui::new()?.tick_rate(1.0).frame_rate(30.0);
-    tui.enter()?;
-
-    let Ok(store) = TaskStore::new(args.file).await else {
-        panic!("could not load tasks")
+    // the interface goes back to the file it was left in, commands stick to the configured one
+    let last_file = match (&args.file, &args.command) {
+        (None, None) => Session::load()
+            .await
+            .file
+            .filter(|f| Remote::parse(f, &config).is_some() || std::path::Path::new(f).exists()),
+        _ => None,
     };
-
-    let mut app = App {
-        counter: 0,
-        should_quit: false,
-        action_tx: action_tx.clone(),
-        new_task: Input::default(),
-        mode: Mode::Normal,
-        tasks: TaskList {
-            state: ListState::default(),
-        },
-        store,
+    let Some(file) = args.file.clone().or(last_file).or_else(|| config.file()) else {
+        return Err(eyre!(
+            "no todo file, pass --file or set `file` in the config"
+        ));
     };
 
-    loop {
-        let e = tui.next().await.unwrap();
-        match e {
-            tui::Event::Tick => action_tx.send(Action::Tick)?,
-            tui::Event::Render => action_tx.send(Action::Render)?,
-            tui::Event::Key(..) => {
-                let action = get_action(&app, e);
-                action_tx.send(action.clone())?;
-            }
-            _ => {}
-        };
-
-        while let Ok(action) = action_rx.try_recv() {
-            let mut maybe_action = Some(action);
-
-            while let Some(act) = maybe_action {
-                let next_action = update(&mut app, act.clone());
-                if let Action::Render = act {
-                    tui.draw(|f| {
-                        ui(f, &mut app);
-                    })?;
-                }
-                maybe_action = next_action;
+    let mut warnings = plugins::load();
+    let remote = Remote::parse(&file, &config);
+    if let Some(remote) = &remote {
+        if let Err(e) = remote.refresh().await {
+            if !remote.has_local_copy().await {
+                return Err(eyre!("could not fetch {}: {}", remote, e));
             }
-        }
-
-        if app.should_quit {
-            break;
+            warnings.push(format!("editing the local copy of {}: {}", remote, e));
         }
     }
+    let file = remote.as_ref().map(|r| r.local_path()).unwrap_or(file);
 
-    tui.exit()?;
+    if crypt::is_encrypted(&file) {
+        crypt::unlock(&file, &config.age).await?;
+    }
 
-    Ok(())
-}
+    let Some(command) = args.command else {
+        let options = Options {
+            inline: args.inline,
+            stdin: args.stdin,
+            accessible: args.accessible,
+            tick_rate: args.tick_rate,
+            frame_rate: args.frame_rate,
+        };
+        return (app::run(options, config, file, remote, warnings).await)
+            .map(|_| ExitCode::SUCCESS);
+    };
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let result = run().await;
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+    let before = tokio::fs::read(&file).await.ok();
+    let code = cli::run(command, &file, &config).await?;
 
-    result?;
+    if let Some(remote) = &remote {
+        if tokio::fs::read(&file).await.ok() != before {
+            remote.push().await?;
+        }
+    }
 
-    Ok(())
+    Ok(code)
 }
@@ -0,0 +1,76 @@
+use std::mem::discriminant;
+
+use crate::{app::Mode, batch::Batch, confirm::Confirm, stale::Review};
+
+/// A popup drawn over the screen, the topmost one open gets the keys
+#[derive(Clone, Debug)]
+pub enum Modal {
+    Confirm(Confirm),
+    Stats,
+    Errors,
+    /// The first of the sync conflicts waiting for a decision
+    Conflict,
+    Review(Review),
+    /// The changes of `:map`, made once they are confirmed
+    Batch(Batch),
+}
+
+impl Modal {
+    /// The mode shown in the status line and reported to scripts while it is on top
+    pub fn mode(&self) -> Mode {
+        match self {
+            Modal::Confirm(_) => Mode::Confirm,
+            Modal::Stats => Mode::Stats,
+            Modal::Errors => Mode::Errors,
+            Modal::Conflict => Mode::Conflict,
+            Modal::Review(_) => Mode::Review,
+            Modal::Batch(_) => Mode::Batch,
+        }
+    }
+}
+
+/// The popups that are open, layered in the order they were opened
+#[derive(Default)]
+pub struct Modals {
+    stack: Vec<Modal>,
+}
+
+impl Modals {
+    /// Puts `modal` on top, unless one of its kind is open already; a second question
+    /// waits until the first is answered
+    pub fn open(&mut self, modal: Modal) -> bool {
+        let kind = discriminant(&modal);
+        if self.stack.iter().any(|m| discriminant(m) == kind) {
+            return false;
+        }
+        self.stack.push(modal);
+        true
+    }
+
+    /// Closes the topmost popup, handing it back
+    pub fn close(&mut self) -> Option<Modal> {
+        self.stack.pop()
+    }
+
+    /// Closes the popups `matches` picks wherever they are in the stack
+    pub fn close_where(&mut self, matches: impl Fn(&Modal) -> bool) {
+        self.stack.retain(|m| !matches(m));
+    }
+
+    pub fn top(&self) -> Option<&Modal> {
+        self.stack.last()
+    }
+
+    pub fn top_mut(&mut self) -> Option<&mut Modal> {
+        self.stack.last_mut()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// The open popups from the bottom up, the order they are drawn in
+    pub fn iter(&self) -> impl Iterator<Item = &Modal> {
+        self.stack.iter()
+    }
+}
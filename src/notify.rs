@@ -0,0 +1,27 @@
+use tokio::process::Command;
+
+/// Fire-and-forget desktop notification via the platform's notifier
+pub fn desktop(title: &str, body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body, title
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name=tuitodo").arg(title).arg(body);
+        command
+    };
+
+    command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+
+    tokio::spawn(async move {
+        // a missing notifier is not worth surfacing
+        let _ = command.status().await;
+    });
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::{
+    config::config_dir,
+    task_item::{TaskItem, TaskState},
+};
+
+/// Keeps a runaway script from freezing the interface
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// What scripts register while they are loaded, functions are `(script, name)`
+#[derive(Default)]
+struct Registry {
+    script: usize,
+    actions: HashMap<String, (usize, String)>,
+    keys: HashMap<char, String>,
+    filters: HashMap<String, (usize, String)>,
+    renders: Vec<(usize, String)>,
+}
+
+/// Rhai scripts from `~/.config/tuitodo/plugins/*.rhai`
+///
+/// A script registers its functions when it is loaded:
+///
+/// - `action("name", "fn")`: `fn(tasks, selected)` returns the new task list, a message or
+///   nothing; run with `tuitodo plugin name` or a key
+/// - `bind("x", "name")`: runs the action on a key in the list
+/// - `filter("name", "fn")`: `fn(task)` returns a bool, used as `is:name` in filters
+/// - `render("fn")`: `fn(task)` returns text shown after the task in the list
+///
+/// Tasks are maps with `text`, `done`, `depth`, `due`, `priority`, `tags` and `meta`; the
+/// derived `due`, `priority` and `tags` are only read, change `meta` or `text` instead.
+pub struct Plugins {
+    engine: Engine,
+    scripts: Vec<AST>,
+    registry: Registry,
+}
+
+pub enum Outcome {
+    Tasks(Vec<TaskItem>),
+    Message(String),
+    Nothing,
+}
+
+static PLUGINS: OnceLock<Plugins> = OnceLock::new();
+
+/// Loads every plugin once at startup and returns the errors of those that failed
+pub fn load() -> Vec<String> {
+    let registry = Arc::new(Mutex::new(Registry::default()));
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let r = registry.clone();
+    engine.register_fn("action", move |name: &str, function: &str| {
+        let mut r = r.lock().unwrap();
+        let script = r.script;
+        r.actions
+            .insert(name.to_owned(), (script, function.to_owned()));
+    });
+    let r = registry.clone();
+    engine.register_fn("bind", move |key: &str, action: &str| {
+        if let Some(key) = key.chars().next() {
+            r.lock().unwrap().keys.insert(key, action.to_owned());
+        }
+    });
+    let r = registry.clone();
+    engine.register_fn("filter", move |name: &str, function: &str| {
+        let mut r = r.lock().unwrap();
+        let script = r.script;
+        r.filters
+            .insert(name.to_owned(), (script, function.to_owned()));
+    });
+    let r = registry.clone();
+    engine.register_fn("render", move |function: &str| {
+        let mut r = r.lock().unwrap();
+        let script = r.script;
+        r.renders.push((script, function.to_owned()));
+    });
+
+    let mut paths: Vec<_> = std::fs::read_dir(config_dir().join("plugins"))
+        .map(|dir| dir.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    paths.retain(|p| p.extension().is_some_and(|e| e == "rhai"));
+    paths.sort();
+
+    let mut scripts = vec![];
+    let mut errors = vec![];
+    for path in paths {
+        registry.lock().unwrap().script = scripts.len();
+        let result = engine
+            .compile_file(path.clone())
+            .and_then(|ast| engine.run_ast(&ast).map(|_| ast));
+
+        match result {
+            Ok(ast) => scripts.push(ast),
+            Err(e) => errors.push(format!("plugin {}: {}", path.display(), e)),
+        }
+    }
+
+    let registry = std::mem::take(&mut *registry.lock().unwrap());
+    let _ = PLUGINS.set(Plugins {
+        engine,
+        scripts,
+        registry,
+    });
+    errors
+}
+
+pub fn get() -> Option<&'static Plugins> {
+    PLUGINS.get()
+}
+
+impl Plugins {
+    fn call(
+        &self,
+        (script, function): &(usize, String),
+        args: impl rhai::FuncArgs,
+    ) -> Result<Dynamic, String> {
+        self.engine
+            .call_fn(&mut Scope::new(), &self.scripts[*script], function, args)
+            .map_err(|e| e.to_string())
+    }
+
+    /// The action bound to `key`
+    pub fn key(&self, key: char) -> Option<&str> {
+        self.registry.keys.get(&key).map(|a| a.as_str())
+    }
+
+    pub fn run(
+        &self,
+        action: &str,
+        tasks: &[TaskItem],
+        selected: Option<usize>,
+    ) -> Result<Outcome, String> {
+        let function = self
+            .registry
+            .actions
+            .get(action)
+            .ok_or_else(|| format!("no plugin action `{}`", action))?;
+
+        let array: Array = tasks.iter().map(|t| Dynamic::from_map(to_map(t))).collect();
+        let selected = selected.map(|i| i as i64).unwrap_or(-1);
+        let result = self.call(function, (array, selected))?;
+
+        if result.is_unit() {
+            return Ok(Outcome::Nothing);
+        }
+        if result.is_string() {
+            return Ok(Outcome::Message(result.to_string()));
+        }
+
+        let array = result
+            .try_cast::<Array>()
+            .ok_or_else(|| format!("`{}` must return a task list, a message or nothing", action))?;
+        array
+            .into_iter()
+            .enumerate()
+            .map(|(i, task)| {
+                let map = task
+                    .try_cast::<Map>()
+                    .ok_or_else(|| format!("`{}` returned a task that isn't a map", action))?;
+                Ok(from_map(&map, tasks.get(i)))
+            })
+            .collect::<Result<_, String>>()
+            .map(Outcome::Tasks)
+    }
+
+    pub fn has_filter(&self, name: &str) -> bool {
+        self.registry.filters.contains_key(name)
+    }
+
+    /// Failing filters match nothing
+    pub fn matches(&self, name: &str, task: &TaskItem) -> bool {
+        let Some(function) = self.registry.filters.get(name) else {
+            return false;
+        };
+
+        self.call(function, (to_map(task),))
+            .ok()
+            .and_then(|r| r.as_bool().ok())
+            .unwrap_or(false)
+    }
+
+    /// Text the render hooks add after the task
+    pub fn decorations(&self, task: &TaskItem) -> Vec<String> {
+        self.registry
+            .renders
+            .iter()
+            .filter_map(|function| self.call(function, (to_map(task),)).ok())
+            .map(|r| r.to_string())
+            .filter(|r| !r.is_empty())
+            .collect()
+    }
+}
+
+fn to_map(task: &TaskItem) -> Map {
+    let optional = |value: Option<&str>| match value {
+        Some(value) => Dynamic::from(value.to_owned()),
+        None => Dynamic::UNIT,
+    };
+
+    let mut map = Map::new();
+    map.insert("text".into(), task.text.clone().into());
+    map.insert("done".into(), (task.state == TaskState::Done).into());
+    map.insert("depth".into(), (task.depth as i64).into());
+    map.insert("due".into(), optional(task.get_meta("due")));
+    map.insert("scheduled".into(), optional(task.get_meta("scheduled")));
+    map.insert("priority".into(), optional(task.priority()));
+    map.insert(
+        "tags".into(),
+        task.tags()
+            .iter()
+            .map(|t| Dynamic::from(t.to_string()))
+            .collect::<Array>()
+            .into(),
+    );
+
+    let meta: Map = task
+        .meta
+        .iter()
+        .map(|(k, v)| (k.into(), v.clone().into()))
+        .collect();
+    map.insert("meta".into(), meta.into());
+    map
+}
+
+/// Metadata keeps the order of `original` as far as the keys are still there
+fn from_map(map: &Map, original: Option<&TaskItem>) -> TaskItem {
+    let string = |key: &str| map.get(key).map(|v| v.to_string()).unwrap_or_default();
+    let state = match map.get("done").and_then(|d| d.as_bool().ok()) {
+        Some(true) => TaskState::Done,
+        _ => TaskState::Open,
+    };
+
+    let mut task = TaskItem::new(string("text"), state);
+    task.depth = map
+        .get("depth")
+        .and_then(|d| d.as_int().ok())
+        .unwrap_or(0)
+        .max(0) as usize;
+
+    let meta = map
+        .get("meta")
+        .and_then(|m| m.clone().try_cast::<Map>())
+        .unwrap_or_default();
+    if let Some(original) = original {
+        task.notes = original.notes.clone();
+        for (key, _) in &original.meta {
+            if let Some(value) = meta.get(key.as_str()) {
+                task.meta.push((key.clone(), value.to_string()));
+            }
+        }
+    }
+    for (key, value) in meta {
+        if task.get_meta(&key).is_none() {
+            task.meta.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    task
+}
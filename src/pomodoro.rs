@@ -0,0 +1,51 @@
+use chrono::{DateTime, Duration, Local};
+
+use crate::task_item::TaskItem;
+
+const FOCUS_MINUTES: i64 = 25;
+const BREAK_MINUTES: i64 = 5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase {
+    Focus,
+    Break,
+}
+
+pub struct Pomodoro {
+    pub phase: Phase,
+    pub task: String,
+    pub task_id: Option<String>,
+    phase_end: DateTime<Local>,
+}
+
+impl Pomodoro {
+    pub fn new(task: &TaskItem) -> Self {
+        Self {
+            phase: Phase::Focus,
+            task: task.text.clone(),
+            task_id: task.id().map(str::to_owned),
+            phase_end: Local::now() + Duration::minutes(FOCUS_MINUTES),
+        }
+    }
+
+    pub fn remaining(&self) -> i64 {
+        (self.phase_end - Local::now()).num_seconds().max(0)
+    }
+
+    /// Advances the timer, returning the phase that just finished
+    pub fn tick(&mut self) -> Option<Phase> {
+        if Local::now() < self.phase_end {
+            return None;
+        }
+
+        let finished = self.phase;
+        let (phase, minutes) = match finished {
+            Phase::Focus => (Phase::Break, BREAK_MINUTES),
+            Phase::Break => (Phase::Focus, FOCUS_MINUTES),
+        };
+        self.phase = phase;
+        self.phase_end = Local::now() + Duration::minutes(minutes);
+
+        Some(finished)
+    }
+}
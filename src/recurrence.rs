@@ -0,0 +1,50 @@
+use chrono::{Datelike, NaiveDate};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Unit {
+    Day,
+    Week,
+}
+
+/// How often a task comes back, stored as `rec:<n><d|w>` (e.g. `rec:1d`, `rec:2w`)
+#[derive(Clone, Copy)]
+pub struct Recurrence {
+    pub every: i64,
+    pub unit: Unit,
+}
+
+impl Recurrence {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (unit, every) = match value {
+            "daily" => (Unit::Day, "1"),
+            "weekly" => (Unit::Week, "1"),
+            _ => {
+                let unit = match value.chars().last()? {
+                    'd' => Unit::Day,
+                    'w' => Unit::Week,
+                    _ => return None,
+                };
+                (unit, &value[..value.len() - 1])
+            }
+        };
+
+        let every: i64 = every.parse().ok()?;
+        if every < 1 {
+            return None;
+        }
+
+        Some(Self { every, unit })
+    }
+
+    /// Index of the occurrence `date` falls into; consecutive occurrences differ by one
+    pub fn period(&self, date: NaiveDate) -> i64 {
+        // 0001-01-01 was a Monday, so weekly occurrences start on Mondays
+        let days = date.num_days_from_ce() as i64 - 1;
+        let length = match self.unit {
+            Unit::Day => self.every,
+            Unit::Week => self.every * 7,
+        };
+
+        days.div_euclid(length)
+    }
+}
@@ -0,0 +1,282 @@
+use std::{fmt, path::PathBuf};
+
+use color_eyre::eyre::{eyre, Result};
+use reqwest::{header, Client, RequestBuilder, StatusCode};
+use serde_derive::Deserialize;
+use tokio::{fs, process::Command, sync::Mutex};
+
+use crate::{config::Config, hash, history::data_dir, secrets};
+
+/// Credentials for todo files given as `http(s)://` WebDAV URLs
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WebDavConfig {
+    pub username: Option<String>,
+    /// Taken from the keyring when missing, see `tuitodo auth webdav`
+    pub password: Option<String>,
+}
+
+/// A todo file somewhere else, edited through a local copy
+#[derive(Clone)]
+pub enum Remote {
+    /// `user@host:path` or `ssh://[user@]host/path`, copied with scp
+    Ssh { host: String, path: String },
+    /// `https://…/todo.md` on a WebDAV server such as Nextcloud
+    WebDav { url: String, auth: WebDavConfig },
+}
+
+/// Saves run concurrently, but each upload has to see the ETag of the previous one
+static LOCK: Mutex<()> = Mutex::const_new(());
+
+impl fmt::Display for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Remote::Ssh { host, path } => write!(f, "{}:{}", host, path),
+            Remote::WebDav { url, .. } => write!(f, "{}", url),
+        }
+    }
+}
+
+impl Remote {
+    /// `None` for local paths; without a scheme only `user@host:path` is taken for a remote
+    /// one, so a local file with a `:` in its name stays local
+    pub fn parse(file: &str, config: &Config) -> Option<Remote> {
+        if file.starts_with("http://") || file.starts_with("https://") {
+            let mut auth = config.webdav.clone();
+            if auth.password.is_none() {
+                auth.password = secrets::get("webdav");
+            }
+
+            return Some(Remote::WebDav {
+                url: file.to_owned(),
+                auth,
+            });
+        }
+
+        let (host, path) = match file.strip_prefix("ssh://") {
+            Some(rest) => {
+                let (host, path) = rest.split_once('/')?;
+                // `ssh://host/~/todo.md` is relative to the home directory
+                let path = match path.strip_prefix("~/") {
+                    Some(home) => format!("~/{}", home),
+                    None => format!("/{}", path),
+                };
+                (host, path)
+            }
+            None => {
+                let (host, path) = file.split_once(':')?;
+                let (user, name) = host.split_once('@')?;
+                if user.is_empty() || name.is_empty() {
+                    return None;
+                }
+                (host, path.to_owned())
+            }
+        };
+        if host.is_empty() || host.contains('/') || path.trim_start_matches(['/', '~']).is_empty() {
+            return None;
+        }
+
+        Some(Remote::Ssh {
+            host: host.to_owned(),
+            path,
+        })
+    }
+
+    /// Where the local copy is kept, it stays around for working offline
+    pub fn local_path(&self) -> String {
+        let id = self.to_string();
+        let path = match self {
+            Remote::Ssh { path, .. } => path,
+            Remote::WebDav { url, .. } => url,
+        };
+        let name = path.rsplit('/').next().unwrap_or("todo.md");
+
+        data_dir()
+            .join("remote")
+            .join(format!("{:016x}-{}", hash::stable(id.as_bytes()), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// There when the local copy was changed and not uploaded since
+    fn unpushed_path(&self) -> String {
+        self.local_path() + ".unpushed"
+    }
+
+    /// Notes that the local copy is about to change, until a push goes through
+    pub async fn mark_unpushed(&self) -> Result<()> {
+        fs::create_dir_all(data_dir().join("remote")).await?;
+        fs::write(self.unpushed_path(), "").await?;
+        Ok(())
+    }
+
+    /// Whether the local copy has changes the remote file doesn't
+    pub async fn is_unpushed(&self) -> bool {
+        fs::metadata(self.unpushed_path()).await.is_ok()
+    }
+
+    /// Brings the local copy up to date, uploading changes left from before first; when
+    /// they can't be uploaded the local copy is kept rather than fetched over
+    pub async fn refresh(&self) -> Result<()> {
+        if self.is_unpushed().await {
+            self.push()
+                .await
+                .map_err(|e| eyre!("changes to {} weren't uploaded yet: {}", self, e))?;
+        }
+        self.fetch().await
+    }
+
+    /// The ETag of the version the local copy is based on
+    fn etag_path(&self) -> String {
+        self.local_path() + ".etag"
+    }
+
+    /// Downloads the remote file over the local copy
+    pub async fn fetch(&self) -> Result<()> {
+        fs::create_dir_all(data_dir().join("remote")).await?;
+
+        match self {
+            Remote::Ssh { .. } => scp(&self.to_string(), &self.local_path()).await,
+            Remote::WebDav { url, auth } => {
+                let response = authorize(Client::new().get(url), auth).send().await?;
+                if response.status() == StatusCode::NOT_FOUND {
+                    // a new list, created on the first save
+                    let _ = fs::remove_file(self.etag_path()).await;
+                    fs::write(self.local_path(), "").await?;
+                    return Ok(());
+                }
+
+                let response = response.error_for_status()?;
+                self.store_etag(response.headers()).await?;
+                fs::write(self.local_path(), response.bytes().await?).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Uploads the local copy
+    pub async fn push(&self) -> Result<()> {
+        let _lock = LOCK.lock().await;
+        self.mark_unpushed().await?;
+        self.upload().await?;
+        let _ = fs::remove_file(self.unpushed_path()).await;
+        Ok(())
+    }
+
+    async fn upload(&self) -> Result<()> {
+        match self {
+            Remote::Ssh { .. } => scp(&self.local_path(), &self.to_string()).await,
+            Remote::WebDav { url, auth } => {
+                let client = Client::new();
+                let content = fs::read(self.local_path()).await?;
+                let request = match fs::read_to_string(self.etag_path()).await {
+                    Ok(etag) => client.put(url).header(header::IF_MATCH, etag),
+                    Err(_) => client.put(url).header(header::IF_NONE_MATCH, "*"),
+                };
+
+                let response = authorize(request, auth).body(content).send().await?;
+                if response.status() == StatusCode::PRECONDITION_FAILED {
+                    return Err(eyre!("it was changed by someone else meanwhile"));
+                }
+                let response = response.error_for_status()?;
+
+                // not every server returns the new ETag from a PUT
+                match response.headers().contains_key(header::ETAG) {
+                    true => self.store_etag(response.headers()).await,
+                    false => {
+                        let head = authorize(client.head(url), auth).send().await?;
+                        self.store_etag(head.error_for_status()?.headers()).await
+                    }
+                }
+            }
+        }
+    }
+
+    async fn store_etag(&self, headers: &header::HeaderMap) -> Result<()> {
+        match headers.get(header::ETAG).and_then(|e| e.to_str().ok()) {
+            Some(etag) => fs::write(self.etag_path(), etag).await?,
+            None => {
+                let _ = fs::remove_file(self.etag_path()).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn has_local_copy(&self) -> bool {
+        fs::metadata(PathBuf::from(self.local_path())).await.is_ok()
+    }
+}
+
+fn authorize(request: RequestBuilder, auth: &WebDavConfig) -> RequestBuilder {
+    match &auth.username {
+        Some(username) => request.basic_auth(username, auth.password.as_ref()),
+        None => request,
+    }
+}
+
+async fn scp(from: &str, to: &str) -> Result<()> {
+    // never prompt, a password prompt would end up inside the interface
+    let output = Command::new("scp")
+        .args([
+            "-q",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ConnectTimeout=10",
+            from,
+            to,
+        ])
+        .output()
+        .await?;
+
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(eyre!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim().to_owned()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(file: &str) -> Option<String> {
+        Remote::parse(file, &Config::default()).map(|r| r.to_string())
+    }
+
+    #[test]
+    fn ssh_remotes_need_a_user_or_a_scheme() {
+        assert_eq!(parse("me@box:todo.md").as_deref(), Some("me@box:todo.md"));
+        assert_eq!(
+            parse("ssh://box/srv/todo.md").as_deref(),
+            Some("box:/srv/todo.md")
+        );
+        assert_eq!(
+            parse("ssh://me@box/~/todo.md").as_deref(),
+            Some("me@box:~/todo.md")
+        );
+        assert_eq!(
+            parse("https://dav.example/todo.md").as_deref(),
+            Some("https://dav.example/todo.md")
+        );
+    }
+
+    #[test]
+    fn local_paths_stay_local() {
+        for file in [
+            "todo.md",
+            "notes:todo.md",
+            "C:\\todo.md",
+            "dir/a:b.md",
+            "@box:todo.md",
+            "me@:todo.md",
+            "me@box:",
+            "ssh://box",
+            "ssh://box/",
+        ] {
+            assert_eq!(parse(file), None, "{}", file);
+        }
+    }
+}
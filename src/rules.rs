@@ -0,0 +1,113 @@
+use std::sync::Mutex;
+
+use chrono::NaiveDate;
+use ratatui::style::Style;
+use regex::Regex;
+use serde_derive::Deserialize;
+
+use crate::{
+    filter::{Filter, SearchConfig},
+    task_item::TaskItem,
+    theme::TextStyle,
+};
+
+/// `[[theme.rules]]`, a style for the tasks a filter expression or a pattern picks:
+///
+/// ```toml
+/// [[theme.rules]]
+/// pattern = "URGENT"
+/// style = "bold red"
+///
+/// [[theme.rules]]
+/// filter = "#someday or waiting"
+/// style = "dim"
+/// ```
+///
+/// Every rule that matches is applied in the order they are written, later ones win.
+#[derive(Clone, Deserialize)]
+pub struct StyleRule {
+    /// Like the search, e.g. `due:<1d and not done`
+    pub filter: Option<String>,
+    /// A regular expression matched against the text
+    pub pattern: Option<String>,
+    pub style: TextStyle,
+}
+
+enum Matcher {
+    Filter(Filter),
+    Regex(Regex),
+}
+
+struct Rule {
+    matcher: Matcher,
+    style: Style,
+}
+
+/// The parsed rules and the day they were parsed for, relative dates in filters move on
+struct Rules {
+    config: Vec<StyleRule>,
+    search: SearchConfig,
+    parsed: Vec<Rule>,
+    today: Option<NaiveDate>,
+}
+
+static RULES: Mutex<Option<Rules>> = Mutex::new(None);
+
+/// Takes the rules of the config, the ones that don't parse are left out with a warning
+/// each; plugins have to be loaded first for their `is:` filters
+pub fn init(config: &[StyleRule], search: &SearchConfig, today: NaiveDate) -> Vec<String> {
+    let mut warnings = vec![];
+    let config: Vec<StyleRule> = (config.iter().enumerate())
+        .filter_map(|(i, rule)| match parse(rule, search, today) {
+            Ok(_) => Some(rule.clone()),
+            Err(e) => {
+                warnings.push(format!("style rule {} is left out: {}", i + 1, e));
+                None
+            }
+        })
+        .collect();
+    if !config.is_empty() {
+        *RULES.lock().unwrap() = Some(Rules {
+            config,
+            search: search.clone(),
+            parsed: vec![],
+            today: None,
+        });
+    }
+    warnings
+}
+
+fn parse(rule: &StyleRule, search: &SearchConfig, today: NaiveDate) -> Result<Rule, String> {
+    let matcher = match (&rule.filter, &rule.pattern) {
+        (Some(filter), None) => Matcher::Filter(Filter::parse(filter, today, search)?),
+        (None, Some(pattern)) => Matcher::Regex(Regex::new(pattern).map_err(|e| e.to_string())?),
+        _ => return Err("needs either `filter` or `pattern`".to_owned()),
+    };
+    Ok(Rule {
+        matcher,
+        style: rule.style.0,
+    })
+}
+
+/// The styles of the rules `task` matches, on top of each other
+pub fn style(task: &TaskItem, today: NaiveDate) -> Style {
+    let mut rules = RULES.lock().unwrap();
+    let Some(rules) = rules.as_mut() else {
+        return Style::default();
+    };
+    if rules.today != Some(today) {
+        rules.parsed = (rules.config.iter())
+            .filter_map(|rule| parse(rule, &rules.search, today).ok())
+            .collect();
+        rules.today = Some(today);
+    }
+
+    rules
+        .parsed
+        .iter()
+        .filter(|rule| match &rule.matcher {
+            Matcher::Filter(filter) => filter.matches(task),
+            Matcher::Regex(regex) => regex.is_match(&task.text),
+        })
+        .fold(Style::default(), |style, rule| style.patch(rule.style))
+}
@@ -0,0 +1,208 @@
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    app::{Action, Engine, Snapshot},
+    cli,
+    config::Config,
+    file::TaskStore,
+    task_item::TaskState,
+};
+
+const SCREEN_SIZE: (u16, u16) = (80, 24);
+
+/// Replays the script at `path` (`-` for stdin) against `file`, one command per line:
+///
+/// ```text
+/// key j j space       press keys, named ones are space, enter, esc, tab, backspace,
+///                     delete, up, down, left, right and ctrl-<char>
+/// type buy milk       type text into the create or edit input
+/// tick                let a second pass, reopening due recurring tasks
+/// expect mode normal  fail unless the app is in that mode
+/// expect count 3      ... has that many tasks
+/// expect selected 2   ... has that task number selected, or `none`
+/// expect task 2 text  ... has that text on the task
+/// expect state 2 done ... has that task `done` or `open`
+/// expect input text   ... has that text in the input
+/// expect message text ... shows that message
+/// print               print the numbered tasks, `>` marks the selection
+/// screen [80x24]      print the interface as it would be drawn
+/// ```
+///
+/// Empty lines and lines starting with `#` are skipped, replay stops at `q`. The tasks
+/// are saved at the end unless `dry_run` is set.
+pub async fn run(path: &str, file: &str, config: &Config, dry_run: bool) -> Result<()> {
+    let script = match path {
+        "-" => cli::read_stdin().await?,
+        _ => tokio::fs::read_to_string(path).await?,
+    };
+
+    let store = TaskStore::new(file.to_owned())
+        .await
+        .wrap_err_with(|| format!("could not load tasks from {}", file))?;
+    let store = store.auto_commit(config.git.auto_commit);
+    let mut engine = Engine::new(store, config.clone());
+
+    for (number, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let snapshot = step(&mut engine, line)
+            .await
+            .map_err(|e| eyre!("{}:{}: {}", path, number + 1, e))?;
+        if snapshot.quit {
+            break;
+        }
+    }
+
+    if !dry_run {
+        engine.into_store().save().await?;
+    }
+
+    Ok(())
+}
+
+async fn step(engine: &mut Engine, line: &str) -> Result<Snapshot> {
+    let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+
+    match command {
+        "key" => {
+            let mut snapshot = engine.snapshot();
+            for name in rest.split_whitespace() {
+                snapshot = engine.key(parse_key(name)?).await;
+                if snapshot.quit {
+                    break;
+                }
+            }
+            Ok(snapshot)
+        }
+        "type" => {
+            let mut snapshot = engine.snapshot();
+            for c in rest.chars() {
+                snapshot = engine
+                    .key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+                    .await;
+            }
+            Ok(snapshot)
+        }
+        "tick" => Ok(engine.dispatch(Action::Tick).await),
+        "expect" => {
+            let snapshot = engine.snapshot();
+            expect(&snapshot, rest)?;
+            Ok(snapshot)
+        }
+        "print" => {
+            let snapshot = engine.snapshot();
+            for (i, task) in snapshot.tasks.iter().enumerate() {
+                let marker = if snapshot.selected == Some(i) {
+                    '>'
+                } else {
+                    ' '
+                };
+                let state = if task.state == TaskState::Done {
+                    'x'
+                } else {
+                    ' '
+                };
+                println!("{} {:>3} [{}] {}", marker, i + 1, state, task.text);
+            }
+            Ok(snapshot)
+        }
+        "screen" => {
+            let (width, height) = match rest {
+                "" => SCREEN_SIZE,
+                size => parse_size(size)?,
+            };
+            println!("{}", engine.render(width, height)?);
+            Ok(engine.snapshot())
+        }
+        _ => Err(eyre!("unknown command `{}`", command)),
+    }
+}
+
+fn expect(snapshot: &Snapshot, check: &str) -> Result<()> {
+    let (what, expected) = check.split_once(' ').unwrap_or((check, ""));
+    let expected = expected.trim();
+
+    let actual = match what {
+        "mode" => format!("{:?}", snapshot.mode).to_lowercase(),
+        "count" => snapshot.tasks.len().to_string(),
+        "selected" => match snapshot.selected {
+            Some(i) => (i + 1).to_string(),
+            None => "none".to_owned(),
+        },
+        "input" => snapshot.input.clone(),
+        "message" => snapshot.message.clone().unwrap_or_default(),
+        "task" | "state" => {
+            let (number, expected) = expected.split_once(' ').unwrap_or((expected, ""));
+            let task = number
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| snapshot.tasks.get(i))
+                .ok_or_else(|| eyre!("there is no task {}", number))?;
+            let actual = match (what, &task.state) {
+                ("task", _) => task.text.clone(),
+                (_, TaskState::Done) => "done".to_owned(),
+                (_, TaskState::Open) => "open".to_owned(),
+            };
+            return compare(what, expected.trim(), &actual);
+        }
+        _ => return Err(eyre!("can't expect `{}`", what)),
+    };
+
+    compare(what, expected, &actual)
+}
+
+fn compare(what: &str, expected: &str, actual: &str) -> Result<()> {
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(eyre!(
+            "expected {} `{}`, found `{}`",
+            what,
+            expected,
+            actual
+        ));
+    }
+    Ok(())
+}
+
+fn parse_key(name: &str) -> Result<KeyEvent> {
+    if let Some(c) = name.strip_prefix("ctrl-") {
+        let mut chars = c.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)),
+            _ => Err(eyre!("unknown key `{}`", name)),
+        };
+    }
+
+    let code = match name {
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return Err(eyre!("unknown key `{}`", name)),
+            }
+        }
+    };
+
+    Ok(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+fn parse_size(size: &str) -> Result<(u16, u16)> {
+    size.split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .ok_or_else(|| eyre!("invalid screen size `{}`, expected e.g. 80x24", size))
+}
@@ -0,0 +1,165 @@
+use std::ops::Range;
+
+use chrono::NaiveDate;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use regex::{Regex, RegexBuilder};
+use tui_input::Input;
+
+use crate::{
+    filter::{Filter, SearchConfig},
+    i18n::t,
+    task_item::TaskItem,
+    text_input, theme,
+};
+
+/// Queries starting with this are a regular expression matched against the text
+const REGEX_PREFIX: &str = "re:";
+
+/// Compiled patterns bigger than this are refused rather than slowing every frame down
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// The search typed after `/`, in the filter syntax of `tuitodo list --filter` or a regular
+/// expression after `re:`; matches are highlighted and `n`/`N` jump between them
+#[derive(Default)]
+pub struct Search {
+    pub input: Input,
+    /// Only the matching tasks are listed instead of all of them
+    pub filtering: bool,
+    /// `None` while the query is empty or doesn't parse
+    matcher: Option<Matcher>,
+    error: Option<String>,
+}
+
+enum Matcher {
+    Filter(Filter),
+    Regex(Regex),
+}
+
+impl Search {
+    /// A search for `query` that was already confirmed
+    pub fn new(query: String, filtering: bool, today: NaiveDate, config: &SearchConfig) -> Self {
+        let mut search = Self {
+            input: Input::new(query),
+            filtering,
+            matcher: None,
+            error: None,
+        };
+        search.update(today, config);
+        search
+    }
+
+    /// Parses the query again after it was edited
+    pub fn update(&mut self, today: NaiveDate, config: &SearchConfig) {
+        let query = self.input.value();
+        let parsed = match query.strip_prefix(REGEX_PREFIX) {
+            Some("") => Ok(None),
+            Some(pattern) => {
+                regex(pattern, config.case_sensitive(pattern)).map(|r| Some(Matcher::Regex(r)))
+            }
+            None if query.trim().is_empty() => Ok(None),
+            None => Filter::parse(query, today, config).map(|f| Some(Matcher::Filter(f))),
+        };
+        (self.matcher, self.error) = match parsed {
+            Ok(matcher) => (matcher, None),
+            Err(e) => (None, Some(e)),
+        };
+    }
+
+    /// Switches between a filter and a regular expression, keeping what was typed
+    pub fn toggle_regex(&mut self, today: NaiveDate, config: &SearchConfig) {
+        let query = self.input.value();
+        let toggled = match query.strip_prefix(REGEX_PREFIX) {
+            Some(pattern) => pattern.to_owned(),
+            None => format!("{}{}", REGEX_PREFIX, query),
+        };
+        self.input = Input::new(toggled);
+        self.update(today, config);
+    }
+
+    pub fn query(&self) -> &str {
+        self.input.value()
+    }
+
+    /// Whether the query filters anything
+    pub fn is_active(&self) -> bool {
+        self.matcher.is_some()
+    }
+
+    /// Whether tasks that don't match are hidden
+    pub fn filters(&self) -> bool {
+        self.filtering && self.is_active()
+    }
+
+    pub fn matches(&self, task: &TaskItem) -> bool {
+        match &self.matcher {
+            Some(Matcher::Filter(filter)) => filter.matches(task),
+            Some(Matcher::Regex(regex)) => regex.is_match(&task.text),
+            None => true,
+        }
+    }
+
+    /// The parts of `text` that made the task match
+    pub fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.matcher {
+            Some(Matcher::Filter(filter)) => filter.highlights(text),
+            // empty matches have nothing to show
+            Some(Matcher::Regex(regex)) => regex
+                .find_iter(text)
+                .map(|m| m.range())
+                .filter(|r| !r.is_empty())
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+/// Compiles `pattern`, reporting only the reason when it is invalid since the full message
+/// spans several lines
+fn regex(pattern: &str, case_sensitive: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| match e {
+            regex::Error::CompiledTooBig(_) => "pattern too big".to_owned(),
+            e => {
+                let message = e.to_string();
+                let reason = message.lines().last().unwrap_or_default();
+                reason.trim_start_matches("error: ").to_owned()
+            }
+        })
+}
+
+/// The query below the list, with the reason when it doesn't parse and the cursor while
+/// it is typed
+pub fn ui(f: &mut Frame, area: Rect, search: &Search, typing: bool) {
+    let (visible, cursor) =
+        text_input::visible(&search.input, area.width.saturating_sub(1) as usize);
+    let mut spans = vec![
+        Span::styled("/", Style::default().fg(theme::palette().accent)),
+        Span::raw(visible),
+    ];
+    if let Some(error) = &search.error {
+        spans.push(Span::styled(
+            format!("  {}", error),
+            Style::default().fg(Color::Red),
+        ));
+    } else if search.filters() {
+        spans.push(Span::styled(
+            format!("  {}", t("search-only-matches")),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+
+    if !typing {
+        return;
+    }
+    f.set_cursor(area.x + 1 + cursor as u16, area.y);
+}
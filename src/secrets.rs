@@ -0,0 +1,15 @@
+use color_eyre::eyre::Result;
+use keyring::Entry;
+
+/// Service name of the entries in the system keyring
+const SERVICE: &str = "tuitodo";
+
+/// The secret stored for `name`, `None` when there is none or no keyring is available
+pub fn get(name: &str) -> Option<String> {
+    Entry::new(SERVICE, name).ok()?.get_password().ok()
+}
+
+pub fn set(name: &str, secret: &str) -> Result<()> {
+    Entry::new(SERVICE, name)?.set_password(secret)?;
+    Ok(())
+}
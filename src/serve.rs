@@ -0,0 +1,268 @@
+use std::{convert::Infallible, sync::Arc, time::SystemTime};
+
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::Local;
+use color_eyre::eyre::{self, eyre};
+use serde_derive::Deserialize;
+use tokio::{
+    fs,
+    sync::{broadcast, Mutex},
+};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+use crate::{
+    export::{self, TaskOutput},
+    file::{parse_task, TaskStore},
+    history::{History, HistoryEntry, HistoryKind},
+    hooks::{self, Hook},
+    ipc, journal,
+    task_item::{TaskItem, TaskState},
+};
+
+#[derive(Clone)]
+struct Server {
+    file: String,
+    token: Option<String>,
+    /// Requests read and write the whole file, one at a time
+    lock: Arc<Mutex<()>>,
+    changes: broadcast::Sender<()>,
+}
+
+struct Error(StatusCode, String);
+
+impl From<eyre::Report> for Error {
+    fn from(error: eyre::Report) -> Self {
+        Error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        (self.0, self.1).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct NewTask {
+    text: String,
+}
+
+/// Serves the todo file over HTTP until interrupted:
+///
+/// - `GET /tasks` lists the tasks as JSON
+/// - `POST /tasks` with `{"text": "..."}` adds one
+/// - `POST /tasks/{n}/toggle` and `DELETE /tasks/{n}` work on task numbers like the CLI
+/// - `GET /events` sends a `changed` event whenever the file changes, from here or elsewhere
+///
+/// While the interface runs on the file, changes are handed to it and answered with an empty
+/// `202 Accepted`
+pub async fn serve(file: &str, host: &str, port: u16, token: Option<String>) -> eyre::Result<()> {
+    let (changes, _) = broadcast::channel(16);
+    let server = Server {
+        file: file.to_owned(),
+        token,
+        lock: Arc::new(Mutex::new(())),
+        changes: changes.clone(),
+    };
+
+    tokio::spawn(watch(file.to_owned(), changes));
+
+    let app = Router::new()
+        .route("/tasks", get(list).post(add))
+        .route("/tasks/:index/toggle", post(toggle))
+        .route("/tasks/:index", delete(remove))
+        .route("/events", get(events))
+        .layer(middleware::from_fn_with_state(server.clone(), authorize))
+        .with_state(server);
+
+    let listener = tokio::net::TcpListener::bind((host, port)).await?;
+    eprintln!("serving {} on http://{}", file, listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn modified(path: impl AsRef<std::path::Path>) -> SystemTime {
+    fs::metadata(path)
+        .await
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Polls the modification time, so edits from the interface or an editor are noticed too
+async fn watch(file: String, changes: broadcast::Sender<()>) {
+    // big lists change through their journal
+    let changed = || async {
+        modified(&file)
+            .await
+            .max(modified(journal::path(&file)).await)
+    };
+
+    let mut last = changed().await;
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let current = changed().await;
+        if current != last {
+            last = current;
+            let _ = changes.send(());
+        }
+    }
+}
+
+async fn authorize(State(server): State<Server>, request: Request, next: Next) -> Response {
+    let Some(token) = &server.token else {
+        return next.run(request).await;
+    };
+
+    let expected = format!("Bearer {}", token);
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok());
+
+    match given.is_some_and(|given| same(given.as_bytes(), expected.as_bytes())) {
+        true => next.run(request).await,
+        false => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+/// Compares without stopping at the first difference, so the time taken doesn't give away
+/// how much of the token was right
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn load(file: &str) -> Result<TaskStore, Error> {
+    TaskStore::new(file.to_owned())
+        .await
+        .map_err(|e| eyre!("could not load tasks from {}: {}", file, e).into())
+}
+
+/// Translates a 1-based task number into an index into `store.items`
+fn position(store: &TaskStore, index: usize) -> Result<usize, Error> {
+    match index {
+        0 => Err(Error(
+            StatusCode::NOT_FOUND,
+            "there is no task 0".to_owned(),
+        )),
+        i if i > store.items.len() => Err(Error(
+            StatusCode::NOT_FOUND,
+            format!("there is no task {}", index),
+        )),
+        i => Ok(i - 1),
+    }
+}
+
+async fn log(kind: HistoryKind, file: &str, task: &TaskItem) -> Result<(), Error> {
+    let entry = HistoryEntry::new(kind, file, &task.text).task_id(task.id());
+    History::new().append(entry).await?;
+    Ok(())
+}
+
+fn task_json(index: usize, task: &TaskItem) -> Result<Json<serde_json::Value>, Error> {
+    let value = serde_json::to_value(TaskOutput::new(index, task)).map_err(eyre::Report::from)?;
+    Ok(Json(value))
+}
+
+async fn list(State(server): State<Server>) -> Result<Response, Error> {
+    let _lock = server.lock.lock().await;
+    let store = load(&server.file).await?;
+
+    let tasks = store.items.iter().enumerate().map(|(i, t)| (i + 1, t));
+    let json = export::json(&tasks.collect())?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response())
+}
+
+async fn add(State(server): State<Server>, Json(new): Json<NewTask>) -> Result<Response, Error> {
+    let _lock = server.lock.lock().await;
+    // a running interface would overwrite the change with its next save
+    if ipc::send(&server.file, ipc::Request::Add(new.text.clone())).await? {
+        return Ok(StatusCode::ACCEPTED.into_response());
+    }
+    let mut store = load(&server.file).await?;
+
+    let today = Local::now().date_naive();
+    let mut task = parse_task(&new.text, TaskState::Open);
+    task.resolve_dates(today);
+    task.mark_created(today);
+
+    store.items.push(task.clone());
+    let index = store.items.len();
+    store.save().await?;
+    hooks::fire(Hook::Add, &server.file, Some(&task)).await;
+    log(HistoryKind::Add, &server.file, &task).await?;
+
+    Ok((StatusCode::CREATED, task_json(index, &task)?).into_response())
+}
+
+async fn toggle(State(server): State<Server>, Path(index): Path<usize>) -> Result<Response, Error> {
+    let _lock = server.lock.lock().await;
+    let mut store = load(&server.file).await?;
+    let i = position(&store, index)?;
+
+    let task = &mut store.items[i];
+    task.toggle_state();
+    task.record_state_change(Local::now().date_naive());
+    let kind = match task.state {
+        TaskState::Done => {
+            task.stop_tracking();
+            HistoryKind::Complete
+        }
+        TaskState::Open => HistoryKind::Uncomplete,
+    };
+    let task = task.clone();
+
+    let request = match task.state {
+        TaskState::Done => ipc::Request::Done,
+        TaskState::Open => ipc::Request::Reopen,
+    };
+    if ipc::send_for(&server.file, &task, request).await? {
+        return Ok(StatusCode::ACCEPTED.into_response());
+    }
+
+    store.save().await?;
+    if let HistoryKind::Complete = kind {
+        hooks::fire(Hook::Complete, &server.file, Some(&task)).await;
+    }
+    log(kind, &server.file, &task).await?;
+
+    Ok(task_json(index, &task)?.into_response())
+}
+
+async fn remove(
+    State(server): State<Server>,
+    Path(index): Path<usize>,
+) -> Result<StatusCode, Error> {
+    let _lock = server.lock.lock().await;
+    let mut store = load(&server.file).await?;
+    let i = position(&store, index)?;
+
+    if ipc::send_for(&server.file, &store.items[i], ipc::Request::Remove).await? {
+        return Ok(StatusCode::ACCEPTED);
+    }
+
+    let task = store.items.remove(i);
+    store.save().await?;
+    hooks::fire(Hook::Delete, &server.file, Some(&task)).await;
+    log(HistoryKind::Delete, &server.file, &task).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn events(
+    State(server): State<Server>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(server.changes.subscribe())
+        .map(|_| Ok(Event::default().event("changed").data("tasks")));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
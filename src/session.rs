@@ -0,0 +1,87 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use chrono::{DateTime, Local};
+use color_eyre::eyre::Result;
+use serde_derive::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::warn;
+
+use crate::history::data_dir;
+
+/// Files whose view is remembered at most, the ones closed longest ago are forgotten
+const MAX_FILES: usize = 20;
+
+/// Where the interface was left, so starting it again without `--file` goes back there
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// The file open last, a remote one the way it was given
+    pub file: Option<String>,
+    /// The view each file was left in, by absolute path
+    views: HashMap<String, View>,
+}
+
+/// Where a file was left
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct View {
+    pub closed: DateTime<Local>,
+    pub selected: Option<usize>,
+    /// The first task row shown
+    pub offset: usize,
+    pub focus: bool,
+    /// The confirmed search, with whether only its matches are listed
+    pub search: Option<String>,
+    pub filtering: bool,
+}
+
+impl Session {
+    pub async fn load() -> Self {
+        let Ok(content) = fs::read_to_string(session_path()).await else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("unreadable session: {}", e);
+            Self::default()
+        })
+    }
+
+    pub fn view(&self, file: &str) -> Option<&View> {
+        self.views.get(&absolute(file))
+    }
+
+    /// Remembers `file` as the one open last, left in `view`; `path` is where it is read
+    /// from, the local copy for a remote file
+    pub async fn save(mut self, file: &str, path: &str, view: View) -> Result<()> {
+        self.file = Some(match file == path {
+            true => absolute(file),
+            false => file.to_owned(),
+        });
+        self.views.insert(absolute(path), view);
+
+        if self.views.len() > MAX_FILES {
+            let mut closed: Vec<_> = self.views.values().map(|v| v.closed).collect();
+            closed.sort_unstable();
+            let oldest = closed[self.views.len() - MAX_FILES];
+            self.views.retain(|_, v| v.closed >= oldest);
+        }
+
+        let path = session_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::write(path, serde_json::to_string(&self)?).await?;
+        Ok(())
+    }
+}
+
+/// The same file opened from another directory is the same file
+fn absolute(file: &str) -> String {
+    std::fs::canonicalize(file)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| file.to_owned())
+}
+
+fn session_path() -> PathBuf {
+    data_dir().join("session.json")
+}
@@ -0,0 +1,116 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::{
+    i18n::t,
+    task_item::{TaskItem, TaskState},
+    theme,
+};
+
+/// Columns taken by the sidebar, its border included
+pub const WIDTH: u16 = 24;
+
+/// A context or tag and how many of the listed tasks that mention it are open
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub tag: String,
+    pub open: usize,
+}
+
+/// The `@contexts` and `#tags` of the tasks next to the list, picking one filters it
+#[derive(Default)]
+pub struct Sidebar {
+    pub visible: bool,
+    /// The row picked with j and k, the first one lists every task
+    pub selected: usize,
+}
+
+impl Sidebar {
+    /// Moves the selection over `count` entries and the row above them
+    pub fn select_next(&mut self, count: usize, forward: bool) {
+        self.selected = match forward {
+            true => (self.selected + 1).min(count),
+            false => self.selected.saturating_sub(1),
+        };
+    }
+}
+
+/// The contexts and tags of `tasks` that are open, or picked as `active`, contexts first
+/// and either in alphabetical order; a tag is the same whatever its case
+pub fn entries<'a>(tasks: impl Iterator<Item = &'a TaskItem>, active: Option<&str>) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = vec![];
+    for task in tasks {
+        let open = usize::from(task.state == TaskState::Open);
+        for tag in task.tags() {
+            match entries.iter_mut().find(|e| e.tag.eq_ignore_ascii_case(tag)) {
+                Some(entry) => entry.open += open,
+                None => entries.push(Entry {
+                    tag: tag.to_owned(),
+                    open,
+                }),
+            }
+        }
+    }
+
+    // done tasks don't need a place to be found from, but the one filtered by stays
+    entries.retain(|e| e.open > 0 || active.is_some_and(|a| a.eq_ignore_ascii_case(&e.tag)));
+    entries.sort_by_key(|e| (!e.tag.starts_with('@'), e.tag.to_lowercase()));
+    entries
+}
+
+/// The entries below a row for every task, the one filtered by marked and the selected
+/// one highlighted while the sidebar has the keys
+pub fn ui(
+    f: &mut Frame,
+    area: Rect,
+    sidebar: &Sidebar,
+    entries: &[Entry],
+    active: Option<&str>,
+    focused: bool,
+) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let width = area.width.saturating_sub(1) as usize;
+    let row = |label: String, count: Option<usize>, is_active: bool| {
+        let count = count.map(|c| c.to_string()).unwrap_or_default();
+        let style = match is_active {
+            true => Style::default()
+                .fg(theme::palette().accent)
+                .add_modifier(Modifier::BOLD),
+            false => Style::default().fg(theme::palette().text),
+        };
+        let room = width.saturating_sub(count.chars().count() + 2);
+        let label: String = label.chars().take(room).collect();
+        let padding = width.saturating_sub(label.chars().count() + count.chars().count() + 1);
+        ListItem::new(Line::from(vec![
+            Span::styled(format!(" {}", label), style),
+            Span::raw(" ".repeat(padding)),
+            Span::styled(count, dim),
+        ]))
+    };
+
+    let mut items = vec![row(t("sidebar-all"), None, active.is_none())];
+    items.extend(entries.iter().map(|e| {
+        let is_active = active.is_some_and(|a| a.eq_ignore_ascii_case(&e.tag));
+        row(e.tag.clone(), Some(e.open), is_active)
+    }));
+
+    let block = Block::default()
+        .title(format!(" {} ", t("sidebar-title")))
+        .borders(Borders::RIGHT)
+        .border_style(match focused {
+            true => Style::default().fg(theme::palette().accent),
+            false => dim,
+        });
+    let list = List::new(items).block(block);
+    let list = match focused {
+        true => list.highlight_style(theme::selection()),
+        false => list,
+    };
+    let mut state = ListState::default().with_selected(Some(sidebar.selected));
+    f.render_stateful_widget(list, area, &mut state);
+}
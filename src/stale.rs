@@ -0,0 +1,160 @@
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use serde_derive::Deserialize;
+
+use crate::{
+    i18n::{t, t_args},
+    task_item::{Bucket, TaskItem, TaskState},
+    theme,
+};
+
+/// `[stale]` section of the config
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct StaleConfig {
+    /// Days an open task can go untouched before it is stale, 0 never
+    pub days: u32,
+    /// Suggests reviewing the stale tasks when a file is opened
+    pub remind: bool,
+}
+
+impl Default for StaleConfig {
+    fn default() -> Self {
+        Self {
+            days: 30,
+            remind: true,
+        }
+    }
+}
+
+static CONFIG: OnceLock<StaleConfig> = OnceLock::new();
+
+pub fn init(config: StaleConfig) {
+    let _ = CONFIG.set(config);
+}
+
+pub fn config() -> &'static StaleConfig {
+    CONFIG.get_or_init(StaleConfig::default)
+}
+
+/// Whether `task` is open and hasn't been touched for longer than `stale.days`; tasks
+/// without a creation date can't tell, recurring ones come back by themselves and ones
+/// due or scheduled later are planned, and someday ones are meant to wait
+pub fn is_stale(task: &TaskItem, today: NaiveDate) -> bool {
+    let days = config().days;
+    if days == 0 || task.state == TaskState::Done || task.recurrence().is_some() {
+        return false;
+    }
+    if task.bucket() == Some(Bucket::Someday) {
+        return false;
+    }
+    if task.due().is_some_and(|due| due >= today) || task.is_scheduled_later(today) {
+        return false;
+    }
+    untouched(task, today).is_some_and(|untouched| untouched > days as i64)
+}
+
+/// Days since `task` was created or last changed
+fn untouched(task: &TaskItem, today: NaiveDate) -> Option<i64> {
+    task.touched().map(|touched| (today - touched).num_days())
+}
+
+/// What becomes of a task under review
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    /// Still wanted as it is, which makes it fresh again
+    Keep,
+    Tomorrow,
+    NextWeek,
+    Done,
+    Drop,
+    /// Leaves it for the next review
+    Skip,
+}
+
+/// Going through the stale tasks one by one
+#[derive(Clone, Debug)]
+pub struct Review {
+    /// The tasks still to decide on, the first one is shown
+    pending: Vec<TaskItem>,
+    total: usize,
+}
+
+impl Review {
+    pub fn new(tasks: Vec<TaskItem>) -> Self {
+        let total = tasks.len();
+        Self {
+            pending: tasks,
+            total,
+        }
+    }
+
+    pub fn current(&self) -> Option<&TaskItem> {
+        self.pending.first()
+    }
+
+    /// Moves on past the current task
+    pub fn advance(&mut self) {
+        if !self.pending.is_empty() {
+            self.pending.remove(0);
+        }
+    }
+}
+
+pub fn ui(f: &mut Frame, area: Rect, review: &Review) {
+    let Some(task) = review.current() else {
+        return;
+    };
+    let today = chrono::Local::now().date_naive();
+    let number = review.total - review.pending.len() + 1;
+
+    let block = Block::default()
+        .title(format!(
+            " {} ",
+            t_args(
+                "review-title",
+                &[("number", number.into()), ("total", review.total.into())]
+            )
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::palette().accent));
+    let dim = Style::default().fg(Color::DarkGray);
+    let days = untouched(task, today).unwrap_or_default();
+
+    let key = |key: &'static str| Span::styled(key, Style::default().fg(Color::Yellow));
+    let lines = vec![
+        Line::from(task.text.clone()),
+        Line::styled(t_args("review-untouched", &[("days", days.into())]), dim),
+        Line::default(),
+        Line::from(vec![
+            key("k"),
+            Span::raw(format!(" {}  ", t("review-keep"))),
+            key("t"),
+            Span::raw(format!(" {}  ", t("review-tomorrow"))),
+            key("w"),
+            Span::raw(format!(" {}", t("review-next-week"))),
+        ]),
+        Line::from(vec![
+            key("x"),
+            Span::raw(format!(" {}  ", t("review-done"))),
+            key("d"),
+            Span::raw(format!(" {}  ", t("review-drop"))),
+            key("s"),
+            Span::raw(format!(" {}", t("review-skip"))),
+        ]),
+    ];
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: true }).block(block),
+        area,
+    );
+}
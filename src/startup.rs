@@ -0,0 +1,245 @@
+use std::io::ErrorKind;
+
+use color_eyre::eyre::Result;
+use crossterm::event::KeyCode::{self, Char};
+use ratatui::{prelude::*, widgets::*};
+use tokio::fs::{self, OpenOptions};
+use tui_input::Input;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    config::{expand_home, Config},
+    crypt,
+    file::{self, TaskStore},
+    i18n::t,
+    text_input, theme,
+    tui::{self, Event},
+};
+
+/// Files bigger than this are read in the background while the interface is up
+const STREAM_SIZE: u64 = 1 << 20;
+
+/// A todo file ready to be edited
+pub struct Opened {
+    pub store: TaskStore,
+    pub read_only: bool,
+    /// The tasks still have to be read with `file::stream_tasks`
+    pub stream: bool,
+}
+
+/// Why the todo file can't simply be opened
+enum Problem {
+    Missing,
+    Unreadable(String),
+    /// The tasks could be read, but changes couldn't be written back
+    ReadOnly(Opened, String),
+}
+
+enum Choice {
+    Create,
+    Open(String),
+    ReadOnly,
+    Quit,
+}
+
+/// The tasks to edit, asking what to do when `file` is missing or can't be read or written;
+/// `None` when the user quit
+pub async fn open(mut file: String, config: &Config) -> Result<Option<Opened>> {
+    loop {
+        let problem = match check(&file).await {
+            Ok(opened) => return Ok(Some(opened)),
+            Err(problem) => problem,
+        };
+
+        match ask(&file, &problem).await? {
+            Choice::Create => {
+                if let Some(dir) = std::path::Path::new(&file).parent() {
+                    fs::create_dir_all(dir).await?;
+                }
+                file::append_tasks(&file, &[]).await?;
+            }
+            Choice::Open(path) => {
+                file = expand_home(&path);
+                if crypt::is_encrypted(&file) {
+                    crypt::unlock(&file, &config.age).await?;
+                }
+            }
+            Choice::ReadOnly => {
+                let opened = match problem {
+                    Problem::ReadOnly(opened, _) => opened,
+                    _ => Opened {
+                        store: TaskStore::new(file.clone()).await?,
+                        read_only: true,
+                        stream: false,
+                    },
+                };
+                return Ok(Some(Opened {
+                    read_only: true,
+                    ..opened
+                }));
+            }
+            Choice::Quit => return Ok(None),
+        }
+    }
+}
+
+async fn check(file: &str) -> Result<Opened, Problem> {
+    let size = match fs::metadata(file).await {
+        Err(e) if e.kind() == ErrorKind::NotFound => return Err(Problem::Missing),
+        Err(e) => return Err(Problem::Unreadable(e.to_string())),
+        Ok(metadata) => metadata.len(),
+    };
+
+    let opened = match size > STREAM_SIZE {
+        true => {
+            fs::File::open(file)
+                .await
+                .map_err(|e| Problem::Unreadable(e.to_string()))?;
+            Opened {
+                store: TaskStore::unloaded(file.to_owned()),
+                read_only: false,
+                stream: true,
+            }
+        }
+        false => Opened {
+            store: TaskStore::new(file.to_owned())
+                .await
+                .map_err(|e| Problem::Unreadable(format!("{:#}", e)))?,
+            read_only: false,
+            stream: false,
+        },
+    };
+
+    match OpenOptions::new().append(true).open(file).await {
+        Ok(_) => Ok(opened),
+        Err(e) => Err(Problem::ReadOnly(opened, e.to_string())),
+    }
+}
+
+/// Shows the problem with `file` until one of the offered choices is made
+async fn ask(file: &str, problem: &Problem) -> Result<Choice> {
+    let mut tui = tui::Tui::new()?.frame_rate(30.0);
+    tui.enter()?;
+
+    let mut path: Option<Input> = None;
+    let choice = loop {
+        let Some(event) = tui.next().await else {
+            break Choice::Quit;
+        };
+
+        match (event, &mut path) {
+            (Event::Render, _) => {
+                tui.draw(|f| ui(f, file, problem, path.as_ref()))?;
+            }
+            (Event::Key(key, event), Some(input)) => match key.code {
+                KeyCode::Esc => path = None,
+                KeyCode::Enter if !input.value().trim().is_empty() => {
+                    break Choice::Open(input.value().trim().to_owned());
+                }
+                _ => text_input::handle_event(input, &event),
+            },
+            (Event::Key(key, _), None) => match (key.code, problem) {
+                (Char('c'), Problem::Missing) => break Choice::Create,
+                (Char('r'), Problem::Missing | Problem::ReadOnly(..)) => break Choice::ReadOnly,
+                (Char('o'), _) => path = Some(Input::default()),
+                (Char('q') | KeyCode::Esc, _) => break Choice::Quit,
+                _ => {}
+            },
+            _ => {}
+        }
+    };
+
+    tui.exit()?;
+    Ok(choice)
+}
+
+fn ui(f: &mut Frame, file: &str, problem: &Problem, path: Option<&Input>) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let key = Style::default().fg(theme::palette().accent);
+
+    let (summary, error) = match problem {
+        Problem::Missing => (t("startup-missing"), None),
+        Problem::Unreadable(error) => (t("startup-unreadable"), Some(error)),
+        Problem::ReadOnly(_, error) => (t("startup-read-only"), Some(error)),
+    };
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw(file),
+            Span::styled(format!(" {}", summary), dim),
+        ]),
+        Line::default(),
+    ];
+    if let Some(error) = error {
+        lines.push(Line::styled(
+            error.as_str(),
+            Style::default().fg(Color::Red),
+        ));
+        lines.push(Line::default());
+    }
+
+    let mut options = vec![];
+    if let Problem::Missing = problem {
+        options.push(("c", t("startup-create")));
+    }
+    options.push(("o", t("startup-open")));
+    if let Problem::Missing | Problem::ReadOnly(..) = problem {
+        options.push(("r", t("startup-open-read-only")));
+    }
+    options.push(("q", t("startup-quit")));
+
+    for (k, label) in options {
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {} ", k), key),
+            Span::raw(label),
+        ]));
+    }
+
+    // long errors wrap onto extra rows
+    let wrapped = error.map_or(0, |e| e.chars().count() as u16 / 66);
+    let area = centered(f.size(), 70, lines.len() as u16 + 4 + wrapped);
+    let block = Block::default()
+        .title(format!(" {} ", t("startup-title")))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+    f.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block),
+        area,
+    );
+
+    if let Some(input) = path {
+        let line = Rect {
+            y: area.y + area.height - 2,
+            height: 1,
+            x: area.x + 1,
+            width: area.width.saturating_sub(2),
+        };
+        let prompt = format!("{} ", t("startup-path"));
+        let prompt_width = prompt.width();
+        let width = line.width.saturating_sub(prompt_width as u16);
+        let (visible, cursor) = text_input::visible(input, width as usize);
+        f.render_widget(Clear, line);
+        f.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(prompt, key),
+                Span::raw(visible),
+            ])),
+            line,
+        );
+        f.set_cursor(line.x + (prompt_width + cursor) as u16, line.y);
+    }
+}
+
+/// A `width` x `height` rect in the middle of `area`, shrunk to fit
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{Datelike, Duration, Local};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    history::{HistoryEntry, HistoryKind},
+    i18n::t,
+    task_item::{format_duration, TaskItem},
+};
+
+/// Number of `kind` entries within the last `days` days, or ever when `None`
+fn count_since(history: &[HistoryEntry], kind: HistoryKind, days: Option<i64>) -> usize {
+    let since = days.map(|days| Local::now().date_naive() - Duration::days(days));
+
+    history
+        .iter()
+        .filter(|e| e.kind == kind)
+        .filter(|e| since.is_none_or(|since| e.timestamp.date_naive() > since))
+        .count()
+}
+
+fn row(label: &str, width: usize, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!("{:<width$}", label),
+            Style::default().fg(Color::Gray),
+        ),
+        Span::styled(value, Style::default().add_modifier(Modifier::BOLD)),
+    ])
+}
+
+pub fn ui(f: &mut Frame, area: Rect, tasks: &[TaskItem], history: &[HistoryEntry]) {
+    let week_day = Local::now().weekday().num_days_from_monday() as i64;
+
+    let labels = [
+        t("stats-completed-today"),
+        t("stats-completed-week"),
+        t("stats-completed-total"),
+        t("stats-added-total"),
+        t("stats-tracked"),
+    ];
+    let width = (labels.iter().map(|l| l.width() + 2)).fold(20, usize::max);
+
+    let mut lines = vec![
+        row(
+            &labels[0],
+            width,
+            count_since(history, HistoryKind::Complete, Some(1)).to_string(),
+        ),
+        row(
+            &labels[1],
+            width,
+            count_since(history, HistoryKind::Complete, Some(week_day + 1)).to_string(),
+        ),
+        row(
+            &labels[2],
+            width,
+            count_since(history, HistoryKind::Complete, None).to_string(),
+        ),
+        row(
+            &labels[3],
+            width,
+            count_since(history, HistoryKind::Add, None).to_string(),
+        ),
+        Line::default(),
+    ];
+
+    let mut tracked: Vec<&TaskItem> = tasks.iter().filter(|t| t.tracked() > 0).collect();
+    tracked.sort_by_key(|t| -t.tracked());
+
+    let total: i64 = tracked.iter().map(|t| t.tracked()).sum();
+    lines.push(row(&labels[4], width, format_duration(total)));
+
+    for task in tracked.iter().take(5) {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<1$}", format_duration(task.tracked()), width - 2),
+                Style::default().fg(Color::Green),
+            ),
+            Span::raw(task.text.clone()),
+        ]));
+    }
+
+    let block = Block::default()
+        .title(format!(" {} ", t("stats-title")))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::{
+    i18n::{t, t_args},
+    pomodoro::{Phase, Pomodoro},
+    theme,
+};
+
+/// `badges` are labels and colours, the mode first in bold and then e.g. the view
+pub fn ui(
+    f: &mut Frame,
+    area: Rect,
+    badges: &[(String, Color)],
+    pomodoro: Option<&Pomodoro>,
+    read_only: bool,
+    retry_in: Option<Duration>,
+    loading: Option<f64>,
+) {
+    let mut spans: Vec<Span> = (badges.iter().enumerate())
+        .map(|(i, (label, color))| {
+            let style = Style::default().fg(Color::Black).bg(*color);
+            match i {
+                0 => Span::styled(format!(" {} ", label), style.add_modifier(Modifier::BOLD)),
+                _ => Span::styled(format!(" {} ", label), style),
+            }
+        })
+        .collect();
+
+    if let Some(progress) = loading {
+        spans.push(Span::styled(
+            format!(
+                " {} ",
+                t_args(
+                    "status-loading",
+                    &[("percent", (progress * 100.0).round().into())]
+                )
+            ),
+            Style::default().fg(Color::Black).bg(Color::Blue),
+        ));
+    }
+
+    if let Some(retry_in) = retry_in {
+        spans.push(Span::styled(
+            format!(
+                " {} ",
+                t_args("status-unsaved", &[("seconds", retry_in.as_secs().into())])
+            ),
+            Style::default().fg(Color::Black).bg(Color::Red),
+        ));
+    }
+
+    if read_only {
+        spans.push(Span::styled(
+            format!(
+                " {}{} ",
+                theme::glyph(&theme::glyphs().lock),
+                t("status-read-only")
+            ),
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        ));
+    }
+
+    if let Some(pomodoro) = pomodoro {
+        let (label, color) = match pomodoro.phase {
+            Phase::Focus => (t("pomodoro-focus"), Color::Red),
+            Phase::Break => (t("pomodoro-break"), Color::Green),
+        };
+        let remaining = pomodoro.remaining();
+
+        spans.push(Span::styled(
+            format!(
+                " {}{} {:02}:{:02} ",
+                theme::glyph(&theme::glyphs().timer),
+                label,
+                remaining / 60,
+                remaining % 60
+            ),
+            Style::default().fg(Color::Black).bg(color),
+        ));
+        spans.push(Span::styled(
+            format!(" {}", pomodoro.task),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
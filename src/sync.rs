@@ -0,0 +1,484 @@
+pub mod caldav;
+pub mod google;
+pub mod microsoft;
+mod oauth;
+#[cfg(feature = "todoist")]
+pub mod todoist;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use chrono::{Local, NaiveDate};
+use color_eyre::eyre::{eyre, Result};
+use serde_derive::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    dates,
+    file::TaskStore,
+    hash,
+    history::data_dir,
+    task_item::{TaskItem, TaskState},
+};
+use caldav::{CalDav, CalDavConfig};
+
+/// `[sync]` section of the config, the first configured backend is used
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub caldav: Option<CalDavConfig>,
+    pub google: Option<google::GoogleConfig>,
+    pub microsoft: Option<microsoft::MicrosoftConfig>,
+    #[cfg(feature = "todoist")]
+    pub todoist: Option<todoist::TodoistConfig>,
+}
+
+/// The part of a task that is mirrored to a remote service
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RemoteTask {
+    pub uid: String,
+    pub text: String,
+    pub done: bool,
+    pub due: Option<NaiveDate>,
+    pub priority: Option<String>,
+}
+
+impl RemoteTask {
+    pub fn from_task(uid: &str, task: &TaskItem) -> Self {
+        Self {
+            uid: uid.to_owned(),
+            text: task.text.clone(),
+            done: task.state == TaskState::Done,
+            due: task.due(),
+            priority: task.priority().map(|p| p.to_owned()),
+        }
+    }
+
+    pub fn apply_to(&self, task: &mut TaskItem) {
+        task.text = self.text.clone();
+        let state = match self.done {
+            true => TaskState::Done,
+            false => TaskState::Open,
+        };
+        if task.state != state {
+            task.toggle_state();
+        }
+        task.set_meta("due", self.due.map(|d| d.format(dates::FORMAT).to_string()));
+        task.set_meta("pri", self.priority.clone());
+        task.set_meta("uid", Some(self.uid.clone()));
+    }
+
+    pub fn to_task(&self) -> TaskItem {
+        let mut task = TaskItem::new(String::new(), TaskState::Open);
+        self.apply_to(&mut task);
+        task.mark_created(Local::now().date_naive());
+        task
+    }
+}
+
+/// A remote service tasks are synchronized with
+// only used with concrete backends, whose futures are `Send` where needed
+#[allow(async_fn_in_trait)]
+pub trait Backend {
+    /// Whether `list` includes completed tasks; when it doesn't, a task that disappeared
+    /// remotely is taken as completed rather than deleted
+    fn lists_completed(&self) -> bool {
+        true
+    }
+
+    async fn list(&mut self) -> Result<Vec<RemoteTask>>;
+    /// Whether the service has priorities; when it doesn't, local ones are left alone
+    fn supports_priority(&self) -> bool {
+        true
+    }
+
+    /// Creates the task remotely and returns the uid the service assigned
+    async fn create(&mut self, task: &RemoteTask) -> Result<String>;
+    async fn update(&mut self, task: &RemoteTask) -> Result<()>;
+    async fn delete(&mut self, uid: &str) -> Result<()>;
+}
+
+/// A task changed both locally and remotely since the last sync
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    /// How the task looked after the last sync
+    pub base: Option<RemoteTask>,
+    /// The local task before the remote version was applied
+    pub local: TaskItem,
+    pub remote: RemoteTask,
+}
+
+#[derive(Default, Debug)]
+pub struct Report {
+    pub pulled: usize,
+    pub pushed: usize,
+    pub deleted: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Report {
+    pub fn summary(&self) -> String {
+        format!(
+            "pulled {}, pushed {}, deleted {}, conflicts {}",
+            self.pulled,
+            self.pushed,
+            self.deleted,
+            self.conflicts.len()
+        )
+    }
+}
+
+/// What every synced task looked like after the last sync, the base for three-way merges
+#[derive(Default, Serialize, Deserialize)]
+struct SyncState {
+    tasks: BTreeMap<String, RemoteTask>,
+}
+
+fn state_path(backend: &str, file_path: &str) -> PathBuf {
+    let hash = hash::stable(file_path.as_bytes());
+    data_dir()
+        .join("sync")
+        .join(format!("{}-{:016x}.json", backend, hash))
+}
+
+async fn load_state(path: &PathBuf) -> Result<SyncState> {
+    match fs::read_to_string(path).await {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(_) => Ok(SyncState::default()),
+    }
+}
+
+async fn save_state(path: &PathBuf, state: &SyncState) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    fs::write(path, serde_json::to_string(state)?).await?;
+    Ok(())
+}
+
+/// What the backend can see of `task`
+fn snapshot<B: Backend>(backend: &B, uid: &str, task: &TaskItem) -> RemoteTask {
+    let mut remote = RemoteTask::from_task(uid, task);
+    if !backend.supports_priority() {
+        remote.priority = None;
+    }
+    remote
+}
+
+fn apply<B: Backend>(backend: &B, remote: &RemoteTask, task: &mut TaskItem) {
+    let priority = task.priority().map(|p| p.to_owned());
+    remote.apply_to(task);
+    if !backend.supports_priority() {
+        task.set_meta("pri", priority);
+    }
+}
+
+/// Two-way sync of `store` with `backend`; conflicting edits are resolved in favour of the
+/// remote and returned in the report, so the local version can still be chosen afterwards.
+/// When it fails partway, `store` keeps what was synced until then, uids of tasks created
+/// remotely included, and has to be saved all the same.
+pub async fn sync<B: Backend>(
+    name: &str,
+    backend: &mut B,
+    store: &mut TaskStore,
+) -> Result<Report> {
+    let path = state_path(name, store.file_path());
+    let base = load_state(&path).await?;
+    let mut report = Report::default();
+    let mut synced = SyncState::default();
+
+    if let Err(e) = exchange(backend, store, &base, &mut synced, &mut report).await {
+        // or the tasks created remotely so far would be created again by the next sync
+        let mut partial = base;
+        partial.tasks.extend(synced.tasks);
+        save_state(&path, &partial).await?;
+        return Err(e);
+    }
+    save_state(&path, &synced).await?;
+
+    Ok(report)
+}
+
+/// Pushes and pulls the changes since `base`, noting what every task looks like after
+/// in `synced`
+async fn exchange<B: Backend>(
+    backend: &mut B,
+    store: &mut TaskStore,
+    base: &SyncState,
+    synced: &mut SyncState,
+    report: &mut Report,
+) -> Result<()> {
+    let remote: BTreeMap<String, RemoteTask> = backend
+        .list()
+        .await?
+        .into_iter()
+        .map(|t| (t.uid.clone(), t))
+        .collect();
+
+    // tasks that exist locally
+    let mut removed = vec![];
+    for (index, task) in store.items.iter_mut().enumerate() {
+        let Some(uid) = task.get_meta("uid").map(|u| u.to_owned()) else {
+            let mut new = snapshot(&*backend, "", task);
+            new.uid = backend.create(&new).await?;
+            task.set_meta("uid", Some(new.uid.clone()));
+            synced.tasks.insert(new.uid.clone(), new);
+            report.pushed += 1;
+            continue;
+        };
+
+        let local = snapshot(&*backend, &uid, task);
+        let local_changed = base.tasks.get(&uid) != Some(&local);
+
+        match remote.get(&uid) {
+            Some(remote) if *remote == local => {
+                synced.tasks.insert(uid, local);
+            }
+            Some(remote) => {
+                let remote_changed = base.tasks.get(&uid) != Some(remote);
+                if local_changed && remote_changed {
+                    report.conflicts.push(Conflict {
+                        base: base.tasks.get(&uid).cloned(),
+                        local: task.clone(),
+                        remote: remote.clone(),
+                    });
+                }
+
+                if local_changed && !remote_changed {
+                    backend.update(&local).await?;
+                    report.pushed += 1;
+                    synced.tasks.insert(uid, local);
+                } else {
+                    apply(&*backend, remote, task);
+                    report.pulled += 1;
+                    synced.tasks.insert(uid, remote.clone());
+                }
+            }
+            None if base.tasks.contains_key(&uid) && !backend.lists_completed() => {
+                if local_changed {
+                    backend.update(&snapshot(&*backend, &uid, task)).await?;
+                    report.pushed += 1;
+                } else if task.state == TaskState::Open {
+                    task.toggle_state();
+                    report.pulled += 1;
+                }
+                synced
+                    .tasks
+                    .insert(uid.clone(), snapshot(&*backend, &uid, task));
+            }
+            // deleted remotely, keep it only if it was edited here in the meantime
+            None if base.tasks.contains_key(&uid) && !local_changed => {
+                removed.push(index);
+                report.deleted += 1;
+            }
+            None => {
+                let mut new = local;
+                new.uid = backend.create(&new).await?;
+                task.set_meta("uid", Some(new.uid.clone()));
+                synced.tasks.insert(new.uid.clone(), new);
+                report.pushed += 1;
+            }
+        }
+    }
+
+    for index in removed.into_iter().rev() {
+        store.items.remove(index);
+    }
+
+    // tasks that only exist remotely
+    for (uid, task) in remote {
+        if synced.tasks.contains_key(&uid) {
+            continue;
+        }
+
+        if base.tasks.contains_key(&uid) {
+            // deleted locally since the last sync
+            backend.delete(&uid).await?;
+            report.deleted += 1;
+        } else {
+            store.items.push(task.to_task());
+            synced.tasks.insert(uid, task);
+            report.pulled += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// The tasks after a sync that started from `before` and ended with `synced`, keeping what
+/// changed in `current` meanwhile: tasks edited, added or removed since the sync started
+/// stay as they are here and are pushed with the next one, the others are taken as the sync
+/// left them. Tasks are told apart by their id.
+pub fn merge(before: &[TaskItem], synced: &[TaskItem], current: &[TaskItem]) -> Vec<TaskItem> {
+    let by_id = |tasks: &[TaskItem]| -> HashMap<String, TaskItem> {
+        (tasks.iter())
+            .filter_map(|t| Some((t.id()?.to_owned(), t.clone())))
+            .collect()
+    };
+    let (before_ids, synced_ids) = (by_id(before), by_id(synced));
+
+    let mut merged = vec![];
+    for task in current {
+        let Some(id) = task.id() else {
+            merged.push(task.clone());
+            continue;
+        };
+        match (before_ids.get(id), synced_ids.get(id)) {
+            // untouched here, so as the sync left it, or gone when it was deleted remotely
+            (Some(old), synced) if old == task => merged.extend(synced.cloned()),
+            // the uid it was created remotely with, or it would be created again
+            (_, Some(synced)) if task.get_meta("uid").is_none() => {
+                let mut task = task.clone();
+                task.set_meta("uid", synced.get_meta("uid").map(str::to_owned));
+                merged.push(task);
+            }
+            _ => merged.push(task.clone()),
+        }
+    }
+
+    // the ones pulled in, at the end like the sync puts them
+    let pulled = synced
+        .iter()
+        .filter(|t| t.id().is_some_and(|id| !before_ids.contains_key(id)));
+    merged.extend(pulled.cloned());
+    merged
+}
+
+/// Syncs with whichever backend is configured; `interactive` allows sign-in prompts
+pub async fn sync_configured(
+    config: &SyncConfig,
+    store: &mut TaskStore,
+    interactive: bool,
+) -> Result<Report> {
+    if let Some(caldav) = &config.caldav {
+        return sync("caldav", &mut CalDav::new(caldav.clone()), store).await;
+    }
+
+    if let Some(google) = &config.google {
+        let mut backend =
+            google::GoogleTasks::new(google.clone(), store.file_path(), interactive).await?;
+        return sync("google", &mut backend, store).await;
+    }
+
+    if let Some(microsoft) = &config.microsoft {
+        let mut backend = microsoft::MicrosoftToDo::new(microsoft.clone(), interactive).await?;
+        return sync("microsoft", &mut backend, store).await;
+    }
+
+    #[cfg(feature = "todoist")]
+    if let Some(todoist) = &config.todoist {
+        return sync(
+            "todoist",
+            &mut todoist::Todoist::new(todoist.clone())?,
+            store,
+        )
+        .await;
+    }
+
+    Err(eyre!("no sync backend configured"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(text: &str, id: &str) -> TaskItem {
+        let mut task = TaskItem::new(text.to_owned(), TaskState::Open);
+        task.set_meta("id", Some(id.to_owned()));
+        task
+    }
+
+    fn texts(tasks: &[TaskItem]) -> Vec<&str> {
+        tasks.iter().map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn merge_takes_the_sync_where_nothing_changed_meanwhile() {
+        let before = vec![task("a", "1"), task("b", "2"), task("c", "3")];
+        let mut synced = vec![task("a pulled", "1"), task("c", "3"), task("d", "4")];
+        synced[1].set_meta("uid", Some("remote-c".to_owned()));
+
+        let merged = merge(&before, &synced, &before);
+        assert_eq!(texts(&merged), ["a pulled", "c", "d"]);
+        assert_eq!(merged[1].get_meta("uid"), Some("remote-c"));
+    }
+
+    #[test]
+    fn merge_keeps_edits_made_during_the_sync() {
+        let before = vec![task("a", "1"), task("b", "2"), task("c", "3")];
+        let mut synced = before.clone();
+        synced[0].text = "a pulled".to_owned();
+        synced[1].set_meta("uid", Some("remote-b".to_owned()));
+
+        // a edited, b toggled, c deleted and e added while the sync ran
+        let mut current = vec![task("a local", "1"), task("b", "2"), task("e", "5")];
+        current[1].toggle_state();
+
+        let merged = merge(&before, &synced, &current);
+        assert_eq!(texts(&merged), ["a local", "b", "e"]);
+        assert_eq!(merged[1].state, TaskState::Done);
+        assert_eq!(merged[1].get_meta("uid"), Some("remote-b"));
+    }
+
+    /// Keeps tasks in memory, failing to create one once `fail_at` were created
+    struct Flaky {
+        tasks: Vec<RemoteTask>,
+        fail_at: Option<usize>,
+    }
+
+    impl Backend for Flaky {
+        async fn list(&mut self) -> Result<Vec<RemoteTask>> {
+            Ok(self.tasks.clone())
+        }
+
+        async fn create(&mut self, task: &RemoteTask) -> Result<String> {
+            if self.fail_at == Some(self.tasks.len()) {
+                return Err(eyre!("offline"));
+            }
+            let uid = format!("remote-{}", self.tasks.len());
+            self.tasks.push(RemoteTask {
+                uid: uid.clone(),
+                ..task.clone()
+            });
+            Ok(uid)
+        }
+
+        async fn update(&mut self, _: &RemoteTask) -> Result<()> {
+            Ok(())
+        }
+
+        async fn delete(&mut self, _: &str) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn tasks_created_before_a_failure_are_not_created_again() {
+        let dir = std::env::temp_dir().join(format!("tuitodo-sync-{}", std::process::id()));
+        let mut store = TaskStore::unloaded(dir.join("todo.md").to_string_lossy().into_owned());
+        store.items = vec![task("a", "1"), task("b", "2"), task("c", "3")];
+        let mut backend = Flaky {
+            tasks: vec![],
+            fail_at: Some(1),
+        };
+
+        let name = format!("flaky-{}", std::process::id());
+        assert!(sync(&name, &mut backend, &mut store).await.is_err());
+        assert_eq!(store.items[0].get_meta("uid"), Some("remote-0"));
+        assert_eq!(store.items[1].get_meta("uid"), None);
+        let path = state_path(&name, store.file_path());
+        let state = load_state(&path).await.unwrap();
+        assert!(state.tasks.contains_key("remote-0"));
+
+        backend.fail_at = None;
+        let report = sync(&name, &mut backend, &mut store).await.unwrap();
+        assert_eq!(report.pushed, 2);
+        assert_eq!(texts(&store.items), ["a", "b", "c"]);
+        let remote: Vec<&str> = backend.tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(remote, ["a", "b", "c"]);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
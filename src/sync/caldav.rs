@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{eyre, Result};
+use reqwest::{header, Client, Method, StatusCode};
+use serde_derive::Deserialize;
+
+use super::{Backend, RemoteTask};
+use crate::{export, import::parse_ics, secrets};
+
+/// A VTODO collection on a CalDAV server such as Nextcloud or Radicale
+#[derive(Clone, Deserialize)]
+pub struct CalDavConfig {
+    /// URL of the task list collection, ending in `/`
+    pub url: String,
+    pub username: String,
+    /// Taken from the keyring when missing, see `tuitodo auth caldav`
+    pub password: Option<String>,
+}
+
+pub struct CalDav {
+    config: CalDavConfig,
+    client: Client,
+    /// Resource URL of every task seen by `list`
+    hrefs: HashMap<String, String>,
+}
+
+const QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+
+impl CalDav {
+    pub fn new(mut config: CalDavConfig) -> Self {
+        if config.password.is_none() {
+            config.password = secrets::get("caldav");
+        }
+
+        Self {
+            config,
+            client: Client::new(),
+            hrefs: HashMap::new(),
+        }
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, url)
+            .basic_auth(&self.config.username, self.config.password.as_ref())
+    }
+
+    /// Resolves a server-relative href against the collection URL
+    fn absolute(&self, href: &str) -> String {
+        if href.starts_with("http") {
+            return href.to_owned();
+        }
+
+        let origin_end = self
+            .config
+            .url
+            .find("://")
+            .and_then(|i| self.config.url[i + 3..].find('/').map(|j| i + 3 + j))
+            .unwrap_or(self.config.url.len());
+        format!("{}{}", &self.config.url[..origin_end], href)
+    }
+
+    async fn put(&self, url: &str, task: &RemoteTask, create: bool) -> Result<()> {
+        let item = task.to_task();
+        let body = export::ics(&vec![(1, &item)]);
+
+        let mut request = self
+            .request(Method::PUT, url)
+            .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(body);
+        if create {
+            request = request.header(header::IF_NONE_MATCH, "*");
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(eyre!("CalDAV PUT {} failed: {}", url, response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+impl Backend for CalDav {
+    async fn list(&mut self) -> Result<Vec<RemoteTask>> {
+        let response = self
+            .request(Method::from_bytes(b"REPORT")?, &self.config.url)
+            .header("Depth", "1")
+            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(QUERY)
+            .send()
+            .await?;
+        if response.status() != StatusCode::MULTI_STATUS {
+            return Err(eyre!("CalDAV REPORT failed: {}", response.status()));
+        }
+
+        let body = response.text().await?;
+        let mut tasks = vec![];
+        self.hrefs.clear();
+
+        for entry in elements(&body, "response") {
+            let Some(href) = elements(entry, "href").first().map(|h| unescape_xml(h)) else {
+                continue;
+            };
+            let Some(data) = elements(entry, "calendar-data")
+                .first()
+                .map(|d| unescape_xml(d))
+            else {
+                continue;
+            };
+
+            for task in parse_ics(&data) {
+                let Some(uid) = task.get_meta("uid") else {
+                    continue;
+                };
+                self.hrefs
+                    .insert(uid.to_owned(), self.absolute(href.trim()));
+                tasks.push(RemoteTask::from_task(uid, &task));
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    async fn create(&mut self, task: &RemoteTask) -> Result<String> {
+        let uid = uuid::Uuid::new_v4().to_string();
+        let task = RemoteTask {
+            uid: uid.clone(),
+            ..task.clone()
+        };
+
+        let url = format!("{}{}.ics", self.config.url, uid);
+        self.put(&url, &task, true).await?;
+        self.hrefs.insert(uid.clone(), url);
+
+        Ok(uid)
+    }
+
+    async fn update(&mut self, task: &RemoteTask) -> Result<()> {
+        let url = match self.hrefs.get(&task.uid) {
+            Some(url) => url.clone(),
+            None => format!("{}{}.ics", self.config.url, task.uid),
+        };
+        self.put(&url, task, false).await
+    }
+
+    async fn delete(&mut self, uid: &str) -> Result<()> {
+        let Some(url) = self.hrefs.get(uid) else {
+            return Ok(());
+        };
+
+        let response = self.request(Method::DELETE, url).send().await?;
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(eyre!("CalDAV DELETE {} failed: {}", url, response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Contents of every `<ns:name>` element, whatever the namespace prefix
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = vec![];
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let tag_end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..tag_end];
+        let tag_name = tag.split_whitespace().next().unwrap_or_default();
+        let local = tag_name.rsplit(':').next().unwrap_or_default();
+
+        if local != name || tag.ends_with('/') {
+            continue;
+        }
+
+        let content = &rest[(tag_end + 1).min(rest.len())..];
+        let close = format!("</{}>", tag_name);
+        if let Some(end) = content.find(&close) {
+            found.push(&content[..end]);
+            rest = &content[end + close.len()..];
+        }
+    }
+
+    found
+}
+
+fn unescape_xml(text: &str) -> String {
+    let text = text
+        .trim()
+        .trim_start_matches("<![CDATA[")
+        .trim_end_matches("]]>");
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&amp;", "&")
+}
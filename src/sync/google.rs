@@ -0,0 +1,176 @@
+use chrono::DateTime;
+use color_eyre::eyre::Result;
+use reqwest::Client;
+use serde_derive::Deserialize;
+use serde_json::json;
+
+use super::{oauth::DeviceFlow, Backend, RemoteTask};
+
+const API: &str = "https://tasks.googleapis.com/tasks/v1";
+const DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const SCOPE: &str = "https://www.googleapis.com/auth/tasks";
+
+#[derive(Clone, Deserialize)]
+pub struct GoogleConfig {
+    /// OAuth client of type "TVs and Limited Input devices"
+    pub client_id: String,
+    pub client_secret: String,
+    /// Which Google task list each todo file mirrors, `@default` for files not listed
+    #[serde(default)]
+    pub lists: Vec<ListMapping>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct ListMapping {
+    pub file: String,
+    pub tasklist: String,
+}
+
+pub struct GoogleTasks {
+    tasklist: String,
+    access_token: String,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskPage {
+    #[serde(default)]
+    items: Vec<Task>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Task {
+    id: String,
+    title: String,
+    status: String,
+    due: Option<String>,
+    #[serde(default)]
+    deleted: bool,
+}
+
+impl GoogleTasks {
+    pub async fn new(config: GoogleConfig, file_path: &str, interactive: bool) -> Result<Self> {
+        let client = Client::new();
+        let tasklist = config
+            .lists
+            .iter()
+            .find(|l| l.file == file_path)
+            .map(|l| l.tasklist.clone())
+            .unwrap_or_else(|| "@default".to_owned());
+
+        let flow = DeviceFlow {
+            name: "google",
+            device_code_url: DEVICE_CODE_URL,
+            token_url: TOKEN_URL,
+            client_id: &config.client_id,
+            client_secret: Some(&config.client_secret),
+            scope: SCOPE,
+        };
+        let access_token = flow.access_token(&client, interactive).await?;
+
+        Ok(Self {
+            tasklist,
+            access_token,
+            client,
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/lists/{}/tasks{}", API, self.tasklist, path)
+    }
+
+    fn body(task: &RemoteTask) -> serde_json::Value {
+        json!({
+            "title": task.text,
+            "status": if task.done { "completed" } else { "needsAction" },
+            // Google keeps only the date part of due
+            "due": task.due.map(|d| format!("{}T00:00:00.000Z", d.format("%Y-%m-%d"))),
+        })
+    }
+}
+
+impl Backend for GoogleTasks {
+    fn supports_priority(&self) -> bool {
+        false
+    }
+
+    async fn list(&mut self) -> Result<Vec<RemoteTask>> {
+        let mut tasks = vec![];
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut request = self
+                .client
+                .get(self.url(""))
+                .bearer_auth(&self.access_token)
+                .query(&[
+                    ("showCompleted", "true"),
+                    ("showHidden", "true"),
+                    ("maxResults", "100"),
+                ]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+
+            let page: TaskPage = request.send().await?.error_for_status()?.json().await?;
+            tasks.extend(page.items.into_iter().filter(|t| !t.deleted).map(|t| {
+                RemoteTask {
+                    uid: t.id,
+                    text: t.title,
+                    done: t.status == "completed",
+                    due: t
+                        .due
+                        .and_then(|d| DateTime::parse_from_rfc3339(&d).ok())
+                        .map(|d| d.date_naive()),
+                    priority: None,
+                }
+            }));
+
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        Ok(tasks)
+    }
+
+    async fn create(&mut self, task: &RemoteTask) -> Result<String> {
+        let created: Task = self
+            .client
+            .post(self.url(""))
+            .bearer_auth(&self.access_token)
+            .json(&Self::body(task))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(created.id)
+    }
+
+    async fn update(&mut self, task: &RemoteTask) -> Result<()> {
+        self.client
+            .patch(self.url(&format!("/{}", task.uid)))
+            .bearer_auth(&self.access_token)
+            .json(&Self::body(task))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, uid: &str) -> Result<()> {
+        self.client
+            .delete(self.url(&format!("/{}", uid)))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::NaiveDate;
+use color_eyre::eyre::Result;
+use reqwest::Client;
+use serde_derive::Deserialize;
+use serde_json::json;
+
+use super::{oauth::DeviceFlow, Backend, RemoteTask};
+
+const API: &str = "https://graph.microsoft.com/v1.0/me/todo/lists";
+const SCOPE: &str = "Tasks.ReadWrite offline_access";
+
+#[derive(Clone, Deserialize)]
+pub struct MicrosoftConfig {
+    /// Application (client) ID of an app registration allowing public client flows
+    pub client_id: String,
+    /// `common`, `organizations` or a tenant ID
+    #[serde(default = "default_tenant")]
+    pub tenant: String,
+    /// ID of the To Do list to mirror
+    pub list_id: String,
+}
+
+fn default_tenant() -> String {
+    "common".to_owned()
+}
+
+pub struct MicrosoftToDo {
+    list_id: String,
+    access_token: String,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct TaskPage {
+    value: Vec<Task>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Task {
+    id: String,
+    title: String,
+    status: String,
+    importance: String,
+    due_date_time: Option<DateTimeTimeZone>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DateTimeTimeZone {
+    date_time: String,
+}
+
+impl MicrosoftToDo {
+    pub async fn new(config: MicrosoftConfig, interactive: bool) -> Result<Self> {
+        let client = Client::new();
+        let device_code_url = format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/devicecode",
+            config.tenant
+        );
+        let token_url = format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+            config.tenant
+        );
+
+        let flow = DeviceFlow {
+            name: "microsoft",
+            device_code_url: &device_code_url,
+            token_url: &token_url,
+            client_id: &config.client_id,
+            client_secret: None,
+            scope: SCOPE,
+        };
+        let access_token = flow.access_token(&client, interactive).await?;
+
+        Ok(Self {
+            list_id: config.list_id,
+            access_token,
+            client,
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}/tasks{}", API, self.list_id, path)
+    }
+
+    fn body(task: &RemoteTask) -> serde_json::Value {
+        let importance = match task.priority.as_deref() {
+            Some("A") => "high",
+            Some("C") => "low",
+            _ => "normal",
+        };
+
+        json!({
+            "title": task.text,
+            "status": if task.done { "completed" } else { "notStarted" },
+            "importance": importance,
+            "dueDateTime": task.due.map(|d| json!({
+                "dateTime": format!("{}T00:00:00", d.format("%Y-%m-%d")),
+                "timeZone": "UTC",
+            })),
+        })
+    }
+}
+
+impl Backend for MicrosoftToDo {
+    async fn list(&mut self) -> Result<Vec<RemoteTask>> {
+        let mut tasks = vec![];
+        let mut url = Some(self.url(""));
+
+        while let Some(next) = url {
+            let page: TaskPage = self
+                .client
+                .get(next)
+                .bearer_auth(&self.access_token)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            tasks.extend(page.value.into_iter().map(|t| RemoteTask {
+                uid: t.id,
+                text: t.title,
+                done: t.status == "completed",
+                due: t.due_date_time.and_then(|d| {
+                    NaiveDate::parse_from_str(d.date_time.get(..10)?, "%Y-%m-%d").ok()
+                }),
+                priority: match t.importance.as_str() {
+                    "high" => Some("A".to_owned()),
+                    "low" => Some("C".to_owned()),
+                    _ => None,
+                },
+            }));
+            url = page.next_link;
+        }
+
+        Ok(tasks)
+    }
+
+    async fn create(&mut self, task: &RemoteTask) -> Result<String> {
+        let created: Task = self
+            .client
+            .post(self.url(""))
+            .bearer_auth(&self.access_token)
+            .json(&Self::body(task))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(created.id)
+    }
+
+    async fn update(&mut self, task: &RemoteTask) -> Result<()> {
+        self.client
+            .patch(self.url(&format!("/{}", task.uid)))
+            .bearer_auth(&self.access_token)
+            .json(&Self::body(task))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, uid: &str) -> Result<()> {
+        self.client
+            .delete(self.url(&format!("/{}", uid)))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::{eyre, Result};
+use reqwest::Client;
+use serde_derive::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::history::data_dir;
+
+/// OAuth 2.0 device authorization grant, for services that need a browser sign-in
+pub struct DeviceFlow<'a> {
+    /// Names the file the refresh token is kept in
+    pub name: &'a str,
+    pub device_code_url: &'a str,
+    pub token_url: &'a str,
+    pub client_id: &'a str,
+    pub client_secret: Option<&'a str>,
+    pub scope: &'a str,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredToken {
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    #[serde(alias = "verification_uri")]
+    verification_url: String,
+    interval: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    error: Option<String>,
+}
+
+impl DeviceFlow<'_> {
+    /// An access token from the stored refresh token, or a new sign-in when `interactive`
+    pub async fn access_token(&self, client: &Client, interactive: bool) -> Result<String> {
+        match fs::read_to_string(self.token_path()).await {
+            Ok(stored) => {
+                let stored: StoredToken = serde_json::from_str(&stored)?;
+                self.refresh(client, &stored.refresh_token).await
+            }
+            Err(_) if interactive => self.authorize(client).await,
+            Err(_) => Err(eyre!(
+                "not signed in to {}, run `tuitodo sync` once",
+                self.name
+            )),
+        }
+    }
+
+    fn token_path(&self) -> PathBuf {
+        data_dir().join(format!("{}-token.json", self.name))
+    }
+
+    fn form<'b>(&'b self, extra: &[(&'b str, &'b str)]) -> Vec<(&'b str, &'b str)> {
+        let mut form = vec![("client_id", self.client_id), ("scope", self.scope)];
+        if let Some(secret) = self.client_secret {
+            form.push(("client_secret", secret));
+        }
+        form.extend_from_slice(extra);
+        form
+    }
+
+    async fn store(&self, response: &TokenResponse) -> Result<()> {
+        if let Some(refresh_token) = &response.refresh_token {
+            let stored = serde_json::to_string(&StoredToken {
+                refresh_token: refresh_token.clone(),
+            })?;
+            fs::create_dir_all(data_dir()).await?;
+            fs::write(self.token_path(), stored).await?;
+        }
+        Ok(())
+    }
+
+    async fn refresh(&self, client: &Client, refresh_token: &str) -> Result<String> {
+        let form = self.form(&[
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+        ]);
+        let response: TokenResponse = client
+            .post(self.token_url)
+            .form(&form)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        // some services rotate refresh tokens
+        self.store(&response).await?;
+        response
+            .access_token
+            .ok_or_else(|| eyre!("{} token refresh failed: {:?}", self.name, response.error))
+    }
+
+    /// The user confirms a code in the browser while we poll for the token
+    async fn authorize(&self, client: &Client) -> Result<String> {
+        let code: DeviceCode = client
+            .post(self.device_code_url)
+            .form(&self.form(&[]))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        eprintln!(
+            "Open {} and enter the code {}",
+            code.verification_url, code.user_code
+        );
+
+        let form = self.form(&[
+            ("device_code", code.device_code.as_str()),
+            ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+        ]);
+
+        let mut interval = code.interval;
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+
+            let response: TokenResponse = client
+                .post(self.token_url)
+                .form(&form)
+                .send()
+                .await?
+                .json()
+                .await?;
+
+            match (&response.access_token, response.error.as_deref()) {
+                (Some(access_token), _) => {
+                    self.store(&response).await?;
+                    return Ok(access_token.clone());
+                }
+                (None, Some("authorization_pending")) => continue,
+                // polling too often, every later poll waits 5 more seconds (RFC 8628 3.5)
+                (None, Some("slow_down")) => interval += 5,
+                (None, error) => return Err(eyre!("{} sign in failed: {:?}", self.name, error)),
+            }
+        }
+    }
+}
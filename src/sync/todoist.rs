@@ -0,0 +1,167 @@
+use chrono::NaiveDate;
+use color_eyre::eyre::{eyre, Result};
+use reqwest::{Client, RequestBuilder};
+use serde_derive::Deserialize;
+use serde_json::json;
+
+use super::{Backend, RemoteTask};
+use crate::{dates, secrets};
+
+const API: &str = "https://api.todoist.com/rest/v2";
+
+/// A Todoist project mirrored into the todo file
+#[derive(Clone, Deserialize)]
+pub struct TodoistConfig {
+    pub project_id: String,
+    /// API token, the keyring or `$TODOIST_TOKEN` are used when missing
+    pub token: Option<String>,
+}
+
+pub struct Todoist {
+    project_id: String,
+    token: String,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct Task {
+    id: String,
+    content: String,
+    is_completed: bool,
+    priority: u8,
+    due: Option<Due>,
+}
+
+#[derive(Deserialize)]
+struct Due {
+    date: String,
+}
+
+impl Todoist {
+    pub fn new(config: TodoistConfig) -> Result<Self> {
+        let token = config
+            .token
+            .or_else(|| secrets::get("todoist"))
+            .or_else(|| std::env::var("TODOIST_TOKEN").ok())
+            .ok_or_else(|| eyre!("no Todoist token, run `tuitodo auth todoist`"))?;
+
+        Ok(Self {
+            project_id: config.project_id,
+            token,
+            client: Client::new(),
+        })
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.client
+            .post(format!("{}{}", API, path))
+            .bearer_auth(&self.token)
+    }
+
+    fn body(&self, task: &RemoteTask) -> serde_json::Value {
+        json!({
+            "content": task.text,
+            "project_id": self.project_id,
+            "priority": to_todoist_priority(task.priority.as_deref()),
+            "due_date": task.due.map(|d| d.format(dates::FORMAT).to_string()),
+            "due_string": if task.due.is_none() { Some("no date") } else { None },
+        })
+    }
+
+    async fn set_done(&self, id: &str, done: bool) -> Result<()> {
+        let action = if done { "close" } else { "reopen" };
+        self.post(&format!("/tasks/{}/{}", id, action))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Todoist counts from 4 (urgent) down to 1 (none)
+fn to_todoist_priority(priority: Option<&str>) -> u8 {
+    match priority {
+        Some("A") => 4,
+        Some("B") => 3,
+        Some("C") => 2,
+        _ => 1,
+    }
+}
+
+fn from_todoist_priority(priority: u8) -> Option<String> {
+    match priority {
+        4 => Some("A".to_owned()),
+        3 => Some("B".to_owned()),
+        2 => Some("C".to_owned()),
+        _ => None,
+    }
+}
+
+impl Backend for Todoist {
+    fn lists_completed(&self) -> bool {
+        false
+    }
+
+    async fn list(&mut self) -> Result<Vec<RemoteTask>> {
+        let tasks: Vec<Task> = self
+            .client
+            .get(format!("{}/tasks", API))
+            .query(&[("project_id", &self.project_id)])
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(tasks
+            .into_iter()
+            .map(|t| RemoteTask {
+                uid: t.id,
+                text: t.content,
+                done: t.is_completed,
+                due: t
+                    .due
+                    .and_then(|d| NaiveDate::parse_from_str(&d.date, dates::FORMAT).ok()),
+                priority: from_todoist_priority(t.priority),
+            })
+            .collect())
+    }
+
+    async fn create(&mut self, task: &RemoteTask) -> Result<String> {
+        let created: Task = self
+            .post("/tasks")
+            .json(&self.body(task))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if task.done {
+            self.set_done(&created.id, true).await?;
+        }
+
+        Ok(created.id)
+    }
+
+    async fn update(&mut self, task: &RemoteTask) -> Result<()> {
+        self.post(&format!("/tasks/{}", task.uid))
+            .json(&self.body(task))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        self.set_done(&task.uid, task.done).await
+    }
+
+    async fn delete(&mut self, uid: &str) -> Result<()> {
+        self.client
+            .delete(format!("{}/tasks/{}", API, uid))
+            .bearer_auth(&self.token)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
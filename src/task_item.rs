@@ -1,24 +1,490 @@
-#[derive(Clone)]
+use std::collections::HashSet;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+
+use crate::{dates, file::format_task, hash::StableHasher, recurrence::Recurrence};
+
+/// How many completion dates a recurring task remembers
+const COMPLETION_LIMIT: usize = 60;
+/// Hex digits of a task id, enough to tell the tasks of any list apart
+const ID_LENGTH: usize = 8;
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum TaskState {
     Done,
     Open,
 }
 
-#[derive(Clone)]
+/// Where a task waits outside the tasks being worked on, the lists of GTD
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bucket {
+    /// Someone else has to do something first
+    Waiting,
+    /// Maybe one day, not now
+    Someday,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct TaskItem {
     pub state: TaskState,
     pub text: String,
+    /// Nesting level, from the indentation in the file
+    pub depth: usize,
+    /// `key:value` pairs stored after the task text, in file order
+    pub meta: Vec<(String, String)>,
+    /// Lines indented below the task that aren't tasks themselves, a longer description
+    pub notes: Vec<String>,
 }
 
 impl TaskItem {
     pub fn new(text: String, state: TaskState) -> Self {
-        Self { text, state }
+        Self {
+            text,
+            state,
+            depth: 0,
+            meta: vec![],
+            notes: vec![],
+        }
     }
 
     pub fn toggle_state(&mut self) {
         self.state = match self.state {
             TaskState::Open => TaskState::Done,
             TaskState::Done => TaskState::Open,
+        };
+
+        let completed = match self.state {
+            TaskState::Done => Some(Local::now().format(dates::FORMAT).to_string()),
+            TaskState::Open => None,
+        };
+        self.set_meta("completed", completed);
+    }
+
+    pub fn completed(&self) -> Option<NaiveDate> {
+        self.get_meta("completed")
+            .and_then(|s| NaiveDate::parse_from_str(s, dates::FORMAT).ok())
+    }
+
+    pub fn get_meta(&self, key: &str) -> Option<&str> {
+        self.meta
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_meta(&mut self, key: &str, value: Option<String>) {
+        let index = self.meta.iter().position(|(k, _)| k == key);
+
+        match (index, value) {
+            (Some(i), Some(value)) => self.meta[i].1 = value,
+            (None, Some(value)) => self.meta.push((key.to_owned(), value)),
+            (Some(i), None) => {
+                self.meta.remove(i);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// `#tags` and `@contexts` mentioned in the text
+    pub fn tags(&self) -> Vec<&str> {
+        self.text
+            .split_whitespace()
+            .filter(|w| w.len() > 1 && (w.starts_with('#') || w.starts_with('@')))
+            .collect()
+    }
+
+    /// The first `+project` mentioned in the text, as in todo.txt
+    pub fn project(&self) -> Option<&str> {
+        self.text
+            .split_whitespace()
+            .find(|w| w.len() > 1 && w.starts_with('+'))
+            .map(|w| &w[1..])
+    }
+
+    pub fn priority(&self) -> Option<&str> {
+        self.get_meta("pri")
+    }
+
+    /// Moves the priority one of `levels` up or down, from the highest ones first; up from
+    /// none is the lowest level and down from the lowest is none
+    pub fn bump_priority(&mut self, levels: &[&str], up: bool) {
+        let current = self
+            .priority()
+            .and_then(|p| levels.iter().position(|l| *l == p));
+        let next = match (current, up) {
+            (None, true) => levels.len().checked_sub(1),
+            (None, false) => None,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) => Some(i + 1).filter(|&i| i < levels.len()),
+        };
+        self.set_meta("pri", next.map(|i| levels[i].to_owned()));
+    }
+
+    pub fn due(&self) -> Option<NaiveDate> {
+        self.get_meta("due")
+            .and_then(|s| NaiveDate::parse_from_str(s, dates::FORMAT).ok())
+    }
+
+    /// The day to start on, from `scheduled:`; the task isn't listed before that
+    pub fn scheduled(&self) -> Option<NaiveDate> {
+        self.get_meta("scheduled")
+            .and_then(|s| NaiveDate::parse_from_str(s, dates::FORMAT).ok())
+    }
+
+    /// The bucket the task was moved to, from `bucket:`; tasks without one are active
+    pub fn bucket(&self) -> Option<Bucket> {
+        match self.get_meta("bucket")? {
+            "waiting" => Some(Bucket::Waiting),
+            "someday" => Some(Bucket::Someday),
+            _ => None,
+        }
+    }
+
+    pub fn set_bucket(&mut self, bucket: Option<Bucket>) {
+        self.set_meta("bucket", bucket.map(|b| b.name().to_owned()));
+    }
+
+    /// Whether the task is open and scheduled to start after `today`
+    pub fn is_scheduled_later(&self, today: NaiveDate) -> bool {
+        self.state == TaskState::Open && self.scheduled().is_some_and(|s| s > today)
+    }
+
+    /// Time of day to be reminded at, from `time:HH:MM`
+    pub fn time(&self) -> Option<NaiveTime> {
+        self.get_meta("time")
+            .and_then(|s| NaiveTime::parse_from_str(s, "%H:%M").ok())
+    }
+
+    pub fn created(&self) -> Option<NaiveDate> {
+        self.get_meta("created")
+            .and_then(|s| NaiveDate::parse_from_str(s, dates::FORMAT).ok())
+    }
+
+    /// Stamps a newly added task with the day it was created and an id, unless it already
+    /// has them
+    pub fn mark_created(&mut self, today: NaiveDate) {
+        if self.created().is_none() {
+            self.set_meta("created", Some(today.format(dates::FORMAT).to_string()));
+        }
+        if self.id().is_none() {
+            self.set_meta("id", Some(new_id()));
         }
     }
+
+    /// The short id that stays with the task through edits and moves, unlike its position or
+    /// text; `uid` is the one a sync service or calendar knows it by
+    pub fn id(&self) -> Option<&str> {
+        self.get_meta("id")
+    }
+
+    /// Notes that the task was changed by hand today, so it isn't stale
+    pub fn touch(&mut self, today: NaiveDate) {
+        self.set_meta("updated", Some(today.format(dates::FORMAT).to_string()));
+    }
+
+    /// The last day the task was created, changed by hand, worked on or completed
+    pub fn touched(&self) -> Option<NaiveDate> {
+        let updated = self
+            .get_meta("updated")
+            .and_then(|s| NaiveDate::parse_from_str(s, dates::FORMAT).ok());
+        let started = self.started().map(|s| s.date_naive());
+        [self.created(), updated, self.completed(), started]
+            .into_iter()
+            .flatten()
+            .max()
+    }
+
+    /// Turns relative dates typed by the user (`due:tomorrow`) into absolute ones
+    pub fn resolve_dates(&mut self, today: NaiveDate) {
+        for key in ["due", "scheduled"] {
+            let resolved = self
+                .get_meta(key)
+                .and_then(|s| dates::parse(s, today))
+                .map(|d| d.format(dates::FORMAT).to_string());
+
+            if resolved.is_some() {
+                self.set_meta(key, resolved);
+            }
+        }
+    }
+
+    /// When the running timer was started, if any
+    pub fn started(&self) -> Option<DateTime<Local>> {
+        self.get_meta("started")
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|d| d.with_timezone(&Local))
+    }
+
+    /// Total tracked seconds, including the currently running timer
+    pub fn tracked(&self) -> i64 {
+        let stored = self
+            .get_meta("tracked")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let running = self
+            .started()
+            .map(|start| (Local::now() - start).num_seconds().max(0))
+            .unwrap_or(0);
+
+        stored + running
+    }
+
+    pub fn toggle_tracking(&mut self) {
+        if self.started().is_some() {
+            self.stop_tracking();
+        } else {
+            self.set_meta("started", Some(Local::now().to_rfc3339()));
+        }
+    }
+
+    pub fn stop_tracking(&mut self) {
+        if self.started().is_none() {
+            return;
+        }
+
+        let total = self.tracked();
+        self.set_meta("started", None);
+        self.set_meta("tracked", Some(total.to_string()));
+    }
+
+    pub fn recurrence(&self) -> Option<Recurrence> {
+        self.get_meta("rec").and_then(Recurrence::parse)
+    }
+
+    /// Dates a recurring task was completed on, oldest first
+    pub fn completions(&self) -> Vec<NaiveDate> {
+        self.get_meta("done")
+            .map(|s| {
+                s.split(',')
+                    .filter_map(|d| NaiveDate::parse_from_str(d, dates::FORMAT).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn set_completions(&mut self, mut dates: Vec<NaiveDate>) {
+        dates.sort();
+        dates.dedup();
+        let skip = dates.len().saturating_sub(COMPLETION_LIMIT);
+
+        let value = dates[skip..]
+            .iter()
+            .map(|d| d.format(dates::FORMAT).to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.set_meta("done", (!value.is_empty()).then_some(value));
+    }
+
+    /// Keeps the completion log of recurring tasks in line with their state
+    pub fn record_state_change(&mut self, today: NaiveDate) {
+        if self.recurrence().is_none() {
+            return;
+        }
+
+        let mut dates = self.completions();
+        match self.state {
+            TaskState::Done => dates.push(today),
+            TaskState::Open => dates.retain(|d| *d != today),
+        }
+        self.set_completions(dates);
+    }
+
+    /// Re-opens a completed recurring task once its next occurrence has started
+    pub fn reopen_if_due(&mut self, today: NaiveDate) -> bool {
+        let (TaskState::Done, Some(rec)) = (&self.state, self.recurrence()) else {
+            return false;
+        };
+
+        let done_this_period = self
+            .completions()
+            .last()
+            .is_some_and(|last| rec.period(*last) == rec.period(today));
+        if done_this_period {
+            return false;
+        }
+
+        self.state = TaskState::Open;
+        self.set_meta("completed", None);
+        true
+    }
+
+    /// Number of consecutive occurrences completed, counting back from the current one
+    pub fn streak(&self, today: NaiveDate) -> usize {
+        let Some(rec) = self.recurrence() else {
+            return 0;
+        };
+
+        let mut periods: Vec<i64> = self.completions().iter().map(|d| rec.period(*d)).collect();
+        periods.dedup();
+
+        // the current occurrence doesn't break the streak until it's over
+        let mut expected = rec.period(today);
+        if periods.last() != Some(&expected) {
+            expected -= 1;
+        }
+
+        let mut streak = 0;
+        for period in periods.iter().rev() {
+            if *period != expected {
+                break;
+            }
+            streak += 1;
+            expected -= 1;
+        }
+
+        streak
+    }
+}
+
+impl Bucket {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bucket::Waiting => "waiting",
+            Bucket::Someday => "someday",
+        }
+    }
+}
+
+impl TaskState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskState::Open => "open",
+            TaskState::Done => "done",
+        }
+    }
+}
+
+/// The start of a random UUID
+fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..ID_LENGTH].to_owned()
+}
+
+/// Gives every task of `tasks` read from `file` without an id one, and another to copies of a
+/// task whose id is already among `seen`, which collects the ids of the tasks read so far.
+/// They are made from the file and the line of the task rather than at random, so reading a
+/// file that was never saved with them gives the same ones every time.
+pub fn assign_ids(tasks: &mut [TaskItem], seen: &mut HashSet<String>, file: &str) {
+    for task in tasks {
+        let mut id = task.id().map(str::to_owned);
+        let mut copy: u32 = 0;
+        while id.as_ref().is_none_or(|id| seen.contains(id)) {
+            let mut hasher = StableHasher::default();
+            hasher.write(file.as_bytes());
+            hasher.write(b"\n");
+            hasher.write(format_task(task).as_bytes());
+            hasher.write(&copy.to_le_bytes());
+            id = Some(format!("{:016x}", hasher.finish())[..ID_LENGTH].to_owned());
+            copy += 1;
+        }
+        let id = id.unwrap();
+        if task.id() != Some(id.as_str()) {
+            task.set_meta("id", Some(id.clone()));
+        }
+        seen.insert(id);
+    }
+}
+
+/// The tasks nested below the one at `index`, however deep
+pub fn subtasks(tasks: &[TaskItem], index: usize) -> &[TaskItem] {
+    let depth = tasks[index].depth;
+    let end = tasks[index + 1..]
+        .iter()
+        .position(|t| t.depth <= depth)
+        .map_or(tasks.len(), |p| index + 1 + p);
+    &tasks[index + 1..end]
+}
+
+/// The task the one at `index` is nested in
+pub fn parent(tasks: &[TaskItem], index: usize) -> Option<usize> {
+    let depth = tasks[index].depth;
+    (0..index).rev().find(|i| tasks[*i].depth < depth)
+}
+
+/// How many of the subtasks of the one at `index` are done and how many there are, `None`
+/// without any
+pub fn progress(tasks: &[TaskItem], index: usize) -> Option<(usize, usize)> {
+    let subtasks = subtasks(tasks, index);
+    let done = subtasks.iter().filter(|t| t.state == TaskState::Done);
+    (!subtasks.is_empty()).then(|| (done.count(), subtasks.len()))
+}
+
+pub fn format_duration(secs: i64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+
+    if hours > 0 {
+        format!("{}h{:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tasks(ids: &[Option<&str>]) -> Vec<TaskItem> {
+        (ids.iter())
+            .map(|id| {
+                let mut task = TaskItem::new("same".to_owned(), TaskState::Open);
+                task.set_meta("id", id.map(str::to_owned));
+                task
+            })
+            .collect()
+    }
+
+    fn ids(tasks: &[TaskItem]) -> Vec<&str> {
+        tasks.iter().map(|t| t.id().unwrap()).collect()
+    }
+
+    #[test]
+    fn assigned_ids_are_the_same_for_the_same_file() {
+        let mut first = tasks(&[None, None, Some("abcdef12")]);
+        let mut second = first.clone();
+        assign_ids(&mut first, &mut HashSet::new(), "todo.md");
+        assign_ids(&mut second, &mut HashSet::new(), "todo.md");
+        assert_eq!(ids(&first), ids(&second));
+        assert_eq!(first[2].id(), Some("abcdef12"));
+        assert_eq!(first[0].id().unwrap().len(), ID_LENGTH);
+
+        let mut elsewhere = tasks(&[None]);
+        assign_ids(&mut elsewhere, &mut HashSet::new(), "other.md");
+        assert_ne!(elsewhere[0].id(), first[0].id());
+    }
+
+    #[test]
+    fn copies_get_ids_of_their_own() {
+        let mut tasks = tasks(&[None, None, Some("abcdef12"), Some("abcdef12")]);
+        let mut seen = HashSet::new();
+        assign_ids(&mut tasks, &mut seen, "todo.md");
+
+        let unique: HashSet<&str> = ids(&tasks).into_iter().collect();
+        assert_eq!(unique.len(), 4);
+        assert_eq!(tasks[2].id(), Some("abcdef12"));
+        assert_eq!(seen.len(), 4);
+    }
+
+    #[test]
+    fn ids_are_kept_across_chunks() {
+        let mut seen = HashSet::new();
+        let mut first = tasks(&[Some("abcdef12")]);
+        let mut second = tasks(&[Some("abcdef12")]);
+        assign_ids(&mut first, &mut seen, "todo.md");
+        assign_ids(&mut second, &mut seen, "todo.md");
+        assert_ne!(first[0].id(), second[0].id());
+    }
+
+    #[test]
+    fn new_tasks_get_an_id_once() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let mut task = TaskItem::new("new".to_owned(), TaskState::Open);
+        task.mark_created(today);
+        let id = task.id().map(str::to_owned);
+        assert!(id.is_some());
+        task.mark_created(today);
+        assert_eq!(task.id().map(str::to_owned), id);
+    }
 }
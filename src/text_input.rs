@@ -0,0 +1,84 @@
+use crossterm::event::Event;
+use tui_input::{backend::crossterm::to_input_request, Input, InputRequest};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Applies a key to `input`, moving over and deleting whole graphemes where tui-input would
+/// split an accented letter or an emoji into the chars it is made of
+pub fn handle_event(input: &mut Input, event: &Event) {
+    let Some(request) = to_input_request(event) else {
+        return;
+    };
+
+    let value = input.value();
+    let cursor = byte_offset(value, input.cursor());
+    let previous = value[..cursor]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(i, _)| i);
+    let next = value[cursor..]
+        .graphemes(true)
+        .next()
+        .map_or(cursor, |g| cursor + g.len());
+
+    match request {
+        InputRequest::GoToPrevChar => set_cursor(input, previous),
+        InputRequest::GoToNextChar => set_cursor(input, next),
+        InputRequest::DeletePrevChar => remove(input, previous, cursor),
+        InputRequest::DeleteNextChar => remove(input, cursor, next),
+        request => {
+            input.handle(request);
+            // a letter typed before a combining mark joins it
+            let value = input.value();
+            let cursor = byte_offset(value, input.cursor());
+            let boundary = value
+                .grapheme_indices(true)
+                .map(|(i, _)| i)
+                .find(|i| *i >= cursor)
+                .unwrap_or(value.len());
+            set_cursor(input, boundary);
+        }
+    }
+}
+
+/// Where the `chars`th char of `value` starts, tui-input counts its cursor in chars
+fn byte_offset(value: &str, chars: usize) -> usize {
+    value
+        .char_indices()
+        .nth(chars)
+        .map_or(value.len(), |(i, _)| i)
+}
+
+fn set_cursor(input: &mut Input, byte: usize) {
+    let chars = input.value()[..byte].chars().count();
+    input.handle(InputRequest::SetCursor(chars));
+}
+
+fn remove(input: &mut Input, start: usize, end: usize) {
+    let mut value = input.value().to_owned();
+    value.replace_range(start..end, "");
+    let cursor = value[..start].chars().count();
+    *input = Input::new(value).with_cursor(cursor);
+}
+
+/// The end of the value that fits in `width` columns with the cursor in view, and the
+/// column of the cursor within it
+pub fn visible(input: &Input, width: usize) -> (&str, usize) {
+    let value = input.value();
+    let before = &value[..byte_offset(value, input.cursor())];
+    let column: usize = before.graphemes(true).map(|g| g.width()).sum();
+
+    // the cursor needs a column of its own after the last grapheme
+    let scroll = (column + 1).saturating_sub(width);
+    let mut skipped = 0;
+    let mut start = 0;
+    for (i, grapheme) in value.grapheme_indices(true) {
+        if skipped >= scroll {
+            break;
+        }
+        skipped += grapheme.width();
+        start = i + grapheme.len();
+    }
+
+    (&value[start..], column.saturating_sub(skipped))
+}
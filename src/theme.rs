@@ -0,0 +1,638 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, OnceLock,
+};
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier, Style},
+};
+use serde_derive::Deserialize;
+use tracing::debug;
+
+use crate::{
+    rules::StyleRule,
+    task_item::{TaskItem, TaskState},
+};
+
+/// `[theme]` section of the config
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// `light` or `dark` colours, `auto` picks them by the terminal's background
+    pub background: Background,
+    /// How the text of a task is drawn in each state
+    pub states: StateStyles,
+    /// Highlights the selected task with a bar across the list instead of its text colour
+    pub selection_bar: bool,
+    /// Alternates the background of the rows, so long lists are easier to follow
+    pub zebra: bool,
+    pub glyphs: GlyphConfig,
+    /// Styles for the tasks a filter or pattern picks
+    pub rules: Vec<StyleRule>,
+}
+
+/// `[theme.glyphs]`, a set of icons with some of them replaced, e.g. `done = "✔"`
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GlyphConfig {
+    pub set: GlyphSet,
+    pub open: Option<String>,
+    pub done: Option<String>,
+    pub due: Option<String>,
+    pub timer: Option<String>,
+    pub prompt: Option<String>,
+    pub recurrence: Option<String>,
+    pub streak: Option<String>,
+    /// In front of tasks with a priority
+    pub star: Option<String>,
+    /// On the read-only badge
+    pub lock: Option<String>,
+    /// Behind tasks untouched for long
+    pub stale: Option<String>,
+    /// Behind tasks scheduled to start later
+    pub scheduled: Option<String>,
+    pub scrollbar_track: Option<String>,
+    pub scrollbar_thumb: Option<String>,
+    /// The cells of the bar showing how many subtasks are done
+    pub gauge_full: Option<String>,
+    pub gauge_empty: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphSet {
+    /// Icons of a patched Nerd Font
+    #[default]
+    NerdFont,
+    /// Symbols most fonts have
+    Unicode,
+}
+
+/// The icons drawn in the interface
+pub struct Glyphs {
+    pub open: String,
+    pub done: String,
+    pub due: String,
+    pub timer: String,
+    pub prompt: String,
+    pub recurrence: String,
+    pub streak: String,
+    pub star: String,
+    pub lock: String,
+    pub stale: String,
+    pub scheduled: String,
+    pub scrollbar_track: String,
+    pub scrollbar_thumb: String,
+    pub gauge_full: String,
+    pub gauge_empty: String,
+}
+
+impl From<&GlyphConfig> for Glyphs {
+    fn from(config: &GlyphConfig) -> Self {
+        let set = match config.set {
+            GlyphSet::NerdFont => &NERD_FONT,
+            GlyphSet::Unicode => &UNICODE,
+        };
+        let pick = |glyph: &Option<String>, default: &str| {
+            glyph.clone().unwrap_or_else(|| default.to_owned())
+        };
+        Self {
+            open: pick(&config.open, set.open),
+            done: pick(&config.done, set.done),
+            due: pick(&config.due, set.due),
+            timer: pick(&config.timer, set.timer),
+            prompt: pick(&config.prompt, set.prompt),
+            recurrence: pick(&config.recurrence, set.recurrence),
+            streak: pick(&config.streak, set.streak),
+            star: pick(&config.star, set.star),
+            lock: pick(&config.lock, set.lock),
+            stale: pick(&config.stale, set.stale),
+            scheduled: pick(&config.scheduled, set.scheduled),
+            scrollbar_track: pick(&config.scrollbar_track, set.scrollbar_track),
+            scrollbar_thumb: pick(&config.scrollbar_thumb, set.scrollbar_thumb),
+            gauge_full: pick(&config.gauge_full, set.gauge_full),
+            gauge_empty: pick(&config.gauge_empty, set.gauge_empty),
+        }
+    }
+}
+
+/// The glyphs of a `GlyphSet`
+struct Defaults {
+    open: &'static str,
+    done: &'static str,
+    due: &'static str,
+    timer: &'static str,
+    prompt: &'static str,
+    recurrence: &'static str,
+    streak: &'static str,
+    star: &'static str,
+    lock: &'static str,
+    stale: &'static str,
+    scheduled: &'static str,
+    scrollbar_track: &'static str,
+    scrollbar_thumb: &'static str,
+    gauge_full: &'static str,
+    gauge_empty: &'static str,
+}
+
+const NERD_FONT: Defaults = Defaults {
+    open: "\u{f05d}",
+    done: "\u{f058}",
+    due: "\u{f073}",
+    timer: "\u{f017}",
+    prompt: "\u{f460}",
+    recurrence: "\u{f021}",
+    streak: "\u{f06d}",
+    star: "\u{f005}",
+    lock: "\u{f023}",
+    stale: "\u{f254}",
+    scheduled: "\u{f04b}",
+    scrollbar_track: "│",
+    scrollbar_thumb: "█",
+    gauge_full: "■",
+    gauge_empty: "□",
+};
+
+const UNICODE: Defaults = Defaults {
+    open: "☐",
+    done: "☑",
+    due: "◷",
+    timer: "⧗",
+    prompt: "❯",
+    recurrence: "↻",
+    streak: "✦",
+    star: "★",
+    lock: "⚷",
+    stale: "⧖",
+    scheduled: "⏵",
+    scrollbar_track: "│",
+    scrollbar_thumb: "█",
+    gauge_full: "■",
+    gauge_empty: "□",
+};
+
+/// `[theme.states]`, e.g. `done = "dim crossed_out"`
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct StateStyles {
+    pub open: TextStyle,
+    pub done: TextStyle,
+    /// An open task with its timer running
+    pub tracking: TextStyle,
+}
+
+impl Default for StateStyles {
+    fn default() -> Self {
+        Self {
+            open: TextStyle::default(),
+            done: TextStyle(
+                Style::new()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT),
+            ),
+            tracking: TextStyle::default(),
+        }
+    }
+}
+
+/// A style written like `bold dim red on black`: modifiers, the text colour and the
+/// background colour after `on`; colours are names like `darkgray`, `#ff8800` or palette
+/// indices
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TextStyle(pub Style);
+
+impl TryFrom<String> for TextStyle {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let color = |word: &str| {
+            word.parse::<Color>()
+                .map_err(|_| format!("unknown colour or modifier `{}` in `{}`", word, value))
+        };
+
+        let mut style = Style::new();
+        let mut words = value.split_whitespace();
+        while let Some(word) = words.next() {
+            style = match word.to_lowercase().as_str() {
+                "bold" => style.add_modifier(Modifier::BOLD),
+                "dim" => style.add_modifier(Modifier::DIM),
+                "italic" => style.add_modifier(Modifier::ITALIC),
+                "underlined" | "underline" => style.add_modifier(Modifier::UNDERLINED),
+                "crossed_out" | "strikethrough" => style.add_modifier(Modifier::CROSSED_OUT),
+                "reversed" => style.add_modifier(Modifier::REVERSED),
+                "on" => match words.next() {
+                    Some(word) => style.bg(color(word)?),
+                    None => return Err(format!("no colour after `on` in `{}`", value)),
+                },
+                word => style.fg(color(word)?),
+            };
+        }
+
+        // both set the intensity, and leaving dim text would turn the bold of the list off
+        if style.add_modifier.contains(Modifier::DIM) {
+            style = style.remove_modifier(Modifier::BOLD);
+        }
+        Ok(TextStyle(style))
+    }
+}
+
+static CONFIG: OnceLock<ThemeConfig> = OnceLock::new();
+static GLYPHS: OnceLock<Glyphs> = OnceLock::new();
+
+fn config() -> &'static ThemeConfig {
+    CONFIG.get_or_init(ThemeConfig::default)
+}
+
+pub fn glyphs() -> &'static Glyphs {
+    GLYPHS.get_or_init(|| Glyphs::from(&config().glyphs))
+}
+
+/// The configured style of `task`'s text, on top of the list's
+pub fn state_style(task: &TaskItem) -> Style {
+    let styles = &config().states;
+    match task.state {
+        TaskState::Done => styles.done.0,
+        TaskState::Open if task.started().is_some() => styles.tracking.0,
+        TaskState::Open => styles.open.0,
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Background {
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+/// The colours that have to stand out against the terminal's background
+#[derive(Clone, Copy)]
+pub struct Palette {
+    /// Task text
+    pub text: Color,
+    /// The selected task, prompts and key hints
+    pub accent: Color,
+    /// Search matches within a line
+    pub matched: Style,
+    /// Behind the selected task with `selection_bar`
+    pub bar: Color,
+    /// Behind every other row with `zebra`
+    pub stripe: Color,
+    /// Mode badges in the status line: the list, typing a task, searching and the other views
+    pub normal_badge: Color,
+    pub insert_badge: Color,
+    pub search_badge: Color,
+    pub view_badge: Color,
+}
+
+const DARK: Palette = Palette {
+    text: Color::White,
+    accent: Color::Cyan,
+    matched: Style::new()
+        .fg(Color::Yellow)
+        .bg(Color::DarkGray)
+        .add_modifier(Modifier::UNDERLINED),
+    bar: Color::Indexed(238),
+    stripe: Color::Indexed(235),
+    normal_badge: Color::Cyan,
+    insert_badge: Color::Green,
+    search_badge: Color::Yellow,
+    view_badge: Color::Magenta,
+};
+
+const LIGHT: Palette = Palette {
+    text: Color::Black,
+    accent: Color::Blue,
+    matched: Style::new()
+        .fg(Color::Black)
+        .bg(Color::LightYellow)
+        .add_modifier(Modifier::UNDERLINED),
+    bar: Color::Indexed(189),
+    stripe: Color::Indexed(255),
+    normal_badge: Color::LightBlue,
+    insert_badge: Color::LightGreen,
+    search_badge: Color::LightYellow,
+    view_badge: Color::LightMagenta,
+};
+
+/// Whether the light palette is used, toggled at runtime
+static LIGHT_BACKGROUND: AtomicBool = AtomicBool::new(false);
+/// The accent of the open file, in place of the palette's
+static ACCENT: Mutex<Option<Color>> = Mutex::new(None);
+
+/// Picks the palette and styles from the config, asking the terminal for its background on
+/// `auto`; has to run before the terminal's input is read for events
+pub fn init(config: &ThemeConfig) {
+    let _ = CONFIG.set(config.clone());
+
+    let light = match config.background {
+        Background::Light => true,
+        Background::Dark => false,
+        Background::Auto => detect_background().unwrap_or(false),
+    };
+    debug!(light, "picked palette");
+    LIGHT_BACKGROUND.store(light, Ordering::Relaxed);
+}
+
+pub fn palette() -> Palette {
+    let mut palette = match is_light() {
+        true => LIGHT,
+        false => DARK,
+    };
+    if let Some(accent) = *ACCENT.lock().unwrap() {
+        palette.accent = accent;
+    }
+    palette
+}
+
+/// Replaces the accent of both palettes, `None` goes back to theirs
+pub fn set_accent(accent: Option<Color>) {
+    *ACCENT.lock().unwrap() = accent;
+}
+
+pub fn is_light() -> bool {
+    LIGHT_BACKGROUND.load(Ordering::Relaxed)
+}
+
+/// Switches between the light and the dark palette
+pub fn toggle() {
+    LIGHT_BACKGROUND.fetch_xor(true, Ordering::Relaxed);
+}
+
+/// Whether the background is light, from the terminal's answer or else `COLORFGBG`
+fn detect_background() -> Option<bool> {
+    query_background()
+        .map(|(r, g, b)| luminance(r, g, b) > 0.5)
+        .or_else(colorfgbg)
+}
+
+/// `COLORFGBG=15;0` as rxvt and konsole set it, the background is the last palette index
+fn colorfgbg() -> Option<bool> {
+    let value = std::env::var("COLORFGBG").ok()?;
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    Some(matches!(background, 7 | 9..=15))
+}
+
+fn luminance(r: u8, g: u8, b: u8) -> f64 {
+    (0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64) / 255.0
+}
+
+/// Gives up on terminals that answer neither query
+#[cfg(unix)]
+const QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Asks the terminal for its background colour with OSC 11, followed by a device
+/// attributes request every terminal answers so those that ignore OSC 11 don't cost
+/// the whole timeout
+#[cfg(unix)]
+fn query_background() -> Option<(u8, u8, u8)> {
+    use std::{
+        io::{IsTerminal, Write},
+        time::Instant,
+    };
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let mut stdout = std::io::stdout();
+    let asked = stdout
+        .write_all(b"\x1b]11;?\x1b\\\x1b[c")
+        .and_then(|_| stdout.flush());
+
+    let mut answer = vec![];
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    while asked.is_ok() && !answer_complete(&answer) {
+        let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        let mut fd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a single valid pollfd
+        if unsafe { libc::poll(&mut fd, 1, left.as_millis() as libc::c_int) } <= 0 {
+            break;
+        }
+        let mut buffer = [0u8; 64];
+        // SAFETY: at most `buffer.len()` bytes are written to `buffer`
+        let read = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), 64) };
+        if read <= 0 {
+            break;
+        }
+        answer.extend_from_slice(&buffer[..read as usize]);
+    }
+    let _ = crossterm::terminal::disable_raw_mode();
+
+    let answer = String::from_utf8_lossy(&answer);
+    debug!(?answer, "terminal background");
+    parse_background(&answer)
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<(u8, u8, u8)> {
+    None
+}
+
+/// The device attributes answer `ESC [ ? ... c` comes last
+#[cfg(unix)]
+fn answer_complete(answer: &[u8]) -> bool {
+    answer.ends_with(b"c")
+        && answer
+            .windows(3)
+            .rposition(|w| w == b"\x1b[?")
+            .is_some_and(|start| {
+                answer[start + 3..answer.len() - 1]
+                    .iter()
+                    .all(|b| b.is_ascii_digit() || *b == b';')
+            })
+}
+
+/// The colour in `ESC ] 11 ; rgb:RRRR/GGGG/BBBB`, each channel having one to four hex digits
+fn parse_background(answer: &str) -> Option<(u8, u8, u8)> {
+    let start = answer.find("]11;rgb:")? + "]11;rgb:".len();
+    let rest = &answer[start..];
+    let end = rest.find(|c: char| !c.is_ascii_hexdigit() && c != '/')?;
+    let mut channels = rest[..end].split('/').map(|hex| {
+        let value = u64::from_str_radix(hex, 16).ok()?;
+        let max = 16u64.checked_pow(hex.len() as u32)?.checked_sub(1)?;
+        Some((value * 255 / max.max(1)) as u8)
+    });
+    Some((channels.next()??, channels.next()??, channels.next()??))
+}
+
+/// How many colours the terminal can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    /// `NO_COLOR` is set or the terminal is dumb, only bold, reverse and the like are used
+    None,
+    /// The 16 ANSI colours
+    Basic,
+    /// The 256 colour palette
+    Indexed,
+    TrueColor,
+}
+
+static SUPPORT: OnceLock<ColorSupport> = OnceLock::new();
+
+/// Set by `--accessible`
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+
+pub fn color_support() -> ColorSupport {
+    *SUPPORT.get_or_init(detect)
+}
+
+/// Switches to plain text for screen readers and braille displays: no colours, words
+/// instead of decorative glyphs; has to run before anything is drawn
+pub fn set_accessible() {
+    ACCESSIBLE.store(true, Ordering::Relaxed);
+    let _ = SUPPORT.set(ColorSupport::None);
+}
+
+pub fn accessible() -> bool {
+    ACCESSIBLE.load(Ordering::Relaxed)
+}
+
+/// `glyph` and a space, or nothing where a screen reader would read the glyph out
+pub fn glyph(glyph: &str) -> String {
+    match accessible() {
+        true => String::new(),
+        false => format!("{} ", glyph),
+    }
+}
+
+fn detect() -> ColorSupport {
+    let var = |name| std::env::var(name).unwrap_or_default();
+
+    // https://no-color.org, any value but an empty one
+    if !var("NO_COLOR").is_empty() {
+        return ColorSupport::None;
+    }
+
+    let term = var("TERM");
+    match var("COLORTERM").as_str() {
+        _ if term == "dumb" => ColorSupport::None,
+        "truecolor" | "24bit" => ColorSupport::TrueColor,
+        _ if term.ends_with("-direct") => ColorSupport::TrueColor,
+        _ if term.contains("256color") => ColorSupport::Indexed,
+        _ => ColorSupport::Basic,
+    }
+}
+
+/// The selected task, reversed where a colour can't set it apart
+pub fn selection() -> Style {
+    match color_support() {
+        ColorSupport::None => Style::default().add_modifier(Modifier::REVERSED),
+        _ if config().selection_bar => Style::default().bg(palette().bar),
+        _ => Style::default().fg(palette().accent),
+    }
+}
+
+/// The background of the `row`th row of the list, striped with `zebra`
+pub fn row_style(row: usize) -> Style {
+    match config().zebra && row % 2 == 1 {
+        true => Style::default().bg(palette().stripe),
+        false => Style::default(),
+    }
+}
+
+/// Replaces the colours in `buffer` the terminal can't show with the closest ones it can
+pub fn degrade(buffer: &mut Buffer) {
+    let support = color_support();
+    if support == ColorSupport::TrueColor {
+        return;
+    }
+
+    for cell in buffer.content.iter_mut() {
+        cell.fg = downgrade(cell.fg, support);
+        cell.bg = downgrade(cell.bg, support);
+    }
+}
+
+fn downgrade(color: Color, support: ColorSupport) -> Color {
+    match (support, color) {
+        (ColorSupport::None, _) => Color::Reset,
+        (ColorSupport::Indexed, Color::Rgb(r, g, b)) => Color::Indexed(nearest_indexed(r, g, b)),
+        (ColorSupport::Basic, Color::Rgb(..) | Color::Indexed(_)) => {
+            let (r, g, b) = rgb(color);
+            BASIC[nearest(BASIC.iter().map(|(_, rgb)| *rgb), (r, g, b))].0
+        }
+        _ => color,
+    }
+}
+
+/// The ANSI colours in palette order, as xterm shows them
+const BASIC: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Steps of each channel in the 6x6x6 colour cube of the 256 colour palette
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(i @ 0..=15) => BASIC[i as usize].1,
+        Color::Indexed(i @ 16..=231) => {
+            let i = i - 16;
+            let channel = |n: u8| CUBE[n as usize];
+            (channel(i / 36), channel(i / 6 % 6), channel(i % 6))
+        }
+        Color::Indexed(i) => {
+            let gray = 8 + 10 * (i - 232);
+            (gray, gray, gray)
+        }
+        named => BASIC
+            .iter()
+            .find(|(c, _)| *c == named)
+            .map_or((0, 0, 0), |(_, rgb)| *rgb),
+    }
+}
+
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let step = |v: u8| nearest(CUBE.iter().map(|c| (*c, *c, *c)), (v, v, v)) as u8;
+    let cube = 16 + 36 * step(r) + 6 * step(g) + step(b);
+
+    let average = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23);
+
+    match distance(rgb(Color::Indexed(cube)), (r, g, b))
+        <= distance(rgb(Color::Indexed(gray)), (r, g, b))
+    {
+        true => cube,
+        false => gray,
+    }
+}
+
+/// The position of the colour in `palette` closest to `target`
+fn nearest(palette: impl Iterator<Item = (u8, u8, u8)>, target: (u8, u8, u8)) -> usize {
+    palette
+        .enumerate()
+        .min_by_key(|(_, rgb)| distance(*rgb, target))
+        .map_or(0, |(i, _)| i)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
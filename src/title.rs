@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::{
+    i18n::{t, t_args},
+    task_item::{TaskItem, TaskState},
+    theme,
+};
+
+/// Where syncing with the configured service stands
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SyncStatus {
+    /// Not synced since the interface started
+    #[default]
+    Idle,
+    Syncing,
+    Synced(DateTime<Local>),
+    Failed,
+}
+
+/// The file's name and how much of it is done on the left, the sync status on the right,
+/// so terminals with different lists open can be told apart
+pub fn ui(f: &mut Frame, area: Rect, file: &str, tasks: &[TaskItem], sync: SyncStatus) {
+    let name = Path::new(file)
+        .file_name()
+        .map_or(file.into(), |n| n.to_string_lossy());
+    let total = tasks.len();
+    let done = tasks.iter().filter(|t| t.state == TaskState::Done).count();
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut left = vec![Span::styled(
+        format!(" {}", name),
+        Style::default()
+            .fg(theme::palette().accent)
+            .add_modifier(Modifier::BOLD),
+    )];
+    if total > 0 {
+        let progress = t_args(
+            "title-progress",
+            &[
+                ("done", done.into()),
+                ("total", total.into()),
+                ("percent", (done * 100 / total).into()),
+            ],
+        );
+        left.push(Span::styled(format!("  {}", progress), dim));
+    }
+
+    let right = match sync {
+        SyncStatus::Idle => None,
+        SyncStatus::Syncing => Some(Span::styled(t("title-syncing"), dim)),
+        SyncStatus::Synced(at) => Some(Span::styled(
+            t_args(
+                "title-synced",
+                &[("time", at.format("%H:%M").to_string().into())],
+            ),
+            dim,
+        )),
+        SyncStatus::Failed => Some(Span::styled(
+            t("title-sync-failed"),
+            Style::default().fg(Color::Red),
+        )),
+    };
+
+    f.render_widget(Paragraph::new(Line::from(left)), area);
+    if let Some(right) = right {
+        let line = Line::from(vec![right, Span::raw(" ")]);
+        f.render_widget(Paragraph::new(line).alignment(Alignment::Right), area);
+    }
+}
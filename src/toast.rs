@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use tracing::{error, info, warn};
+
+use crate::i18n::t;
+
+/// How long a toast stays on screen
+const INFO_DURATION: Duration = Duration::from_secs(4);
+const PROBLEM_DURATION: Duration = Duration::from_secs(8);
+
+/// Warnings and errors kept for the history popup
+const HISTORY_LEN: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self) -> Color {
+        match self {
+            Severity::Info => Color::Cyan,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub severity: Severity,
+    pub text: String,
+    pub at: DateTime<Local>,
+}
+
+/// The toast on screen and the warnings and errors seen so far
+#[derive(Default)]
+pub struct Toasts {
+    current: Option<Toast>,
+    left: Duration,
+    history: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, severity: Severity, text: String) {
+        match severity {
+            Severity::Info => info!("{}", text),
+            Severity::Warning => warn!("{}", text),
+            Severity::Error => error!("{}", text),
+        }
+
+        let toast = Toast {
+            severity,
+            text,
+            at: Local::now(),
+        };
+
+        if severity != Severity::Info {
+            if self.history.len() == HISTORY_LEN {
+                self.history.remove(0);
+            }
+            self.history.push(toast.clone());
+        }
+
+        self.left = match severity {
+            Severity::Info => INFO_DURATION,
+            _ => PROBLEM_DURATION,
+        };
+        self.current = Some(toast);
+    }
+
+    /// Hides the current toast once its time is up, `elapsed` having passed since the last tick
+    pub fn tick(&mut self, elapsed: Duration) {
+        self.left = self.left.saturating_sub(elapsed);
+        if self.left.is_zero() {
+            self.current = None;
+        }
+    }
+
+    pub fn current(&self) -> Option<&Toast> {
+        self.current.as_ref()
+    }
+
+    pub fn history(&self) -> &[Toast] {
+        &self.history
+    }
+}
+
+/// The toast in the top right corner of `area`
+pub fn ui(f: &mut Frame, area: Rect, toast: &Toast) {
+    let width = (toast.text.chars().count() as u16 + 4).min(area.width);
+    let rect = Rect {
+        x: area.x + area.width - width,
+        y: area.y,
+        width,
+        height: 3.min(area.height),
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(toast.severity.color()));
+
+    f.render_widget(Clear, rect);
+    f.render_widget(
+        Paragraph::new(toast.text.as_str())
+            .style(Style::default().fg(toast.severity.color()))
+            .block(block),
+        rect,
+    );
+}
+
+/// The toast as a plain line in `area`, for screen readers in accessible mode; problems
+/// say what they are instead of relying on the colour
+pub fn line_ui(f: &mut Frame, area: Rect, toast: &Toast) {
+    let text = match toast.severity {
+        Severity::Info => toast.text.clone(),
+        severity => format!("{}: {}", severity.label(), toast.text),
+    };
+    f.render_widget(Paragraph::new(text), area);
+}
+
+/// Every warning and error so far, the newest first
+pub fn history_ui(f: &mut Frame, area: Rect, history: &[Toast]) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = match history.is_empty() {
+        true => vec![Line::styled(t("errors-none"), dim)],
+        false => history
+            .iter()
+            .rev()
+            .map(|toast| {
+                Line::from(vec![
+                    Span::styled(format!("{} ", toast.at.format("%H:%M:%S")), dim),
+                    Span::styled(
+                        format!("{:<8}", toast.severity.label()),
+                        Style::default().fg(toast.severity.color()),
+                    ),
+                    Span::raw(toast.text.clone()),
+                ])
+            })
+            .collect(),
+    };
+
+    let block = Block::default()
+        .title(format!(" {} ", t("errors-title")))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Red));
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
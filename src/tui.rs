@@ -1,37 +1,43 @@
 use std::{
     ops::{Deref, DerefMut},
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     time::Duration,
 };
 
+use tokio::time::Instant;
+
 use color_eyre::eyre::Result;
 use crossterm::{
     cursor,
-    event::{
-        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
-        Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent,
-    },
+    event::{Event as CrosstermEvent, KeyEvent, KeyEventKind},
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use futures::{FutureExt, StreamExt};
-use ratatui::backend::CrosstermBackend as Backend;
+use ratatui::{backend::CrosstermBackend as Backend, Frame, TerminalOptions, Viewport};
 use tokio::{
+    process::Command,
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
     task::JoinHandle,
 };
 use tokio_util::sync::CancellationToken;
 
+use crate::theme;
+
 #[derive(Clone, Debug)]
 pub enum Event {
-    Init,
     Error,
     Tick,
     Render,
-    FocusGained,
-    FocusLost,
-    Paste(String),
     Key(KeyEvent, CrosstermEvent),
-    Mouse(MouseEvent),
-    Resize(u16, u16),
+    Resize,
+    /// SIGTSTP came from outside, e.g. `kill -TSTP`; ctrl-z is a key press in raw mode
+    Suspend,
+    /// SIGTERM or SIGHUP, the terminal may be gone already
+    Terminate,
 }
 
 pub struct Tui {
@@ -42,20 +48,29 @@ pub struct Tui {
     pub event_tx: UnboundedSender<Event>,
     pub frame_rate: f64,
     pub tick_rate: f64,
-    pub mouse: bool,
-    pub paste: bool,
+    /// Frames per second once no input came for `idle_after`
+    pub idle_frame_rate: f64,
+    pub idle_after: Duration,
+    pub inline: bool,
+    /// Forwards the signals for as long as the Tui lives, the terminal handed off or not
+    pub signals: JoinHandle<()>,
+    /// Whether the terminal is ours, not the shell's or an editor's
+    active: Arc<AtomicBool>,
 }
 
 impl Tui {
     pub fn new() -> Result<Self> {
         let tick_rate = 4.0;
         let frame_rate = 60.0;
+        let idle_frame_rate = frame_rate;
+        let idle_after = Duration::MAX;
         let terminal = ratatui::Terminal::new(Backend::new(std::io::stderr()))?;
         let (event_tx, event_rx) = mpsc::unbounded_channel();
         let cancellation_token = CancellationToken::new();
         let task = tokio::spawn(async {});
-        let mouse = false;
-        let paste = false;
+        let inline = false;
+        let active = Arc::new(AtomicBool::new(false));
+        let signals = forward_signals(event_tx.clone(), active.clone());
 
         Ok(Self {
             terminal,
@@ -65,8 +80,11 @@ impl Tui {
             event_tx,
             frame_rate,
             tick_rate,
-            mouse,
-            paste,
+            idle_frame_rate,
+            idle_after,
+            inline,
+            signals,
+            active,
         })
     }
 
@@ -75,24 +93,39 @@ impl Tui {
         self
     }
 
+    /// Draws a frame, replacing colours the terminal can't show
+    pub fn draw(&mut self, ui: impl FnOnce(&mut Frame)) -> Result<()> {
+        self.terminal.draw(|f| {
+            ui(f);
+            theme::degrade(f.buffer_mut());
+        })?;
+        Ok(())
+    }
+
     pub fn frame_rate(mut self, frame_rate: f64) -> Self {
         self.frame_rate = frame_rate;
         self
     }
 
-    // pub fn mouse(mut self, mouse: bool) -> Self {
-    //     self.mouse = mouse;
-    //     self
-    // }
-    //
-    // pub fn paste(mut self, paste: bool) -> Self {
-    //     self.paste = paste;
-    //     self
-    // }
+    /// Drops to `frame_rate` once nothing was pressed for `after`, back on the next key
+    pub fn idle(mut self, frame_rate: f64, after: Duration) -> Self {
+        self.idle_frame_rate = frame_rate;
+        self.idle_after = after;
+        self
+    }
+
+    /// Draw into a viewport of `height` lines below the cursor instead of the alternate screen
+    pub fn inline(mut self, height: u16) -> Result<Self> {
+        self.terminal = inline_terminal(height)?;
+        self.inline = true;
+        Ok(self)
+    }
 
     pub fn start(&mut self) {
         let tick_delay = std::time::Duration::from_secs_f64(1.0 / self.tick_rate);
         let render_delay = std::time::Duration::from_secs_f64(1.0 / self.frame_rate);
+        let idle_render_delay = std::time::Duration::from_secs_f64(1.0 / self.idle_frame_rate);
+        let idle_after = self.idle_after;
         self.cancel();
         self.cancellation_token = CancellationToken::new();
         let _cancellation_token = self.cancellation_token.clone();
@@ -100,40 +133,30 @@ impl Tui {
         self.task = tokio::spawn(async move {
             let mut reader = crossterm::event::EventStream::new();
             let mut tick_interval = tokio::time::interval(tick_delay);
-            let mut render_interval = tokio::time::interval(render_delay);
-            _event_tx.send(Event::Init).unwrap();
+            let mut last_input = Instant::now();
+            let mut next_render = Instant::now();
             loop {
                 let tick_delay = tick_interval.tick();
-                let render_delay = render_interval.tick();
+                let render_due = tokio::time::sleep_until(next_render);
                 let crossterm_event = reader.next().fuse();
                 tokio::select! {
                   _ = _cancellation_token.cancelled() => {
                     break;
                   }
                   maybe_event = crossterm_event => {
+                    // an idle frame could be a while off
+                    last_input = Instant::now();
+                    next_render = next_render.min(last_input + render_delay);
                     match maybe_event {
                       Some(Ok(evt)) => {
                         match evt {
-                          CrosstermEvent::Key(key) => {
-                            if key.kind == KeyEventKind::Press {
-                              _event_tx.send(Event::Key(key, evt)).unwrap();
-                            }
-                          },
-                          CrosstermEvent::Mouse(mouse) => {
-                            _event_tx.send(Event::Mouse(mouse)).unwrap();
+                          CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => {
+                            _event_tx.send(Event::Key(key, evt)).unwrap();
                           },
-                          CrosstermEvent::Resize(x, y) => {
-                            _event_tx.send(Event::Resize(x, y)).unwrap();
-                          },
-                          CrosstermEvent::FocusLost => {
-                            _event_tx.send(Event::FocusLost).unwrap();
-                          },
-                          CrosstermEvent::FocusGained => {
-                            _event_tx.send(Event::FocusGained).unwrap();
-                          },
-                          CrosstermEvent::Paste(s) => {
-                            _event_tx.send(Event::Paste(s)).unwrap();
+                          CrosstermEvent::Resize(..) => {
+                            _event_tx.send(Event::Resize).unwrap();
                           },
+                          _ => {},
                         }
                       }
                       Some(Err(_)) => {
@@ -145,8 +168,13 @@ impl Tui {
                   _ = tick_delay => {
                       _event_tx.send(Event::Tick).unwrap();
                   },
-                  _ = render_delay => {
+                  _ = render_due => {
                       _event_tx.send(Event::Render).unwrap();
+                      let delay = match last_input.elapsed() >= idle_after {
+                          true => idle_render_delay,
+                          false => render_delay,
+                      };
+                      next_render = Instant::now() + delay;
                   },
                 }
             }
@@ -172,28 +200,28 @@ impl Tui {
 
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
-        crossterm::execute!(std::io::stderr(), EnterAlternateScreen, cursor::Hide)?;
-        if self.mouse {
-            crossterm::execute!(std::io::stderr(), EnableMouseCapture)?;
-        }
-        if self.paste {
-            crossterm::execute!(std::io::stderr(), EnableBracketedPaste)?;
+        if self.inline {
+            crossterm::execute!(std::io::stderr(), cursor::Hide)?;
+        } else {
+            crossterm::execute!(std::io::stderr(), EnterAlternateScreen, cursor::Hide)?;
         }
         self.start();
+        self.active.store(true, Ordering::SeqCst);
         Ok(())
     }
 
     pub fn exit(&mut self) -> Result<()> {
+        self.active.store(false, Ordering::SeqCst);
         self.stop()?;
         if crossterm::terminal::is_raw_mode_enabled()? {
             self.flush()?;
-            if self.paste {
-                crossterm::execute!(std::io::stderr(), DisableBracketedPaste)?;
-            }
-            if self.mouse {
-                crossterm::execute!(std::io::stderr(), DisableMouseCapture)?;
+            if self.inline {
+                // leave the last frame in the scrollback and continue below it
+                let bottom = self.terminal.get_frame().size().bottom();
+                crossterm::execute!(std::io::stderr(), cursor::MoveTo(0, bottom), cursor::Show)?;
+            } else {
+                crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, cursor::Show)?;
             }
-            crossterm::execute!(std::io::stderr(), LeaveAlternateScreen, cursor::Show)?;
             crossterm::terminal::disable_raw_mode()?;
         }
         Ok(())
@@ -203,23 +231,125 @@ impl Tui {
         self.cancellation_token.cancel();
     }
 
-    // pub fn suspend(&mut self) -> Result<()> {
-    //     self.exit()?;
-    //     #[cfg(not(windows))]
-    //     // signal_hook::low_level::raise(signal_hook::consts::signal::SIGTSTP)?;
-    //     Ok(())
-    // }
+    /// Hands the terminal back to the shell and stops until the job is continued with `fg`,
+    /// then takes it over again; does nothing without job control
+    pub fn suspend(&mut self) -> Result<()> {
+        #[cfg(unix)]
+        {
+            self.exit()?;
+            // SIGTSTP is caught to get here, SIGSTOP can't be
+            unsafe { libc::raise(libc::SIGSTOP) };
+            self.resume()?;
+        }
+        Ok(())
+    }
 
-    // pub fn resume(&mut self) -> Result<()> {
-    //     self.enter()?;
-    //     Ok(())
-    // }
+    /// Hands the terminal to `command` until it finishes, e.g. an editor, then takes it
+    /// over again
+    pub async fn run(&mut self, command: &mut Command) -> Result<ExitStatus> {
+        self.exit()?;
+        let status = command.status().await;
+        self.resume()?;
+        Ok(status?)
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        if self.inline {
+            // the shell wrote below the old viewport, so the new one starts at the cursor
+            let height = self.terminal.get_frame().size().height;
+            self.terminal = inline_terminal(height)?;
+        }
+        self.enter()?;
+        // whatever the shell left on the screen has to go on the next draw
+        self.terminal.clear()?;
+        Ok(())
+    }
 
     pub async fn next(&mut self) -> Option<Event> {
         self.event_rx.recv().await
     }
 }
 
+fn inline_terminal(height: u16) -> Result<ratatui::Terminal<Backend<std::io::Stderr>>> {
+    let terminal = ratatui::Terminal::with_options(
+        Backend::new(std::io::stderr()),
+        TerminalOptions {
+            viewport: Viewport::Inline(height),
+        },
+    )?;
+    Ok(terminal)
+}
+
+/// Sends the events the signals lead to; SIGTERM and SIGHUP wait for the terminal to come
+/// back, SIGTSTP while it is handed off stops the process along with the program it went to
+fn forward_signals(tx: UnboundedSender<Event>, active: Arc<AtomicBool>) -> JoinHandle<()> {
+    // caught from here on, so none of them is lost between the handoffs
+    let mut signals = Signals::new();
+    tokio::spawn(async move {
+        loop {
+            match signals.recv().await {
+                #[cfg(unix)]
+                Event::Suspend if !active.load(Ordering::SeqCst) => unsafe {
+                    libc::raise(libc::SIGSTOP);
+                },
+                event => {
+                    if tx.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Signals that would stop or end the process without a chance to restore the terminal
+/// and save unless they are caught
+struct Signals {
+    #[cfg(unix)]
+    stop: Option<tokio::signal::unix::Signal>,
+    #[cfg(unix)]
+    terminate: Option<tokio::signal::unix::Signal>,
+    #[cfg(unix)]
+    hangup: Option<tokio::signal::unix::Signal>,
+}
+
+impl Signals {
+    fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            Self {
+                stop: signal(SignalKind::from_raw(libc::SIGTSTP)).ok(),
+                terminate: signal(SignalKind::terminate()).ok(),
+                hangup: signal(SignalKind::hangup()).ok(),
+            }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    /// The event the next of them leads to
+    async fn recv(&mut self) -> Event {
+        #[cfg(unix)]
+        tokio::select! {
+            Some(_) = recv(&mut self.stop) => Event::Suspend,
+            Some(_) = recv(&mut self.terminate) => Event::Terminate,
+            Some(_) = recv(&mut self.hangup) => Event::Terminate,
+            else => std::future::pending().await,
+        }
+        #[cfg(not(unix))]
+        std::future::pending().await
+    }
+}
+
+#[cfg(unix)]
+async fn recv(signal: &mut Option<tokio::signal::unix::Signal>) -> Option<()> {
+    match signal {
+        Some(signal) => signal.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 impl Deref for Tui {
     type Target = ratatui::Terminal<Backend<std::io::Stderr>>;
 
@@ -236,6 +366,8 @@ impl DerefMut for Tui {
 
 impl Drop for Tui {
     fn drop(&mut self) {
-        self.exit().unwrap();
+        // the terminal may be gone after SIGHUP, which is no reason to panic on the way out
+        let _ = self.exit();
+        self.signals.abort();
     }
 }
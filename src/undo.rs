@@ -0,0 +1,479 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Local};
+use color_eyre::eyre::Result;
+use serde_derive::{Deserialize, Serialize};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::AsyncWriteExt,
+    sync::{mpsc, oneshot},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    crypt,
+    file::{format_task, parse_tasks},
+    hash,
+    history::data_dir,
+    journal,
+    task_item::TaskItem,
+};
+
+/// Changes kept at most, the oldest go first
+const MAX_ENTRIES: usize = 100;
+/// Changes older than this are forgotten
+const MAX_AGE_DAYS: i64 = 7;
+/// Size of the task lines kept across all changes, the latest change is kept even above it
+const MAX_BYTES: usize = 2 << 20;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    at: DateTime<Local>,
+    /// What the change was, e.g. `deleting buy milk`
+    label: String,
+    /// Where the tasks the change touched start
+    index: usize,
+    /// Their lines before the change
+    before: Vec<String>,
+    /// Their lines after it, which have to be unchanged to take it back
+    after: Vec<String>,
+    /// How many tasks there were after the change
+    count: usize,
+}
+
+/// One line of the journal, replayed in order when it is loaded
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Record {
+    Change(Entry),
+    /// The latest change was taken back
+    Undo,
+    /// All changes were forgotten
+    Forget,
+}
+
+/// A change begun but not made yet
+struct Pending {
+    label: String,
+    tasks: Vec<TaskItem>,
+}
+
+/// The recent changes to a file and what each one replaced, kept next to the history so
+/// they can still be undone after reopening the file
+#[derive(Default)]
+pub struct Undo {
+    /// `None` keeps the changes in memory only
+    path: Option<PathBuf>,
+    entries: Vec<Entry>,
+    pending: Option<Pending>,
+    /// Lines in the journal, it is written anew once most of them are outdated
+    records: usize,
+    writer: Option<Writer>,
+}
+
+/// What undoing the latest change came to
+pub enum Undone {
+    /// The tasks as they were before the change with that label
+    Tasks(String, Vec<TaskItem>),
+    /// Nothing left to undo
+    Nothing,
+    /// The tasks the change touched changed some other way since, by a sync or another
+    /// program, so undoing would lose that; the changes before are forgotten
+    Changed,
+}
+
+impl Undo {
+    /// The changes remembered for `file`; encrypted files keep theirs in memory so no
+    /// plain text ends up on disk
+    pub async fn load(file: &str) -> Self {
+        if crypt::is_encrypted(file) {
+            return Self::default();
+        }
+
+        // the same file opened from another directory
+        let file = match fs::canonicalize(file).await {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => file.to_owned(),
+        };
+        let path = journal_path(&file);
+        let mut undo = Self {
+            path: Some(path.clone()),
+            ..Self::default()
+        };
+        if let Ok(content) = fs::read_to_string(&path).await {
+            if let Err(e) = undo.replay(&content) {
+                warn!(path = %path.display(), "unreadable undo journal: {}", e);
+                undo.entries.clear();
+            }
+        }
+
+        undo.trim();
+        if undo.records > undo.entries.len() {
+            undo.rewrite();
+        }
+        debug!(changes = undo.entries.len(), "loaded undo journal");
+        undo
+    }
+
+    fn replay(&mut self, content: &str) -> Result<()> {
+        for line in content.lines().filter(|l| !l.is_empty()) {
+            match serde_json::from_str(line)? {
+                Record::Change(entry) => self.entries.push(entry),
+                Record::Undo => {
+                    self.entries.pop();
+                }
+                Record::Forget => self.entries.clear(),
+            }
+            self.records += 1;
+        }
+        Ok(())
+    }
+
+    /// Remembers `tasks` before a change described by `label`, `commit` finishes it
+    pub fn begin(&mut self, label: String, tasks: &[TaskItem]) {
+        // a change that was begun but never made is replaced
+        self.pending = Some(Pending {
+            label,
+            tasks: tasks.to_vec(),
+        });
+    }
+
+    /// Finishes the change begun last, now that `tasks` are what it led to
+    pub fn commit(&mut self, tasks: &[TaskItem]) {
+        let Some(Pending { label, tasks: old }) = self.pending.take() else {
+            return;
+        };
+        let (before, after) = journal::changed(&old, tasks);
+        if before.is_empty() && after.is_empty() {
+            return;
+        }
+
+        let entry = Entry {
+            at: Local::now(),
+            label,
+            index: before.start,
+            before: old[before].iter().map(format_task).collect(),
+            after: tasks[after].iter().map(format_task).collect(),
+            count: tasks.len(),
+        };
+        self.entries.push(entry.clone());
+        self.trim();
+        self.append(Record::Change(entry));
+    }
+
+    /// Takes the latest change back, provided the tasks it touched are still what it
+    /// left them as
+    pub fn undo(&mut self, tasks: &[TaskItem]) -> Undone {
+        self.pending = None;
+        let Some(entry) = self.entries.last() else {
+            return Undone::Nothing;
+        };
+
+        let end = entry.index + entry.after.len();
+        let unchanged = tasks.len() == entry.count
+            && (tasks.get(entry.index..end)).is_some_and(|touched| {
+                (touched.iter().zip(&entry.after)).all(|(task, line)| format_task(task) == *line)
+            });
+        if !unchanged {
+            self.entries.clear();
+            self.append(Record::Forget);
+            return Undone::Changed;
+        }
+
+        let entry = self.entries.pop().unwrap();
+        let mut undone = tasks[..entry.index].to_vec();
+        undone.extend(parse_tasks(&entry.before.join("\n")));
+        undone.extend_from_slice(&tasks[end..]);
+        self.append(Record::Undo);
+        Undone::Tasks(entry.label, undone)
+    }
+
+    /// Waits for the journal to be written, for quitting when writes in the background
+    /// could be cut off
+    pub async fn flush(&mut self) -> Result<()> {
+        let Some(writer) = &self.writer else {
+            return Ok(());
+        };
+        let (tx, rx) = oneshot::channel();
+        let _ = writer.tx.send(Write::Flush(tx));
+        rx.await.unwrap_or(Ok(()))
+    }
+
+    /// Forgets changes that are too old or too many
+    fn trim(&mut self) {
+        let oldest = Local::now() - Duration::days(MAX_AGE_DAYS);
+        self.entries.retain(|e| e.at >= oldest);
+
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+
+        let mut bytes: usize = self.entries.iter().map(size).sum();
+        while self.entries.len() > 1 && bytes > MAX_BYTES {
+            bytes -= size(&self.entries.remove(0));
+        }
+    }
+
+    /// Adds `record` to the end of the journal, or writes it anew when it is mostly
+    /// changes that are forgotten by now
+    fn append(&mut self, record: Record) {
+        if self.records >= self.entries.len() + MAX_ENTRIES {
+            self.rewrite();
+            return;
+        }
+        self.records += 1;
+        self.send(Write::Append(line(&record)));
+    }
+
+    fn rewrite(&mut self) {
+        let content = (self.entries.iter())
+            .map(|entry| line(&Record::Change(entry.clone())))
+            .collect();
+        self.records = self.entries.len();
+        self.send(Write::Replace(content));
+    }
+
+    fn send(&mut self, write: Write) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let writer = self
+            .writer
+            .get_or_insert_with(|| Writer::spawn(path.clone()));
+        let _ = writer.tx.send(write);
+    }
+}
+
+enum Write {
+    Append(String),
+    Replace(String),
+    /// Answers once everything before is written, with the first failure since the last
+    Flush(oneshot::Sender<Result<()>>),
+}
+
+/// Writes the journal in the background one write after the other, so an older one can't
+/// land after a newer one
+struct Writer {
+    tx: mpsc::UnboundedSender<Write>,
+}
+
+impl Writer {
+    fn spawn(path: PathBuf) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut failed = None;
+            while let Some(write) = rx.recv().await {
+                let result = match write {
+                    Write::Append(content) => append(&path, &content).await,
+                    Write::Replace(content) => replace(&path, &content).await,
+                    Write::Flush(done) => {
+                        let _ = done.send(failed.take().map_or(Ok(()), Err));
+                        continue;
+                    }
+                };
+                if let Err(e) = result {
+                    warn!(path = %path.display(), "writing the undo journal failed: {}", e);
+                    failed.get_or_insert(e);
+                }
+            }
+        });
+        Self { tx }
+    }
+}
+
+async fn append(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+async fn replace(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    fs::write(path, content).await?;
+    Ok(())
+}
+
+fn line(record: &Record) -> String {
+    // nothing in a record fails to serialize
+    serde_json::to_string(record).unwrap_or_default() + "\n"
+}
+
+fn size(entry: &Entry) -> usize {
+    (entry.before.iter().chain(&entry.after))
+        .map(|l| l.len() + 1)
+        .sum()
+}
+
+fn journal_path(file: &str) -> PathBuf {
+    let name = Path::new(file)
+        .file_name()
+        .map_or("todo.md".into(), |n| n.to_string_lossy());
+    data_dir().join("undo").join(format!(
+        "{:016x}-{}.jsonl",
+        hash::stable(file.as_bytes()),
+        name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_item::TaskState;
+
+    fn tasks(texts: &[&str]) -> Vec<TaskItem> {
+        (texts.iter())
+            .map(|text| TaskItem::new(text.to_string(), TaskState::Open))
+            .collect()
+    }
+
+    fn change(undo: &mut Undo, label: &str, before: &[TaskItem], after: &[TaskItem]) {
+        undo.begin(label.to_owned(), before);
+        undo.commit(after);
+    }
+
+    fn undone(undo: &mut Undo, tasks: &[TaskItem]) -> Option<(String, Vec<TaskItem>)> {
+        match undo.undo(tasks) {
+            Undone::Tasks(label, tasks) => Some((label, tasks)),
+            Undone::Nothing => None,
+            Undone::Changed => panic!("the tasks changed"),
+        }
+    }
+
+    #[test]
+    fn takes_changes_back_latest_first() {
+        let (a, b, c) = (tasks(&["a"]), tasks(&["a", "b"]), tasks(&["a", "b", "c"]));
+        let mut undo = Undo::default();
+        change(&mut undo, "adding b", &a, &b);
+        change(&mut undo, "adding c", &b, &c);
+
+        assert_eq!(
+            undone(&mut undo, &c),
+            Some(("adding c".to_owned(), b.clone()))
+        );
+        assert_eq!(undone(&mut undo, &b), Some(("adding b".to_owned(), a)));
+        assert_eq!(undone(&mut undo, &b), None);
+    }
+
+    #[test]
+    fn changes_never_made_are_skipped() {
+        let (a, b) = (tasks(&["a"]), tasks(&["b"]));
+        let mut undo = Undo::default();
+        change(&mut undo, "editing a", &a, &b);
+        undo.begin("deleting b".to_owned(), &b);
+        undo.begin("editing b".to_owned(), &b);
+
+        assert_eq!(undone(&mut undo, &b), Some(("editing a".to_owned(), a)));
+        assert_eq!(undone(&mut undo, &b), None);
+    }
+
+    #[test]
+    fn changes_from_elsewhere_are_not_undone() {
+        let (a, b) = (tasks(&["a"]), tasks(&["b"]));
+        let mut undo = Undo::default();
+        change(&mut undo, "editing a", &a, &b);
+        change(&mut undo, "editing b", &b, &a);
+
+        assert!(matches!(undo.undo(&tasks(&["synced"])), Undone::Changed));
+        // what came before can't be undone either
+        assert_eq!(undone(&mut undo, &a), None);
+    }
+
+    #[test]
+    fn forgets_old_and_excess_changes() {
+        let mut undo = Undo::default();
+        for i in 0..MAX_ENTRIES + 5 {
+            let text = i.to_string();
+            change(&mut undo, &text, &tasks(&[&text]), &tasks(&[]));
+        }
+        assert_eq!(undo.entries.len(), MAX_ENTRIES);
+        assert_eq!(undo.entries[0].label, "5");
+
+        undo.entries[0].at = Local::now() - Duration::days(MAX_AGE_DAYS + 1);
+        undo.trim();
+        assert_eq!(undo.entries.len(), MAX_ENTRIES - 1);
+
+        let big = "x".repeat(MAX_BYTES);
+        change(&mut undo, "big", &tasks(&[&big]), &tasks(&[]));
+        assert_eq!(undo.entries.len(), 1);
+        assert_eq!(undone(&mut undo, &[]).unwrap().0, "big");
+    }
+
+    #[test]
+    fn keeps_only_the_touched_lines_and_changes_around_them() {
+        let before = tasks(&["a", "b", "c", "d"]);
+        let after = tasks(&["a", "B", "c", "d"]);
+        let mut undo = Undo::default();
+        change(&mut undo, "editing b", &before, &after);
+        assert_eq!(undo.entries[0].index, 1);
+        assert_eq!(undo.entries[0].before, ["- [ ] b"]);
+        assert_eq!(undo.entries[0].after, ["- [ ] B"]);
+
+        // a task the change didn't touch was edited elsewhere meanwhile
+        let synced = tasks(&["a", "B", "c", "D"]);
+        let expected = tasks(&["a", "b", "c", "D"]);
+        assert_eq!(
+            undone(&mut undo, &synced),
+            Some(("editing b".to_owned(), expected))
+        );
+    }
+
+    #[test]
+    fn replays_the_journal_in_order() {
+        let mut undo = Undo::default();
+        change(&mut undo, "adding a", &[], &tasks(&["a"]));
+        change(&mut undo, "adding b", &tasks(&["a"]), &tasks(&["a", "b"]));
+        change(
+            &mut undo,
+            "adding c",
+            &tasks(&["a", "b"]),
+            &tasks(&["a", "b", "c"]),
+        );
+        let mut content: String = (undo.entries.iter())
+            .map(|e| line(&Record::Change(e.clone())))
+            .collect();
+        content += &line(&Record::Undo);
+
+        let mut loaded = Undo::default();
+        loaded.replay(&content).unwrap();
+        let labels: Vec<&str> = loaded.entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["adding a", "adding b"]);
+        assert_eq!(loaded.records, 4);
+
+        loaded.replay(&line(&Record::Forget)).unwrap();
+        assert!(loaded.entries.is_empty());
+        assert!(Undo::default().replay("[]").is_err());
+    }
+
+    #[tokio::test]
+    async fn flushing_writes_the_latest_changes() {
+        let path = std::env::temp_dir()
+            .join(format!("tuitodo-undo-{}", std::process::id()))
+            .join("todo.json");
+        let mut undo = Undo {
+            path: Some(path.clone()),
+            ..Undo::default()
+        };
+        change(&mut undo, "adding a", &[], &tasks(&["a"]));
+        change(&mut undo, "adding b", &tasks(&["a"]), &tasks(&["a", "b"]));
+        assert!(matches!(undo.undo(&tasks(&["a", "b"])), Undone::Tasks(..)));
+        undo.flush().await.unwrap();
+
+        let mut loaded = Undo::default();
+        loaded
+            .replay(&std::fs::read_to_string(&path).unwrap())
+            .unwrap();
+        let labels: Vec<&str> = loaded.entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["adding a"]);
+        assert_eq!(loaded.records, 3);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
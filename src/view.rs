@@ -0,0 +1,67 @@
+use chrono::NaiveDate;
+
+use crate::{
+    i18n::t,
+    task_item::{Bucket, TaskItem},
+};
+
+/// Which tasks the list shows, before the file's settings and a search narrow it down
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ListView {
+    /// What can be worked on now; tasks scheduled to start later and the ones in a bucket
+    /// are left out
+    #[default]
+    Current,
+    /// The tasks in a bucket
+    Bucket(Bucket),
+    /// Every task, also the ones scheduled later
+    All,
+}
+
+impl ListView {
+    pub fn lists(self, task: &TaskItem, today: NaiveDate) -> bool {
+        match self {
+            ListView::Current => task.bucket().is_none() && !task.is_scheduled_later(today),
+            ListView::Bucket(bucket) => task.bucket() == Some(bucket),
+            ListView::All => true,
+        }
+    }
+
+    /// The view after this one, the switcher goes round
+    pub fn next(self) -> Self {
+        match self {
+            ListView::Current => ListView::Bucket(Bucket::Waiting),
+            ListView::Bucket(Bucket::Waiting) => ListView::Bucket(Bucket::Someday),
+            ListView::Bucket(Bucket::Someday) => ListView::All,
+            ListView::All => ListView::Current,
+        }
+    }
+
+    /// The bucket new tasks go to while the view is shown
+    pub fn bucket(self) -> Option<Bucket> {
+        match self {
+            ListView::Bucket(bucket) => Some(bucket),
+            ListView::Current | ListView::All => None,
+        }
+    }
+
+    /// The name the status line shows, none for the usual view
+    pub fn label(self) -> Option<String> {
+        match self {
+            ListView::Current => None,
+            ListView::Bucket(Bucket::Waiting) => Some(t("view-waiting")),
+            ListView::Bucket(Bucket::Someday) => Some(t("view-someday")),
+            ListView::All => Some(t("view-all")),
+        }
+    }
+
+    /// What accessible mode announces when the view is switched to
+    pub fn name(self) -> &'static str {
+        match self {
+            ListView::Current => "current tasks",
+            ListView::Bucket(Bucket::Waiting) => "waiting for",
+            ListView::Bucket(Bucket::Someday) => "someday",
+            ListView::All => "all tasks",
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Once,
+    time::Duration,
+};
+
+use tuitodo::{
+    config::Config,
+    hooks::{self, Hooks},
+    i18n, script,
+};
+
+const TASKS: &str = "- [ ] buy milk\n- [ ] call mom\n";
+
+/// A directory of its own for `name`, with the history and hooks of every test kept in
+/// `shared()`
+fn scratch(name: &str) -> PathBuf {
+    setup();
+    let dir = std::env::temp_dir().join(format!("tuitodo-script-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn shared() -> PathBuf {
+    std::env::temp_dir().join(format!("tuitodo-script-{}", std::process::id()))
+}
+
+fn setup() {
+    static SETUP: Once = Once::new();
+    SETUP.call_once(|| {
+        let shared = shared();
+        std::fs::create_dir_all(&shared).unwrap();
+        std::env::set_var("XDG_DATA_HOME", &shared);
+        // the expectations below are in English
+        i18n::init(Some("en"));
+        let touch = |name: &str| Some(format!("touch {}", shared.join(name).display()));
+        hooks::init(Hooks {
+            on_add: touch("added"),
+            on_complete: touch("completed"),
+            ..Hooks::default()
+        });
+    });
+}
+
+async fn replay(dir: &Path, lines: &str, dry_run: bool) -> color_eyre::Result<String> {
+    let file = dir.join("todo.md");
+    if !file.exists() {
+        std::fs::write(&file, TASKS).unwrap();
+    }
+    let path = dir.join("script");
+    std::fs::write(&path, lines).unwrap();
+
+    let file = file.to_str().unwrap();
+    script::run(path.to_str().unwrap(), file, &Config::default(), dry_run).await?;
+    Ok(std::fs::read_to_string(file).unwrap())
+}
+
+#[tokio::test]
+async fn saves_what_the_keys_did() {
+    let dir = scratch("saves");
+    let script = "\
+        expect count 2\n\
+        expect selected none\n\
+        key j j space\n\
+        expect state 2 done\n\
+        expect selected 2\n\
+        key enter\n\
+        type water plants\n\
+        key enter esc\n\
+        expect count 3\n\
+        expect task 3 water plants\n";
+
+    let written = replay(&dir, script, false).await.unwrap();
+    assert!(written.contains("- [ ] buy milk"));
+    assert!(written.contains("- [x] call mom"));
+    assert!(written.contains("water plants"));
+}
+
+#[tokio::test]
+async fn undoes_edits() {
+    let dir = scratch("undo");
+    let script = "\
+        key j space\n\
+        expect state 1 done\n\
+        key u\n\
+        expect state 1 open\n\
+        key u\n\
+        expect message nothing to undo\n";
+
+    let written = replay(&dir, script, false).await.unwrap();
+    assert!(written.contains("- [ ] buy milk"));
+}
+
+#[tokio::test]
+async fn fails_on_the_first_wrong_expectation() {
+    let dir = scratch("fails");
+    let error = replay(&dir, "key j space\nexpect state 1 open\n", false)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains(":2:"), "{}", error);
+    // nothing is saved after a failure
+    assert_eq!(std::fs::read_to_string(dir.join("todo.md")).unwrap(), TASKS);
+}
+
+#[tokio::test]
+async fn dry_run_leaves_everything_outside_alone() {
+    let dir = scratch("dry-run");
+    let script = "\
+        key enter\n\
+        type water plants\n\
+        key enter esc\n\
+        key j space\n\
+        key S\n\
+        expect message changes aren't saved here, so they aren't synced either\n";
+
+    let written = replay(&dir, script, true).await.unwrap();
+    assert_eq!(written, TASKS);
+
+    // hooks would have run in the background by now
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    for name in ["added", "completed"] {
+        assert!(!shared().join(name).exists(), "the {} hook ran", name);
+    }
+    assert!(!shared().join("tuitodo/history.jsonl").exists());
+}
+
+#[tokio::test]
+async fn waits_for_background_work() {
+    let dir = scratch("background");
+    // the history is read in the background before the stats open
+    let script = "\
+        key s\n\
+        expect mode stats\n\
+        key esc\n\
+        expect mode normal\n";
+
+    replay(&dir, script, true).await.unwrap();
+}